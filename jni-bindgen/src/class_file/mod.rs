@@ -0,0 +1,233 @@
+//! Class file attributes [jreflection] doesn't (yet?) expose.
+//!
+//! See the [class file format](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html) for details.
+
+mod mutf8;
+pub mod signature;
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io;
+
+/// Attributes of a single field.
+#[derive(Debug, Default, Clone)]
+pub struct FieldAttributes {
+    /// The generic type of the field from the `Signature` attribute, e.g. `"Ljava/util/List<Ljava/lang/String;>;"`.
+    pub signature: Option<String>,
+}
+
+/// Attributes of a single method.
+#[derive(Debug, Default, Clone)]
+pub struct MethodAttributes {
+    /// Classes listed by the `Exceptions` attribute, e.g. `"java/io/IOException"`.
+    pub exceptions: Vec<String>,
+
+    /// The generic signature of the method from the `Signature` attribute, e.g. `"<T:Ljava/lang/Object;>(TT;)V"`.
+    pub signature: Option<String>,
+}
+
+/// Attributes of a class and its members.
+#[derive(Debug, Default, Clone)]
+pub struct Attributes {
+    /// The generic signature of the class from the `Signature` attribute, e.g.
+    /// `"<E:Ljava/lang/Object;>Ljava/lang/Object;Ljava/util/Collection<TE;>;"`.
+    pub signature: Option<String>,
+
+    /// Field attributes, keyed by `"name\x1fdescriptor"`.
+    pub fields: HashMap<String, FieldAttributes>,
+
+    /// Method attributes, keyed by `"name\x1fdescriptor"`.
+    pub methods: HashMap<String, MethodAttributes>,
+}
+
+impl Attributes {
+    /// Read the attributes we care about from the bytes of a `.class` file.
+    pub fn read(class: &[u8]) -> io::Result<Self> {
+        let mut reader = Reader { data: class, off: 0 };
+        if reader.u32()? != 0xCAFE_BABE {
+            return io_data_err!("Not a class file (bad magic)");
+        }
+        reader.skip(4)?; // minor_version, major_version
+
+        let constants = ConstantPool::read(&mut reader)?;
+
+        reader.skip(6)?; // access_flags, this_class, super_class
+        let interfaces_count = reader.u16()? as usize;
+        reader.skip(2 * interfaces_count)?;
+
+        let mut result = Self::default();
+
+        let fields_count = reader.u16()?;
+        for _ in 0..fields_count {
+            reader.skip(2)?; // access_flags
+            let name = constants.utf8(reader.u16()?)?;
+            let descriptor = constants.utf8(reader.u16()?)?;
+            let mut field = FieldAttributes::default();
+            reader.attributes(&constants, |attribute_name, reader| {
+                if attribute_name == "Signature" {
+                    field.signature = Some(constants.utf8(reader.u16()?)?.to_owned());
+                }
+                Ok(())
+            })?;
+            result.fields.insert(format!("{}\x1f{}", name, descriptor), field);
+        }
+
+        let methods_count = reader.u16()?;
+        for _ in 0..methods_count {
+            reader.skip(2)?; // access_flags
+            let name = constants.utf8(reader.u16()?)?;
+            let descriptor = constants.utf8(reader.u16()?)?;
+            let mut method = MethodAttributes::default();
+            reader.attributes(&constants, |attribute_name, reader| {
+                match attribute_name {
+                    "Exceptions" => {
+                        let number_of_exceptions = reader.u16()?;
+                        for _ in 0..number_of_exceptions {
+                            method.exceptions.push(constants.class(reader.u16()?)?);
+                        }
+                    }
+                    "Signature" => method.signature = Some(constants.utf8(reader.u16()?)?.to_owned()),
+                    _ => {}
+                }
+                Ok(())
+            })?;
+            result.methods.insert(format!("{}\x1f{}", name, descriptor), method);
+        }
+
+        let mut signature = None;
+        reader.attributes(&constants, |attribute_name, reader| {
+            if attribute_name == "Signature" {
+                signature = Some(constants.utf8(reader.u16()?)?.to_owned());
+            }
+            Ok(())
+        })?;
+        result.signature = signature;
+
+        Ok(result)
+    }
+
+    /// Merge in the attributes of an older version of the same class, keeping ours where both have a member.
+    pub fn merge_older(&mut self, older: Attributes) {
+        if self.signature.is_none() {
+            self.signature = older.signature;
+        }
+        for (key, field) in older.fields {
+            self.fields.entry(key).or_insert(field);
+        }
+        for (key, method) in older.methods {
+            self.methods.entry(key).or_insert(method);
+        }
+    }
+}
+
+enum Constant {
+    Utf8(String),
+    Class(u16),
+    Other,
+}
+
+struct ConstantPool(Vec<Constant>);
+
+impl ConstantPool {
+    fn read(reader: &mut Reader) -> io::Result<Self> {
+        let count = reader.u16()? as usize;
+        let mut constants = Vec::with_capacity(count);
+        constants.push(Constant::Other); // constant_pool[0] is unused
+        while constants.len() < count {
+            match reader.u8()? {
+                1 => {
+                    let length = reader.u16()? as usize;
+                    constants.push(Constant::Utf8(mutf8::decode(reader.bytes(length)?)));
+                }
+                7 => constants.push(Constant::Class(reader.u16()?)),
+                tag => {
+                    let size = match tag {
+                        8 | 16 | 19 | 20 => 2, // String, MethodType, Module, Package
+                        15 => 3,               // MethodHandle
+                        3 | 4 | 9 | 10 | 11 | 12 | 17 | 18 => 4,
+                        5 | 6 => 8, // Long, Double
+                        other => return io_data_err!("Unknown constant pool tag {}", other),
+                    };
+                    reader.skip(size)?;
+                    constants.push(Constant::Other);
+                    if size == 8 {
+                        constants.push(Constant::Other); // 8 byte constants take up two entries
+                    }
+                }
+            }
+        }
+        Ok(Self(constants))
+    }
+
+    fn utf8(&self, index: u16) -> io::Result<&str> {
+        match self.0.get(index as usize) {
+            Some(Constant::Utf8(s)) => Ok(s.as_str()),
+            _ => io_data_err!("Constant pool entry {} isn't a CONSTANT_Utf8", index),
+        }
+    }
+
+    fn class(&self, index: u16) -> io::Result<String> {
+        match self.0.get(index as usize) {
+            Some(Constant::Class(name)) => Ok(self.utf8(*name)?.to_owned()),
+            _ => io_data_err!("Constant pool entry {} isn't a CONSTANT_Class", index),
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    off: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        match self.data.get(self.off..self.off + n) {
+            Some(bytes) => {
+                self.off += n;
+                Ok(bytes)
+            }
+            None => io_data_err!("Unexpected end of class file at offset {}", self.off),
+        }
+    }
+
+    fn skip(&mut self, n: usize) -> io::Result<()> {
+        self.bytes(n).map(|_| ())
+    }
+
+    fn seek(&mut self, off: usize) -> io::Result<()> {
+        if off > self.data.len() {
+            return io_data_err!("Attribute extends past the end of the class file");
+        }
+        self.off = off;
+        Ok(())
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    /// Calls `each` with the name of every attribute in an attributes table, and a reader positioned at its contents.
+    fn attributes(
+        &mut self,
+        constants: &ConstantPool,
+        mut each: impl FnMut(&str, &mut Self) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let attributes_count = self.u16()?;
+        for _ in 0..attributes_count {
+            let attribute_name = constants.utf8(self.u16()?)?;
+            let length = self.u32()? as usize;
+            let end = self.off + length;
+            each(attribute_name, self)?;
+            self.seek(end)?;
+        }
+        Ok(())
+    }
+}
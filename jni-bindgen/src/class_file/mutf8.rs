@@ -0,0 +1,39 @@
+/// Decode [Modified UTF-8](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.4.7), as used by class
+/// and dex files:  NULs are encoded as `C0 80`, and supplementary characters as two 3-byte encoded UTF-16 surrogates.
+/// Malformed input is replaced with `U+FFFD`.
+pub fn decode(bytes: &[u8]) -> String {
+    // Fast path:  plain ASCII (no NULs, which would be encoded as C0 80) is already valid UTF-8.
+    if bytes.is_ascii() {
+        return String::from_utf8(bytes.to_vec()).unwrap();
+    }
+
+    let mut utf16 = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let b0 = bytes[i] as u16;
+        let continuation = |n: usize| bytes.get(i + n).map(|&b| b as u16).filter(|b| b & 0xC0 == 0x80);
+        if b0 & 0x80 == 0 {
+            utf16.push(b0);
+            i += 1;
+        } else if let (0xC0, Some(b1)) = (b0 & 0xE0, continuation(1)) {
+            utf16.push(((b0 & 0x1F) << 6) | (b1 & 0x3F));
+            i += 2;
+        } else if let (0xE0, Some(b1), Some(b2)) = (b0 & 0xF0, continuation(1), continuation(2)) {
+            utf16.push(((b0 & 0x0F) << 12) | ((b1 & 0x3F) << 6) | (b2 & 0x3F));
+            i += 3;
+        } else {
+            utf16.push(0xFFFD);
+            i += 1;
+        }
+    }
+    String::from_utf16_lossy(&utf16[..])
+}
+
+#[test]
+fn decode_mutf8() {
+    assert_eq!(decode(b"java/lang/Object"), "java/lang/Object");
+    assert_eq!(decode(b"a\xC0\x80b"), "a\0b");
+    assert_eq!(decode("caf\u{E9}".as_bytes()), "caf\u{E9}");
+    assert_eq!(decode(b"\xED\xA0\xBD\xED\xB8\x80"), "\u{1F600}"); // surrogate pair
+    assert_eq!(decode(b"\xFF"), "\u{FFFD}");
+}
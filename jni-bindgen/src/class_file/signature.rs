@@ -0,0 +1,343 @@
+//! Parsing of generic [signatures](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.9.1), as
+//! found in `Signature` attributes.
+
+use std::io;
+
+/// A `JavaTypeSignature` (or `V`, for method return types.)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JavaType {
+    /// A primitive type (or void), as its descriptor character:  one of `BCDFIJSZV`.
+    Base(u8),
+    Class(ClassType),
+    /// A type variable such as `T`, by name.
+    TypeVariable(String),
+    Array(Box<JavaType>),
+}
+
+/// A (possibly parameterized) class type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassType {
+    /// The binary name of the class, e.g. `"java/util/Map$Entry"`.
+    pub path: String,
+
+    /// The type arguments of the innermost class, e.g. `<K, V>` for `Map<K, V>.Entry<K, V>`.  Arguments of outer
+    /// classes are dropped.
+    pub arguments: Vec<TypeArgument>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeArgument {
+    /// `?`
+    Any,
+    /// `? extends T`
+    Extends(JavaType),
+    /// `? super T`
+    Super(JavaType),
+    /// `T`
+    Exactly(JavaType),
+}
+
+/// A type parameter of a generic class or method, e.g. `T extends Comparable<T>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeParameter {
+    pub name: String,
+    /// The class bound (if any) followed by any interface bounds.
+    pub bounds: Vec<JavaType>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub super_class: ClassType,
+    pub interfaces: Vec<ClassType>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameters: Vec<JavaType>,
+    /// [JavaType::Base]\(b'V'\) for void methods.
+    pub return_type: JavaType,
+}
+
+impl ClassSignature {
+    pub fn parse(signature: &str) -> io::Result<Self> {
+        let mut parser = Parser::new(signature);
+        let type_parameters = parser.type_parameters()?;
+        let super_class = parser.class_type()?;
+        let mut interfaces = Vec::new();
+        while !parser.done() {
+            interfaces.push(parser.class_type()?);
+        }
+        Ok(Self {
+            type_parameters,
+            super_class,
+            interfaces,
+        })
+    }
+}
+
+impl MethodSignature {
+    pub fn parse(signature: &str) -> io::Result<Self> {
+        let mut parser = Parser::new(signature);
+        let type_parameters = parser.type_parameters()?;
+        parser.expect(b'(')?;
+        let mut parameters = Vec::new();
+        while parser.peek()? != b')' {
+            parameters.push(parser.java_type()?);
+        }
+        parser.expect(b')')?;
+        let return_type = parser.java_type()?;
+        // Skip ThrowsSignature s:  we get the (erased) exceptions from the Exceptions attribute instead.
+        Ok(Self {
+            type_parameters,
+            parameters,
+            return_type,
+        })
+    }
+}
+
+impl JavaType {
+    /// Parse a field's `Signature`.
+    pub fn parse_field(signature: &str) -> io::Result<Self> {
+        let mut parser = Parser::new(signature);
+        let result = parser.java_type()?;
+        if !parser.done() {
+            return parser.error("trailing characters");
+        }
+        Ok(result)
+    }
+}
+
+struct Parser<'a> {
+    signature: &'a str,
+    off: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(signature: &'a str) -> Self {
+        Self { signature, off: 0 }
+    }
+
+    fn error<T>(&self, what: &str) -> io::Result<T> {
+        io_data_err!(
+            "Invalid generic signature {:?}: {} at offset {}",
+            self.signature,
+            what,
+            self.off
+        )
+    }
+
+    fn done(&self) -> bool {
+        self.off >= self.signature.len()
+    }
+
+    fn peek(&self) -> io::Result<u8> {
+        match self.signature.as_bytes().get(self.off) {
+            Some(&b) => Ok(b),
+            None => self.error("unexpected end"),
+        }
+    }
+
+    fn next(&mut self) -> io::Result<u8> {
+        let b = self.peek()?;
+        self.off += 1;
+        Ok(b)
+    }
+
+    fn expect(&mut self, expected: u8) -> io::Result<()> {
+        if self.next()? != expected {
+            self.off -= 1;
+            return self.error(&format!("expected {:?}", expected as char));
+        }
+        Ok(())
+    }
+
+    fn identifier(&mut self) -> io::Result<&'a str> {
+        let start = self.off;
+        while !matches!(self.peek()?, b'.' | b';' | b'[' | b'/' | b'<' | b'>' | b':') {
+            self.off += 1;
+        }
+        if start == self.off {
+            return self.error("expected an identifier");
+        }
+        Ok(&self.signature[start..self.off])
+    }
+
+    fn type_parameters(&mut self) -> io::Result<Vec<TypeParameter>> {
+        let mut result = Vec::new();
+        if self.peek()? != b'<' {
+            return Ok(result);
+        }
+        self.off += 1;
+        while self.peek()? != b'>' {
+            let name = self.identifier()?.to_owned();
+            let mut bounds = Vec::new();
+            self.expect(b':')?;
+            if self.peek()? != b':' && self.peek()? != b'>' && !self.is_type_parameter_start()? {
+                bounds.push(self.java_type()?); // Class bound (optional)
+            }
+            while self.peek()? == b':' {
+                self.off += 1;
+                bounds.push(self.java_type()?); // Interface bounds
+            }
+            result.push(TypeParameter { name, bounds });
+        }
+        self.off += 1;
+        Ok(result)
+    }
+
+    /// Distinguishes the start of the next type parameter (`Identifier ':'`) from a class bound following an empty one.
+    fn is_type_parameter_start(&self) -> io::Result<bool> {
+        let rest = &self.signature.as_bytes()[self.off..];
+        if matches!(rest.first(), Some(b'L') | Some(b'T') | Some(b'[')) {
+            // Could be either "LFoo;" / "TT;" / "[I" ... or a type parameter named e.g. "L".  Type parameters are
+            // followed by ':' before any of the characters that would terminate a type signature.
+            for &b in rest {
+                match b {
+                    b':' => return Ok(true),
+                    b';' | b'<' | b'/' | b'[' | b'.' | b'>' => return Ok(false),
+                    _ => {}
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    fn java_type(&mut self) -> io::Result<JavaType> {
+        match self.peek()? {
+            b'L' => Ok(JavaType::Class(self.class_type()?)),
+            b'T' => {
+                self.off += 1;
+                let name = self.identifier()?.to_owned();
+                self.expect(b';')?;
+                Ok(JavaType::TypeVariable(name))
+            }
+            b'[' => {
+                self.off += 1;
+                Ok(JavaType::Array(Box::new(self.java_type()?)))
+            }
+            b @ (b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' | b'V') => {
+                self.off += 1;
+                Ok(JavaType::Base(b))
+            }
+            _ => self.error("expected a type"),
+        }
+    }
+
+    fn class_type(&mut self) -> io::Result<ClassType> {
+        self.expect(b'L')?;
+        let mut path = String::new();
+        let mut arguments;
+        loop {
+            path.push_str(self.identifier()?);
+            if self.peek()? == b'/' {
+                self.off += 1;
+                path.push('/');
+                continue;
+            }
+            arguments = self.type_arguments()?;
+            match self.next()? {
+                b';' => break,
+                b'.' => path.push('$'), // Inner class
+                _ => {
+                    self.off -= 1;
+                    return self.error("expected ';' or '.'");
+                }
+            }
+        }
+        Ok(ClassType { path, arguments })
+    }
+
+    fn type_arguments(&mut self) -> io::Result<Vec<TypeArgument>> {
+        let mut result = Vec::new();
+        if self.peek()? != b'<' {
+            return Ok(result);
+        }
+        self.off += 1;
+        while self.peek()? != b'>' {
+            result.push(match self.peek()? {
+                b'*' => {
+                    self.off += 1;
+                    TypeArgument::Any
+                }
+                b'+' => {
+                    self.off += 1;
+                    TypeArgument::Extends(self.java_type()?)
+                }
+                b'-' => {
+                    self.off += 1;
+                    TypeArgument::Super(self.java_type()?)
+                }
+                _ => TypeArgument::Exactly(self.java_type()?),
+            });
+        }
+        self.off += 1;
+        Ok(result)
+    }
+}
+
+#[test]
+fn parse_signatures() {
+    let class = |path: &str, arguments: Vec<TypeArgument>| ClassType {
+        path: path.to_owned(),
+        arguments,
+    };
+    let var = |name: &str| JavaType::TypeVariable(name.to_owned());
+    let object = || JavaType::Class(class("java/lang/Object", vec![]));
+
+    let list = ClassSignature::parse("<E:Ljava/lang/Object;>Ljava/lang/Object;Ljava/util/Collection<TE;>;").unwrap();
+    assert_eq!(
+        list.type_parameters,
+        vec![TypeParameter {
+            name: "E".to_owned(),
+            bounds: vec![object()]
+        }]
+    );
+    assert_eq!(list.super_class, class("java/lang/Object", vec![]));
+    assert_eq!(
+        list.interfaces,
+        vec![class("java/util/Collection", vec![TypeArgument::Exactly(var("E"))])]
+    );
+
+    // Interface-only bound, T extends Comparable<T>
+    let comparable = ClassSignature::parse("<T::Ljava/lang/Comparable<TT;>;>Ljava/lang/Object;").unwrap();
+    assert_eq!(
+        comparable.type_parameters[0].bounds,
+        vec![JavaType::Class(class(
+            "java/lang/Comparable",
+            vec![TypeArgument::Exactly(var("T"))]
+        ))]
+    );
+
+    let method = MethodSignature::parse(
+        "<U:Ljava/lang/Number;>(Ljava/util/List<-TU;>;Ljava/util/Map<Ljava/lang/String;[TU;>;[I)TU;^Ljava/io/IOException;",
+    )
+    .unwrap();
+    assert_eq!(method.type_parameters[0].name, "U");
+    assert_eq!(
+        method.parameters,
+        vec![
+            JavaType::Class(class("java/util/List", vec![TypeArgument::Super(var("U"))])),
+            JavaType::Class(class(
+                "java/util/Map",
+                vec![
+                    TypeArgument::Exactly(JavaType::Class(class("java/lang/String", vec![]))),
+                    TypeArgument::Exactly(JavaType::Array(Box::new(var("U")))),
+                ]
+            )),
+            JavaType::Array(Box::new(JavaType::Base(b'I'))),
+        ]
+    );
+    assert_eq!(method.return_type, var("U"));
+
+    assert_eq!(
+        JavaType::parse_field("Ljava/util/Map<TK;TV;>.Entry<TK;*>;").unwrap(),
+        JavaType::Class(class(
+            "java/util/Map$Entry",
+            vec![TypeArgument::Exactly(var("K")), TypeArgument::Any]
+        ))
+    );
+
+    assert!(JavaType::parse_field("Ljava/util/List<TE;>").is_err());
+}
@@ -2,6 +2,7 @@
 
 use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
+use std::io;
 use std::path::{Path, PathBuf};
 
 use crate::config::toml;
@@ -41,6 +42,8 @@ pub struct Config {
     pub(crate) codegen: toml::CodeGen,
     pub(crate) doc_patterns: Vec<DocPattern>,
     pub(crate) input_files: Vec<PathBuf>,
+    pub(crate) classpath_files: Vec<PathBuf>,
+    pub(crate) android_api_levels: Option<Vec<u32>>,
     pub(crate) output_path: PathBuf,
     pub(crate) logging_verbose: bool,
 
@@ -55,6 +58,11 @@ pub struct Config {
     pub(crate) rename_class_fields: HashMap<String, String>,
     pub(crate) rename_class_methods: HashMap<String, String>,
     pub(crate) rename_class_method_sigs: HashMap<String, String>,
+
+    pub(crate) externs: Vec<toml::Extern>,
+
+    pub(crate) native_classes: HashSet<String>,
+    pub(crate) native_exports: HashMap<String, String>,
 }
 
 impl From<toml::FileWithContext> for Config {
@@ -125,6 +133,13 @@ impl From<toml::FileWithContext> for Config {
                 .into_iter()
                 .map(|file| resolve_file(file, &dir))
                 .collect(),
+            classpath_files: file
+                .input
+                .classpath
+                .into_iter()
+                .map(|file| resolve_file(file, &dir))
+                .collect(),
+            android_api_levels: None,
             output_path,
             logging_verbose: logging.verbose,
             include_classes,
@@ -136,10 +151,84 @@ impl From<toml::FileWithContext> for Config {
             rename_class_fields,
             rename_class_methods,
             rename_class_method_sigs,
+            externs: file.externs,
+            native_classes: file.natives.iter().map(|native| native.class.clone()).collect(),
+            native_exports: file
+                .natives
+                .into_iter()
+                .filter_map(|native| Some((native.class, native.export?)))
+                .collect(),
         }
     }
 }
 
+impl Config {
+    /// Generate bindings for multiple Android API levels, gated by `api-level-N` features.  `levels` is a comma separated
+    /// list of levels or inclusive ranges of levels, such as `"7-28"` or `"21,23,26-28"`.
+    pub fn set_android_api_levels(&mut self, levels: &str) -> io::Result<()> {
+        let mut result = Vec::new();
+        for part in levels.split(',') {
+            let part = part.trim();
+            let parse = |level: &str| {
+                level
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|_| io_data_error!("Invalid Android API level {:?} in {:?}", level, levels))
+            };
+            if let Some(dash) = part.find('-') {
+                let (first, last) = (parse(&part[..dash])?, parse(&part[dash + 1..])?);
+                if first > last {
+                    return io_data_err!("Invalid Android API level range {:?} in {:?}", part, levels);
+                }
+                result.extend(first..=last);
+            } else {
+                result.push(parse(part)?);
+            }
+        }
+        result.sort_unstable();
+        result.dedup();
+
+        if !self
+            .input_files
+            .iter()
+            .chain(self.classpath_files.iter())
+            .any(|file| file.to_string_lossy().contains(API_LEVEL_PLACEHOLDER))
+        {
+            return io_data_err!(
+                "Generating for multiple Android API levels requires an [input] file path containing {:?}",
+                API_LEVEL_PLACEHOLDER
+            );
+        }
+
+        self.android_api_levels = Some(result);
+        Ok(())
+    }
+
+    /// The input files to scan for a given Android API level, with [API_LEVEL_PLACEHOLDER] expanded.
+    pub(crate) fn input_files_for_api_level(&self, level: u32) -> Vec<PathBuf> {
+        expand_api_level(&self.input_files, level)
+    }
+
+    /// The classpath files to scan for a given Android API level, with [API_LEVEL_PLACEHOLDER] expanded.
+    pub(crate) fn classpath_files_for_api_level(&self, level: u32) -> Vec<PathBuf> {
+        expand_api_level(&self.classpath_files, level)
+    }
+}
+
+fn expand_api_level(files: &[PathBuf], level: u32) -> Vec<PathBuf> {
+    files
+        .iter()
+        .map(|file| match file.to_str() {
+            Some(path) => PathBuf::from(path.replace(API_LEVEL_PLACEHOLDER, &level.to_string())),
+            None => file.clone(),
+        })
+        .collect()
+}
+
+/// Replaced with each Android API level in \[input\] file paths, e.g.
+/// `"%ANDROID_HOME%/platforms/android-{ANDROID_API_LEVEL}/android.jar"`.
+pub(crate) const API_LEVEL_PLACEHOLDER: &str = "{ANDROID_API_LEVEL}";
+
 fn resolve_file(path: PathBuf, dir: &Path) -> PathBuf {
     let path: PathBuf = match path.into_os_string().into_string() {
         Ok(string) => OsString::from(expand_vars(string)),
@@ -8,10 +8,6 @@ use serde_derive::Deserialize;
 use crate::identifiers::{FieldManglingStyle, MethodManglingStyle};
 
 /// How should the JNIEnv be passed around.
-///
-/// In the future, I plan to support an "Implicit" mode for platforms like Android - where there's at most one JVM at a
-/// time - and said JVM lives for the lifetime of the application - so we can save some boilerplate for end users by
-/// hiding it's use as an implementation detail.
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 #[derive(Default)]
@@ -20,6 +16,12 @@ pub enum StaticEnvStyle {
     #[default]
     Explicit,
 
+    /// Static methods, constructors, and static field accessors take no env parameter, instead using the VM registered
+    /// with `jni_glue::VM::set_ambient` (attaching the current thread if needed.)  Meant for platforms like Android -
+    /// where there's at most one JVM at a time, living for the lifetime of the application.  As there's no env for a
+    /// local reference to be tied to, these return objects and exceptions as `jni_glue::Global`s.
+    Implicit,
+
     #[doc(hidden)]
     __NonExhaustive,
 }
@@ -33,6 +35,10 @@ pub enum CodeShardingStyle {
     None,
 
     /// Split up autogenerated code into a series of .rs files, one per Java class.
+    ///
+    /// The output path becomes the root of the tree, containing the preamble and top level `mod` declarations.  Every
+    /// Rust module gets a `mod.rs`, which `include!`s one `.rs` file per class, placed in the directory Rust expects
+    /// submodules of the output path to live in.
     #[default]
     PerClass,
 
@@ -40,6 +46,41 @@ pub enum CodeShardingStyle {
     __NonExhaustive,
 }
 
+/// What error type generated methods should return when the Java code throws.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum CheckedExceptionStyle {
+    /// Every method returns `Result<T, Local<'env, java::lang::Throwable>>`, whatever it declares it `throws`.
+    #[default]
+    Throwable,
+
+    /// Methods declaring checked exceptions (via `throws`) return a per-method `{Class}_{method}_Error<'env>` enum
+    /// instead, with one variant per declared exception class, and an `Other` variant for everything else (unchecked
+    /// exceptions, or exceptions the method didn't declare.)  Methods without a `throws` clause are unaffected.
+    Typed,
+
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+/// What generated methods and field accessors should do when the class or member they bind is missing at runtime.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum MissingMemberStyle {
+    /// Panic.  Fine when the bindings were generated against what the app will run against (or older.)
+    #[default]
+    Panic,
+
+    /// Return `Result<..., jni_glue::MissingMember>` - wrapping the usual return type - so the caller can check what's
+    /// available at runtime:  e.g. when binding a newer Android API level than some devices have.
+    Result,
+
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
 fn default_true() -> bool {
     true
 }
@@ -57,6 +98,10 @@ pub struct CodeGen {
     #[serde(default = "Default::default")]
     pub static_env: StaticEnvStyle,
 
+    /// How generated code should be split up between files.
+    #[serde(default = "Default::default")]
+    pub code_sharding: CodeShardingStyle,
+
     /// How methods should be named by default.
     #[serde(default = "default_method_naming_style")]
     pub method_naming_style: MethodManglingStyle,
@@ -69,19 +114,42 @@ pub struct CodeGen {
     #[serde(default = "Default::default")]
     pub field_naming_style: FieldManglingStyle,
 
+    /// What error type methods should return.
+    #[serde(default = "Default::default")]
+    pub checked_exceptions: CheckedExceptionStyle,
+
     /// Should not-emitted methods/fields still generate their code commented out?
     #[serde(default = "default_true")]
     pub keep_rejected_emits: bool,
+
+    /// Should methods and fields look up their class and method/field ID once, keeping them in a static for later calls
+    /// (with the class kept alive by a global reference), instead of on every call?
+    #[serde(default = "default_true")]
+    pub cache_lookups: bool,
+
+    /// What methods and fields should do if missing at runtime.
+    #[serde(default = "Default::default")]
+    pub missing_members: MissingMemberStyle,
+
+    /// Should a `verify_bindings(env)` function be generated, which looks up every class, method, and field the
+    /// bindings use in the running VM and reports any that are missing?
+    #[serde(default)]
+    pub verify_bindings: bool,
 }
 
 impl Default for CodeGen {
     fn default() -> Self {
         Self {
             static_env: Default::default(),
+            code_sharding: Default::default(),
             method_naming_style: default_method_naming_style(),
             method_naming_style_collision: default_method_naming_style_collision(),
             field_naming_style: Default::default(),
+            checked_exceptions: Default::default(),
             keep_rejected_emits: true,
+            cache_lookups: true,
+            missing_members: Default::default(),
+            verify_bindings: false,
         }
     }
 }
@@ -192,16 +260,27 @@ pub struct Documentation {
 /// The \[input\] section.
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct Input {
-    /// `.jar` or `.class` files to scan for JVM class info.
+    /// `.jar`, `.aar`, `.class`, `.dex`, or `.apk` files to scan for JVM class info.  `.aar`s are read in place:  their
+    /// `classes.jar` and any `libs/*.jar` are scanned without needing to be extracted first.  `.apk`s have all of their
+    /// `classes*.dex` files scanned.
     ///
-    /// May in the future add support for `.apk`s, `.aab`s, etc.
+    /// When generating with `--android-api-levels`, `{ANDROID_API_LEVEL}` is replaced with each API level in turn, e.g.
+    /// `"%ANDROID_HOME%/platforms/android-{ANDROID_API_LEVEL}/android.jar"`.
+    ///
+    /// May in the future add support for `.aab`s, etc.
     pub files: Vec<PathBuf>,
+
+    /// Files to scan for classes which are *not* emitted, but are still used to resolve argument, return, field,
+    /// `extends`, and `implements` types.  Accepts the same formats as `files`.  These classes only have a Rust type if
+    /// an \[[extern\]] binds them - members referring to any others are treated as if the class were missing.
+    #[serde(default = "Default::default")]
+    pub classpath: Vec<PathBuf>,
 }
 
 /// The \[output\] section.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Output {
-    /// Target `.rs` file to generate.
+    /// Target `.rs` file to generate.  With `code_sharding = "per_class"`, this is the root of the generated tree.
     pub path: PathBuf,
 }
 
@@ -231,6 +310,31 @@ pub struct Rename {
     pub signature: Option<String>,
 }
 
+/// An \[[extern\]] section.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Extern {
+    /// Java classes with this prefix (e.g. `"android/"`) are bound by another crate.
+    pub jni_prefix: String,
+
+    /// The Rust path of the crate or module the other bindings were generated into (e.g. `"jni_android_sys"`.)  Classes
+    /// under `jni_prefix` are referred to by their full module path within it, e.g. `jni_android_sys::android::app::Activity`.
+    pub rust_path: String,
+}
+
+/// A \[[native\]] section.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Native {
+    /// A class (e.g. `"com/example/MainActivity"`) whose `native` methods are implemented in Rust.  Generates a
+    /// `{Class}Natives` trait to implement them with, and `{Class}::register_natives` to bind an implementation.
+    pub class: String,
+
+    /// The Rust path (e.g. `"crate::natives::MainActivity"`) of a type implementing `{Class}Natives`, as an alternative
+    /// to `register_natives`:  generates `#[no_mangle]` `Java_...` functions forwarding to it, for the JVM to find when
+    /// the library is loaded with `System.loadLibrary`.  Must resolve from within the generated bindings.
+    #[serde(default)]
+    pub export: Option<String>,
+}
+
 /// Format for a `jni-bindgen.toml` file or in-memory settings.
 ///
 /// # Example File
@@ -243,8 +347,13 @@ pub struct Rename {
 ///
 /// [codegen]
 /// static_env                      = "implicit"
+/// code_sharding                   = "per_class"
 /// method_naming_style             = "java"
 /// method_naming_style_collision   = "rustify_long_signature"
+/// checked_exceptions              = "typed"
+/// cache_lookups                   = true
+/// missing_members                 = "result"
+/// verify_bindings                 = true
 ///
 /// [logging]
 /// verbose = true
@@ -266,6 +375,9 @@ pub struct Rename {
 /// files = [
 ///     "%LOCALAPPDATA%/Android/Sdk/platforms/android-28/android.jar"
 /// ]
+/// classpath = [
+///     "%LOCALAPPDATA%/Android/Sdk/platforms/android-28/optional/org.apache.http.legacy.jar"
+/// ]
 ///
 /// [output]
 /// path = "android28.rs"
@@ -300,6 +412,18 @@ pub struct Rename {
 /// method    = "someOtherMethod"
 /// signature = "()V"
 /// to        = "some_other_method"
+///
+///
+///
+/// [[extern]]
+/// jni_prefix = "android/"
+/// rust_path  = "jni_android_sys"
+///
+///
+///
+/// [[native]]
+/// class = "com/example/MainActivity"
+/// export = "crate::natives::MainActivity"
 /// ```
 #[derive(Debug, Clone, Deserialize)]
 pub struct File {
@@ -334,6 +458,16 @@ pub struct File {
     #[serde(rename = "rename")]
     #[serde(default = "Vec::new")]
     pub renames: Vec<Rename>,
+
+    /// Java packages bound by other crates.
+    #[serde(rename = "extern")]
+    #[serde(default = "Vec::new")]
+    pub externs: Vec<Extern>,
+
+    /// Classes whose `native` methods are implemented in Rust.
+    #[serde(rename = "native")]
+    #[serde(default = "Vec::new")]
+    pub natives: Vec<Native>,
 }
 
 impl File {
@@ -393,8 +527,13 @@ fn load_well_configured_toml() {
 
         [codegen]
         static_env                      = "explicit"
+        code_sharding                   = "none"
         method_naming_style             = "java"
         method_naming_style_collision   = "rustify_long_signature"
+        checked_exceptions              = "typed"
+        cache_lookups                   = false
+        missing_members                 = "result"
+        verify_bindings                 = true
 
         [logging]
         verbose = true
@@ -415,6 +554,9 @@ fn load_well_configured_toml() {
         files = [
             "%LOCALAPPDATA%/Android/Sdk/platforms/android-28/android.jar"
         ]
+        classpath = [
+            "%LOCALAPPDATA%/Android/Sdk/platforms/android-28/optional/org.apache.http.legacy.jar"
+        ]
 
         [output]
         path = "android28.rs"
@@ -449,15 +591,32 @@ fn load_well_configured_toml() {
         method    = "someOtherMethod"
         signature = "()V"
         to        = "some_other_method"
+
+
+
+        [[extern]]
+        jni_prefix = "android/"
+        rust_path  = "jni_android_sys"
+
+
+
+        [[native]]
+        class = "com/example/MainActivity"
+        export = "crate::natives::MainActivity"
     "#;
     let file = File::read_str(well_configured_toml).unwrap();
 
     assert_eq!(file.codegen.static_env, StaticEnvStyle::Explicit);
+    assert_eq!(file.codegen.code_sharding, CodeShardingStyle::None);
     assert_eq!(file.codegen.method_naming_style, MethodManglingStyle::Java);
     assert_eq!(
         file.codegen.method_naming_style_collision,
         MethodManglingStyle::RustifyLongSignature
     );
+    assert_eq!(file.codegen.checked_exceptions, CheckedExceptionStyle::Typed);
+    assert!(!file.codegen.cache_lookups);
+    assert_eq!(file.codegen.missing_members, MissingMemberStyle::Result);
+    assert!(file.codegen.verify_bindings);
 
     assert!(file.logging.verbose);
 
@@ -489,6 +648,12 @@ fn load_well_configured_toml() {
         file.input.files,
         &[Path::new("%LOCALAPPDATA%/Android/Sdk/platforms/android-28/android.jar")]
     );
+    assert_eq!(
+        file.input.classpath,
+        &[Path::new(
+            "%LOCALAPPDATA%/Android/Sdk/platforms/android-28/optional/org.apache.http.legacy.jar"
+        )]
+    );
     assert_eq!(file.output.path, Path::new("android28.rs"));
 
     assert_eq!(file.ignores.len(), 3);
@@ -521,6 +686,14 @@ fn load_well_configured_toml() {
     assert_eq!(file.renames[2].method, Some("someOtherMethod".to_owned()));
     assert_eq!(file.renames[2].signature, Some("()V".to_owned()));
     assert_eq!(file.renames[2].to, "some_other_method");
+
+    assert_eq!(file.externs.len(), 1);
+    assert_eq!(file.externs[0].jni_prefix, "android/");
+    assert_eq!(file.externs[0].rust_path, "jni_android_sys");
+
+    assert_eq!(file.natives.len(), 1);
+    assert_eq!(file.natives[0].class, "com/example/MainActivity");
+    assert_eq!(file.natives[0].export.as_deref(), Some("crate::natives::MainActivity"));
 }
 
 #[test]
@@ -535,11 +708,16 @@ fn load_minimal_toml() {
     let file = File::read_str(minimal_toml).unwrap();
 
     assert_eq!(file.codegen.static_env, StaticEnvStyle::Explicit);
+    assert_eq!(file.codegen.code_sharding, CodeShardingStyle::PerClass);
     assert_eq!(file.codegen.method_naming_style, MethodManglingStyle::Rustify);
     assert_eq!(
         file.codegen.method_naming_style_collision,
         MethodManglingStyle::RustifyShortSignature
     );
+    assert_eq!(file.codegen.checked_exceptions, CheckedExceptionStyle::Throwable);
+    assert!(file.codegen.cache_lookups);
+    assert_eq!(file.codegen.missing_members, MissingMemberStyle::Panic);
+    assert!(!file.codegen.verify_bindings);
 
     assert!(!file.logging.verbose);
     assert_eq!(file.documentation.patterns.len(), 0);
@@ -547,9 +725,12 @@ fn load_minimal_toml() {
         file.input.files,
         &[Path::new("%LOCALAPPDATA%/Android/Sdk/platforms/android-28/android.jar")]
     );
+    assert_eq!(file.input.classpath.len(), 0);
     assert_eq!(file.output.path, Path::new("android28.rs"));
     assert_eq!(file.ignores.len(), 0);
     assert_eq!(file.renames.len(), 0);
+    assert_eq!(file.externs.len(), 0);
+    assert_eq!(file.natives.len(), 0);
 }
 
 /// A [File] + context (directory path continaing the [File]).
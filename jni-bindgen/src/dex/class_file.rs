@@ -0,0 +1,157 @@
+//! Re-encodes dex class definitions as minimal (code-less) class files.
+
+use std::collections::HashMap;
+
+use super::reader::{ClassDef, Constant, MemberDef};
+
+const MAGIC: u32 = 0xCAFE_BABE;
+const MAJOR_VERSION: u16 = 52; // Java 8
+
+const CONSTANT_UTF8: u8 = 1;
+const CONSTANT_INTEGER: u8 = 3;
+const CONSTANT_FLOAT: u8 = 4;
+const CONSTANT_LONG: u8 = 5;
+const CONSTANT_DOUBLE: u8 = 6;
+const CONSTANT_CLASS: u8 = 7;
+const CONSTANT_STRING: u8 = 8;
+
+pub(crate) fn write(class: &ClassDef) -> Vec<u8> {
+    let mut pool = ConstantPool::default();
+
+    let this_class = pool.class(class.name);
+    let super_class = class.super_name.map(|name| pool.class(name)).unwrap_or(0);
+    let interfaces: Vec<u16> = class.interfaces.iter().map(|name| pool.class(name)).collect();
+    let fields: Vec<Vec<u8>> = class.fields.iter().map(|field| member(&mut pool, field)).collect();
+    let methods: Vec<Vec<u8>> = class.methods.iter().map(|method| member(&mut pool, method)).collect();
+    let mut attributes = Vec::new();
+    if class.deprecated {
+        attributes.push((pool.utf8(b"Deprecated"), Vec::new()));
+    }
+    if let Some(signature) = class.signature.as_ref() {
+        attributes.push((pool.utf8(b"Signature"), pool.utf8(signature).to_be_bytes().to_vec()));
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&MAGIC.to_be_bytes());
+    out.extend_from_slice(&0u16.to_be_bytes()); // minor_version
+    out.extend_from_slice(&MAJOR_VERSION.to_be_bytes());
+    out.extend_from_slice(&pool.next_index.to_be_bytes());
+    out.extend_from_slice(&pool.bytes);
+    out.extend_from_slice(&class.access_flags.to_be_bytes());
+    out.extend_from_slice(&this_class.to_be_bytes());
+    out.extend_from_slice(&super_class.to_be_bytes());
+    out.extend_from_slice(&(interfaces.len() as u16).to_be_bytes());
+    for interface in interfaces {
+        out.extend_from_slice(&interface.to_be_bytes());
+    }
+    for members in [fields, methods] {
+        out.extend_from_slice(&(members.len() as u16).to_be_bytes());
+        for member in members {
+            out.extend_from_slice(&member);
+        }
+    }
+    write_attributes(&mut out, attributes);
+    out
+}
+
+fn member(pool: &mut ConstantPool, member: &MemberDef) -> Vec<u8> {
+    let name = pool.utf8(member.name);
+    let descriptor = pool.utf8(&member.descriptor);
+    let mut attributes = Vec::new();
+    if let Some(constant) = member.constant.as_ref() {
+        let value = pool.constant(constant);
+        attributes.push((pool.utf8(b"ConstantValue"), value.to_be_bytes().to_vec()));
+    }
+    if member.deprecated {
+        attributes.push((pool.utf8(b"Deprecated"), Vec::new()));
+    }
+    if !member.exceptions.is_empty() {
+        let mut exceptions = (member.exceptions.len() as u16).to_be_bytes().to_vec();
+        for name in &member.exceptions {
+            exceptions.extend_from_slice(&pool.class(name).to_be_bytes());
+        }
+        attributes.push((pool.utf8(b"Exceptions"), exceptions));
+    }
+    if let Some(signature) = member.signature.as_ref() {
+        attributes.push((pool.utf8(b"Signature"), pool.utf8(signature).to_be_bytes().to_vec()));
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&member.access_flags.to_be_bytes());
+    out.extend_from_slice(&name.to_be_bytes());
+    out.extend_from_slice(&descriptor.to_be_bytes());
+    write_attributes(&mut out, attributes);
+    out
+}
+
+/// Writes an attributes table from (attribute_name_index, info) pairs.
+fn write_attributes(out: &mut Vec<u8>, attributes: Vec<(u16, Vec<u8>)>) {
+    out.extend_from_slice(&(attributes.len() as u16).to_be_bytes());
+    for (name, info) in attributes {
+        out.extend_from_slice(&name.to_be_bytes());
+        out.extend_from_slice(&(info.len() as u32).to_be_bytes());
+        out.extend_from_slice(&info);
+    }
+}
+
+struct ConstantPool {
+    bytes: Vec<u8>,
+    next_index: u16,
+    utf8s: HashMap<Vec<u8>, u16>,
+    classes: HashMap<u16, u16>,
+}
+
+impl Default for ConstantPool {
+    fn default() -> Self {
+        Self {
+            bytes: Vec::new(),
+            next_index: 1, // constant_pool[0] is unused
+            utf8s: HashMap::new(),
+            classes: HashMap::new(),
+        }
+    }
+}
+
+impl ConstantPool {
+    fn push(&mut self, tag: u8, data: &[u8], slots: u16) -> u16 {
+        let index = self.next_index;
+        self.bytes.push(tag);
+        self.bytes.extend_from_slice(data);
+        self.next_index += slots;
+        index
+    }
+
+    fn utf8(&mut self, modified_utf8: &[u8]) -> u16 {
+        if let Some(&index) = self.utf8s.get(modified_utf8) {
+            return index;
+        }
+        let mut data = (modified_utf8.len() as u16).to_be_bytes().to_vec();
+        data.extend_from_slice(modified_utf8);
+        let index = self.push(CONSTANT_UTF8, &data, 1);
+        self.utf8s.insert(modified_utf8.to_vec(), index);
+        index
+    }
+
+    fn class(&mut self, name: &[u8]) -> u16 {
+        let name = self.utf8(name);
+        if let Some(&index) = self.classes.get(&name) {
+            return index;
+        }
+        let index = self.push(CONSTANT_CLASS, &name.to_be_bytes(), 1);
+        self.classes.insert(name, index);
+        index
+    }
+
+    fn constant(&mut self, constant: &Constant) -> u16 {
+        match *constant {
+            Constant::Integer(value) => self.push(CONSTANT_INTEGER, &value.to_be_bytes(), 1),
+            Constant::Float(bits) => self.push(CONSTANT_FLOAT, &bits.to_be_bytes(), 1),
+            Constant::Long(value) => self.push(CONSTANT_LONG, &value.to_be_bytes(), 2), // 8 byte constants take 2 slots
+            Constant::Double(bits) => self.push(CONSTANT_DOUBLE, &bits.to_be_bytes(), 2),
+            Constant::String(modified_utf8) => {
+                let string = self.utf8(modified_utf8);
+                self.push(CONSTANT_STRING, &string.to_be_bytes(), 1)
+            }
+        }
+    }
+}
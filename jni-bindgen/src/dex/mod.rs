@@ -0,0 +1,40 @@
+//! [DEX](https://source.android.com/devices/tech/dalvik/dex-format) parsing, for classes that only ship as `.dex`s or
+//! `.apk`s.
+//!
+//! Class definitions are re-encoded as code-less `.class` files - with dex annotations such as `@Throws` converted back
+//! into the class file attributes they came from - so dex classes end up with exactly the same model as classes read
+//! from `.jar`s.
+
+mod class_file;
+mod reader;
+
+use std::io;
+
+use reader::Dex;
+#[cfg(test)]
+pub(crate) use {class_file::write as write_class_file, reader::ClassDef, reader::MemberDef};
+
+/// Re-encode every class defined in a `.dex` file as `.class` file bytes.
+pub fn class_files(dex: &[u8]) -> io::Result<Vec<Vec<u8>>> {
+    let dex = Dex::new(dex)?;
+    (0..dex.class_defs_size())
+        .map(|i| Ok(class_file::write(&dex.class_def(i)?)))
+        .collect()
+}
+
+/// Is `name` (a path within an `.apk`) one of the - possibly multidex - `classes.dex`, `classes2.dex`, ... files?
+pub fn is_apk_classes_dex(name: &str) -> bool {
+    name.strip_prefix("classes")
+        .and_then(|rest| rest.strip_suffix(".dex"))
+        .is_some_and(|n| n.is_empty() || n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+#[test]
+fn apk_classes_dex() {
+    assert!(is_apk_classes_dex("classes.dex"));
+    assert!(is_apk_classes_dex("classes2.dex"));
+    assert!(is_apk_classes_dex("classes13.dex"));
+    assert!(!is_apk_classes_dex("lib/classes.dex"));
+    assert!(!is_apk_classes_dex("classes.jar"));
+    assert!(!is_apk_classes_dex("classesX.dex"));
+}
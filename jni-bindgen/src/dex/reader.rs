@@ -0,0 +1,744 @@
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io;
+
+const NO_INDEX: u32 = 0xFFFF_FFFF;
+
+// Dex-only access flags, which have no class file equivalent.
+const ACC_DEX_ONLY: u32 = 0xFFFF_0000;
+
+const ACC_STATIC: u32 = 0x0008;
+const ACC_FINAL: u32 = 0x0010;
+
+/// A class definition, with everything a class file needs for binding purposes.
+pub(crate) struct ClassDef<'a> {
+    pub access_flags: u16,
+    pub name: &'a [u8],
+    pub super_name: Option<&'a [u8]>,
+    pub interfaces: Vec<&'a [u8]>,
+    pub fields: Vec<MemberDef<'a>>,
+    pub methods: Vec<MemberDef<'a>>,
+    pub deprecated: bool,
+    /// Generic signature, e.g. `<E:Ljava/lang/Object;>Ljava/lang/Object;`.
+    pub signature: Option<Vec<u8>>,
+}
+
+/// A field or method definition.  Names and descriptors are Modified UTF-8, just as in class files.
+pub(crate) struct MemberDef<'a> {
+    pub access_flags: u16,
+    pub name: &'a [u8],
+    pub descriptor: Vec<u8>,
+    pub constant: Option<Constant<'a>>,
+    pub deprecated: bool,
+    /// Checked exceptions a method declares, e.g. `java/io/IOException`.
+    pub exceptions: Vec<&'a [u8]>,
+    /// Generic signature, e.g. `TE;` or `(TE;)Z`.
+    pub signature: Option<Vec<u8>>,
+}
+
+/// A static final field's value, already converted to the class file `ConstantValue` type matching its descriptor.
+pub(crate) enum Constant<'a> {
+    Integer(i32),
+    Long(i64),
+    Float(u32),
+    Double(u64),
+    String(&'a [u8]),
+}
+
+/// An encoded_value we care about.
+enum Value<'a> {
+    Integral(i64),
+    Float(u32),
+    Double(u64),
+    String(&'a [u8]),
+    Type(&'a [u8]),
+    Array(Vec<Value<'a>>),
+    Other,
+}
+
+impl Value<'_> {
+    /// Is this the value a field has if never initialized?  Null is lumped in with [Value::Other].  `-0.0` isn't.
+    fn is_default(&self) -> bool {
+        matches!(
+            self,
+            Value::Integral(0) | Value::Float(0) | Value::Double(0) | Value::Other
+        )
+    }
+}
+
+pub(crate) struct Dex<'a> {
+    data: &'a [u8],
+    string_ids_off: u32,
+    string_ids_size: u32,
+    type_ids_off: u32,
+    type_ids_size: u32,
+    proto_ids_off: u32,
+    proto_ids_size: u32,
+    field_ids_off: u32,
+    field_ids_size: u32,
+    method_ids_off: u32,
+    method_ids_size: u32,
+    class_defs_off: u32,
+    class_defs_size: u32,
+}
+
+impl<'a> Dex<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> io::Result<Self> {
+        if data.len() < 0x70 || &data[0..4] != b"dex\n" || data[7] != 0 {
+            return io_data_err!("Not a dex file (bad magic)");
+        }
+        if u32_at(data, 0x28)? != 0x1234_5678 {
+            return io_data_err!("Unsupported dex file (big endian or corrupt endian_tag)");
+        }
+
+        Ok(Self {
+            data,
+            string_ids_size: u32_at(data, 0x38)?,
+            string_ids_off: u32_at(data, 0x3C)?,
+            type_ids_size: u32_at(data, 0x40)?,
+            type_ids_off: u32_at(data, 0x44)?,
+            proto_ids_size: u32_at(data, 0x48)?,
+            proto_ids_off: u32_at(data, 0x4C)?,
+            field_ids_size: u32_at(data, 0x50)?,
+            field_ids_off: u32_at(data, 0x54)?,
+            method_ids_size: u32_at(data, 0x58)?,
+            method_ids_off: u32_at(data, 0x5C)?,
+            class_defs_size: u32_at(data, 0x60)?,
+            class_defs_off: u32_at(data, 0x64)?,
+        })
+    }
+
+    pub(crate) fn class_defs_size(&self) -> u32 {
+        self.class_defs_size
+    }
+
+    pub(crate) fn class_def(&self, index: u32) -> io::Result<ClassDef<'a>> {
+        let data = self.data;
+        let off = self.class_defs_off as usize + 32 * index as usize;
+        let class_idx = u32_at(data, off)?;
+        let access_flags = u32_at(data, off + 4)?;
+        let superclass_idx = u32_at(data, off + 8)?;
+        let interfaces_off = u32_at(data, off + 12)?;
+        let annotations_off = u32_at(data, off + 20)?;
+        let class_data_off = u32_at(data, off + 24)?;
+        let static_values_off = u32_at(data, off + 28)?;
+
+        let annotations = Annotations::new(self, annotations_off)?;
+
+        let mut class = ClassDef {
+            access_flags: (access_flags & !ACC_DEX_ONLY) as u16,
+            name: self.class_name(class_idx)?,
+            super_name: if superclass_idx == NO_INDEX {
+                None
+            } else {
+                Some(self.class_name(superclass_idx)?)
+            },
+            interfaces: self
+                .type_list(interfaces_off)?
+                .into_iter()
+                .map(|idx| self.class_name(idx))
+                .collect::<io::Result<_>>()?,
+            fields: Vec::new(),
+            methods: Vec::new(),
+            deprecated: annotations.class.deprecated,
+            signature: annotations.class.signature.clone(),
+        };
+
+        if class_data_off == 0 {
+            return Ok(class); // e.g. marker interfaces
+        }
+
+        let mut off = class_data_off as usize;
+        let static_fields_size = uleb128(data, &mut off)?;
+        let instance_fields_size = uleb128(data, &mut off)?;
+        let direct_methods_size = uleb128(data, &mut off)?;
+        let virtual_methods_size = uleb128(data, &mut off)?;
+
+        // Fields come first, but whether their static_values can be trusted depends on the methods - see below.
+        let mut static_values = self.static_values(static_values_off)?.into_iter();
+        let mut fields = Vec::new();
+        for (size, is_static) in [(static_fields_size, true), (instance_fields_size, false)] {
+            let mut field_idx = 0u32;
+            for _ in 0..size {
+                field_idx = field_idx
+                    .checked_add(uleb128(data, &mut off)?)
+                    .ok_or_else(|| io_data_error!("field_idx overflowed at offset {}", off))?;
+                let access_flags = uleb128(data, &mut off)?;
+                let value = if is_static { static_values.next() } else { None };
+                fields.push((field_idx, access_flags, value));
+            }
+        }
+
+        for size in [direct_methods_size, virtual_methods_size] {
+            let mut method_idx = 0u32;
+            for _ in 0..size {
+                method_idx = method_idx
+                    .checked_add(uleb128(data, &mut off)?)
+                    .ok_or_else(|| io_data_error!("method_idx overflowed at offset {}", off))?;
+                let access_flags = uleb128(data, &mut off)?;
+                let _code_off = uleb128(data, &mut off)?;
+                class.methods.push(self.method(method_idx, access_flags, &annotations)?);
+            }
+        }
+
+        // static_values is positional, so fields initialized by <clinit> get a default value if a later field has an
+        // initial value.  Such a default is indistinguishable from an actual `= 0` / `= null`, so only trust defaults
+        // if there's no <clinit> that could overwrite them.
+        let has_clinit = class.methods.iter().any(|m| m.name == b"<clinit>");
+        for (field_idx, access_flags, value) in fields {
+            let value = value.filter(|v| !has_clinit || !v.is_default());
+            class
+                .fields
+                .push(self.field(field_idx, access_flags, value, &annotations)?);
+        }
+
+        Ok(class)
+    }
+
+    fn field(
+        &self,
+        field_idx: u32,
+        access_flags: u32,
+        value: Option<Value<'a>>,
+        annotations: &Annotations<'a>,
+    ) -> io::Result<MemberDef<'a>> {
+        if field_idx >= self.field_ids_size {
+            return io_data_err!("field_idx {} out of bounds", field_idx);
+        }
+        let off = self.field_ids_off as usize + 8 * field_idx as usize;
+        let type_idx = u16_at(self.data, off + 2)? as u32;
+        let name_idx = u32_at(self.data, off + 4)?;
+        let descriptor = self.type_descriptor(type_idx)?;
+
+        // Dex has no ConstantValue attribute - static_values also initializes non-final statics - so only treat
+        // initialized static finals of constant types as constants.  `value` is only passed if it's trustworthy.
+        let is_constant = access_flags & (ACC_STATIC | ACC_FINAL) == (ACC_STATIC | ACC_FINAL);
+        let constant = match (is_constant, descriptor, value) {
+            (false, _, _) => None,
+            (true, b"Z", Some(Value::Integral(v)))
+            | (true, b"B", Some(Value::Integral(v)))
+            | (true, b"C", Some(Value::Integral(v)))
+            | (true, b"S", Some(Value::Integral(v)))
+            | (true, b"I", Some(Value::Integral(v))) => Some(Constant::Integer(v as i32)),
+            (true, b"J", Some(Value::Integral(v))) => Some(Constant::Long(v)),
+            (true, b"F", Some(Value::Float(v))) => Some(Constant::Float(v)),
+            (true, b"D", Some(Value::Double(v))) => Some(Constant::Double(v)),
+            (true, b"Ljava/lang/String;", Some(Value::String(v))) => Some(Constant::String(v)),
+            _ => None,
+        };
+
+        Ok(MemberDef {
+            access_flags: (access_flags & !ACC_DEX_ONLY) as u16,
+            name: self.string(name_idx)?,
+            descriptor: descriptor.to_vec(),
+            constant,
+            deprecated: annotations.fields.get(&field_idx).is_some_and(|a| a.deprecated),
+            exceptions: Vec::new(),
+            signature: annotations.fields.get(&field_idx).and_then(|a| a.signature.clone()),
+        })
+    }
+
+    fn method(&self, method_idx: u32, access_flags: u32, annotations: &Annotations<'a>) -> io::Result<MemberDef<'a>> {
+        if method_idx >= self.method_ids_size {
+            return io_data_err!("method_idx {} out of bounds", method_idx);
+        }
+        let off = self.method_ids_off as usize + 8 * method_idx as usize;
+        let proto_idx = u16_at(self.data, off + 2)? as u32;
+        let name_idx = u32_at(self.data, off + 4)?;
+
+        let annotations = annotations.methods.get(&method_idx);
+        Ok(MemberDef {
+            access_flags: (access_flags & !ACC_DEX_ONLY) as u16,
+            name: self.string(name_idx)?,
+            descriptor: self.proto_descriptor(proto_idx)?,
+            constant: None,
+            deprecated: annotations.is_some_and(|a| a.deprecated),
+            exceptions: annotations.map(|a| a.throws.clone()).unwrap_or_default(),
+            signature: annotations.and_then(|a| a.signature.clone()),
+        })
+    }
+
+    /// `(Ljava/lang/String;I)V` style method descriptor for a proto_id_item.
+    fn proto_descriptor(&self, proto_idx: u32) -> io::Result<Vec<u8>> {
+        if proto_idx >= self.proto_ids_size {
+            return io_data_err!("proto_idx {} out of bounds", proto_idx);
+        }
+        let off = self.proto_ids_off as usize + 12 * proto_idx as usize;
+        let return_type_idx = u32_at(self.data, off + 4)?;
+        let parameters_off = u32_at(self.data, off + 8)?;
+
+        let mut descriptor = vec![b'('];
+        for param in self.type_list(parameters_off)? {
+            descriptor.extend_from_slice(self.type_descriptor(param)?);
+        }
+        descriptor.push(b')');
+        descriptor.extend_from_slice(self.type_descriptor(return_type_idx)?);
+        Ok(descriptor)
+    }
+
+    fn type_list(&self, off: u32) -> io::Result<Vec<u32>> {
+        if off == 0 {
+            return Ok(Vec::new());
+        }
+        let off = off as usize;
+        let size = u32_at(self.data, off)? as usize;
+        (0..size)
+            .map(|i| Ok(u16_at(self.data, off + 4 + 2 * i)? as u32))
+            .collect()
+    }
+
+    /// `Lcom/example/Foo;` style type descriptor.
+    fn type_descriptor(&self, type_idx: u32) -> io::Result<&'a [u8]> {
+        if type_idx >= self.type_ids_size {
+            return io_data_err!("type_idx {} out of bounds", type_idx);
+        }
+        self.string(u32_at(self.data, self.type_ids_off as usize + 4 * type_idx as usize)?)
+    }
+
+    /// `com/example/Foo` style class name.
+    fn class_name(&self, type_idx: u32) -> io::Result<&'a [u8]> {
+        strip_class_descriptor(self.type_descriptor(type_idx)?)
+    }
+
+    /// Modified UTF-8 string data, without the trailing `\0`.
+    fn string(&self, string_idx: u32) -> io::Result<&'a [u8]> {
+        if string_idx >= self.string_ids_size {
+            return io_data_err!("string_idx {} out of bounds", string_idx);
+        }
+        let mut off = u32_at(self.data, self.string_ids_off as usize + 4 * string_idx as usize)? as usize;
+        let _utf16_size = uleb128(self.data, &mut off)?;
+        let rest = self.data.get(off..).unwrap_or_default();
+        match rest.iter().position(|&b| b == 0) {
+            Some(len) => Ok(&rest[..len]),
+            None => io_data_err!("Unterminated string_data_item at offset {}", off),
+        }
+    }
+
+    fn static_values(&self, off: u32) -> io::Result<Vec<Value<'a>>> {
+        if off == 0 {
+            return Ok(Vec::new());
+        }
+        let mut off = off as usize;
+        let size = uleb128(self.data, &mut off)?;
+        (0..size).map(|_| self.encoded_value(&mut off)).collect()
+    }
+
+    fn encoded_value(&self, off: &mut usize) -> io::Result<Value<'a>> {
+        let header = u8_at(self.data, *off)?;
+        *off += 1;
+        let value_arg = (header >> 5) as usize;
+        let value_type = header & 0x1F;
+
+        let mut bytes = || -> io::Result<&'a [u8]> {
+            let start = *off;
+            *off += value_arg + 1;
+            match self.data.get(start..*off) {
+                Some(bytes) => Ok(bytes),
+                None => io_data_err!("encoded_value at offset {} out of bounds", start),
+            }
+        };
+
+        Ok(match value_type {
+            0x00 | 0x02 | 0x04 | 0x06 => Value::Integral(sign_extend(bytes()?)), // byte, short, int, long
+            0x03 => Value::Integral(zero_extend(bytes()?) as i64),               // char
+            0x10 => Value::Float(zero_extend_right(bytes()?, 4) as u32),
+            0x11 => Value::Double(zero_extend_right(bytes()?, 8)),
+            0x17 => Value::String(self.string(zero_extend(bytes()?) as u32)?),
+            0x18 => Value::Type(self.type_descriptor(zero_extend(bytes()?) as u32)?),
+            0x1C => {
+                // encoded_array
+                let size = uleb128(self.data, off)?;
+                Value::Array((0..size).map(|_| self.encoded_value(off)).collect::<io::Result<_>>()?)
+            }
+            0x1D => {
+                // encoded_annotation
+                let _type_idx = uleb128(self.data, off)?;
+                let size = uleb128(self.data, off)?;
+                for _ in 0..size {
+                    let _name_idx = uleb128(self.data, off)?;
+                    self.encoded_value(off)?;
+                }
+                Value::Other
+            }
+            0x1E => Value::Other,                      // null
+            0x1F => Value::Integral(value_arg as i64), // boolean
+            0x15..=0x1B => {
+                bytes()?; // method_type, method_handle, field, method, enum
+                Value::Other
+            }
+            other => return io_data_err!("Unknown encoded_value type 0x{:02x}", other),
+        })
+    }
+}
+
+/// The annotations of a class or member that map back to class file attributes.
+#[derive(Default)]
+struct MemberAnnotations<'a> {
+    /// `@java.lang.Deprecated`
+    deprecated: bool,
+
+    /// `@dalvik.annotation.Throws`, dex's equivalent of the `Exceptions` attribute.
+    throws: Vec<&'a [u8]>,
+
+    /// `@dalvik.annotation.Signature`, dex's equivalent of the `Signature` attribute.
+    signature: Option<Vec<u8>>,
+}
+
+#[derive(Default)]
+struct Annotations<'a> {
+    class: MemberAnnotations<'a>,
+    fields: HashMap<u32, MemberAnnotations<'a>>,
+    methods: HashMap<u32, MemberAnnotations<'a>>,
+}
+
+impl<'a> Annotations<'a> {
+    fn new(dex: &Dex<'a>, annotations_directory_off: u32) -> io::Result<Self> {
+        let mut result = Self::default();
+        if annotations_directory_off == 0 {
+            return Ok(result);
+        }
+
+        let data = dex.data;
+        let off = annotations_directory_off as usize;
+        result.class = Self::read_set(dex, u32_at(data, off)?)?;
+        let fields_size = u32_at(data, off + 4)? as usize;
+        let methods_size = u32_at(data, off + 8)? as usize;
+
+        let fields_off = off + 16;
+        for i in 0..fields_size {
+            let idx = u32_at(data, fields_off + 8 * i)?;
+            let set = Self::read_set(dex, u32_at(data, fields_off + 8 * i + 4)?)?;
+            result.fields.insert(idx, set);
+        }
+
+        let methods_off = fields_off + 8 * fields_size;
+        for i in 0..methods_size {
+            let idx = u32_at(data, methods_off + 8 * i)?;
+            let set = Self::read_set(dex, u32_at(data, methods_off + 8 * i + 4)?)?;
+            result.methods.insert(idx, set);
+        }
+
+        Ok(result)
+    }
+
+    fn read_set(dex: &Dex<'a>, annotation_set_off: u32) -> io::Result<MemberAnnotations<'a>> {
+        let mut result = MemberAnnotations::default();
+        if annotation_set_off == 0 {
+            return Ok(result);
+        }
+        let off = annotation_set_off as usize;
+        let size = u32_at(dex.data, off)? as usize;
+        for i in 0..size {
+            let mut off = u32_at(dex.data, off + 4 + 4 * i)? as usize + 1; // skip visibility
+            let type_idx = uleb128(dex.data, &mut off)?;
+            match dex.type_descriptor(type_idx)? {
+                b"Ljava/lang/Deprecated;" => result.deprecated = true,
+                b"Ldalvik/annotation/Throws;" => {
+                    for value in Self::read_values(dex, &mut off)? {
+                        if let Value::Type(descriptor) = value {
+                            result.throws.push(strip_class_descriptor(descriptor)?);
+                        }
+                    }
+                }
+                b"Ldalvik/annotation/Signature;" => {
+                    // Split up into an array of strings, presumably so the pieces can be shared between signatures.
+                    let mut signature = Vec::new();
+                    for value in Self::read_values(dex, &mut off)? {
+                        if let Value::String(piece) = value {
+                            signature.extend_from_slice(piece);
+                        }
+                    }
+                    result.signature = Some(signature);
+                }
+                _ => {}
+            }
+        }
+        Ok(result)
+    }
+
+    /// The `value` array of an encoded_annotation's elements.
+    fn read_values(dex: &Dex<'a>, off: &mut usize) -> io::Result<Vec<Value<'a>>> {
+        let mut result = Vec::new();
+        let elements = uleb128(dex.data, off)?;
+        for _ in 0..elements {
+            let name_idx = uleb128(dex.data, off)?;
+            let value = dex.encoded_value(off)?;
+            if let (b"value", Value::Array(values)) = (dex.string(name_idx)?, value) {
+                result = values;
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// `Lcom/example/Foo;` => `com/example/Foo`
+fn strip_class_descriptor(descriptor: &[u8]) -> io::Result<&[u8]> {
+    if descriptor.len() < 3 || descriptor[0] != b'L' || descriptor[descriptor.len() - 1] != b';' {
+        return io_data_err!(
+            "Expected a class type descriptor, got {:?}",
+            String::from_utf8_lossy(descriptor)
+        );
+    }
+    Ok(&descriptor[1..descriptor.len() - 1])
+}
+
+fn u8_at(data: &[u8], off: usize) -> io::Result<u8> {
+    data.get(off)
+        .cloned()
+        .ok_or_else(|| io_data_error!("Offset {} out of bounds", off))
+}
+
+fn u16_at(data: &[u8], off: usize) -> io::Result<u16> {
+    match data.get(off..off + 2) {
+        Some(bytes) => Ok(u16::from_le_bytes(bytes.try_into().unwrap())),
+        None => io_data_err!("Offset {} out of bounds", off),
+    }
+}
+
+fn u32_at(data: &[u8], off: usize) -> io::Result<u32> {
+    match data.get(off..off + 4) {
+        Some(bytes) => Ok(u32::from_le_bytes(bytes.try_into().unwrap())),
+        None => io_data_err!("Offset {} out of bounds", off),
+    }
+}
+
+fn uleb128(data: &[u8], off: &mut usize) -> io::Result<u32> {
+    let mut result = 0u32;
+    for shift in (0..35).step_by(7) {
+        let byte = u8_at(data, *off)?;
+        *off += 1;
+        result |= ((byte & 0x7F) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+    }
+    io_data_err!("uleb128 at offset {} is longer than 5 bytes", *off)
+}
+
+fn zero_extend(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u64)
+}
+
+fn sign_extend(bytes: &[u8]) -> i64 {
+    let shift = 64 - 8 * bytes.len();
+    ((zero_extend(bytes) << shift) as i64) >> shift
+}
+
+/// Floating point encoded_values drop their low order zero bytes.
+fn zero_extend_right(bytes: &[u8], width: usize) -> u64 {
+    zero_extend(bytes) << (8 * (width - bytes.len()))
+}
+
+#[test]
+fn leb128() {
+    let data = [0x00, 0x01, 0x7F, 0x80, 0x7F, 0xB4, 0x07, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F];
+    let mut off = 0;
+    assert_eq!(uleb128(&data, &mut off).unwrap(), 0);
+    assert_eq!(uleb128(&data, &mut off).unwrap(), 1);
+    assert_eq!(uleb128(&data, &mut off).unwrap(), 127);
+    assert_eq!(uleb128(&data, &mut off).unwrap(), 16256);
+    assert_eq!(uleb128(&data, &mut off).unwrap(), 948);
+    assert_eq!(uleb128(&data, &mut off).unwrap(), 0xFFFF_FFFF);
+    assert_eq!(off, data.len());
+    assert!(uleb128(&data, &mut off).is_err());
+}
+
+#[test]
+fn encoded_value_extension() {
+    assert_eq!(sign_extend(&[0xFF]), -1);
+    assert_eq!(sign_extend(&[0x80, 0x00]), 0x80);
+    assert_eq!(sign_extend(&[0x00, 0x80]), -32768);
+    assert_eq!(zero_extend(&[0xFF, 0xFF]), 0xFFFF);
+    assert_eq!(zero_extend_right(&[0x80, 0x3F], 4), 0x3F80_0000); // 1.0f32
+}
+
+/// A dex file defining just `com/example/Consts`:
+///
+/// ```java
+/// public class Consts {
+///     public static final int A = <0, or computed by <clinit> if with_clinit>;
+///     public static final int B = 5;
+///     public static final String C = "hi";
+///     public static int D = 7;
+///     public int e;
+///     public Consts() {}
+///     public int get() { ... }
+/// }
+/// ```
+///
+/// `field_idx_diffs` overrides the class_data's field_idx_diffs, to make for invalid indices.
+#[cfg(test)]
+fn test_dex(with_clinit: bool, field_idx_diffs: Option<[u32; 5]>) -> Vec<u8> {
+    fn uleb(out: &mut Vec<u8>, mut value: u32) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+    fn put(out: &mut [u8], off: usize, value: u32) {
+        out[off..off + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    let strings: [&[u8]; 14] = [
+        b"Lcom/example/Consts;",
+        b"Ljava/lang/Object;",
+        b"I",
+        b"Ljava/lang/String;",
+        b"V",
+        b"A",
+        b"B",
+        b"C",
+        b"D",
+        b"e",
+        b"<clinit>",
+        b"<init>",
+        b"get",
+        b"hi",
+    ];
+    let types: [u32; 5] = [0, 1, 2, 3, 4]; // Consts, Object, I, String, V
+    let protos: [(u32, u32); 2] = [(4, 4), (2, 2)]; // (shorty_idx, return_type_idx):  ()V, ()I
+    let fields: [(u16, u16, u32); 5] = [(0, 2, 5), (0, 2, 6), (0, 3, 7), (0, 2, 8), (0, 2, 9)];
+    let methods: [(u16, u16, u32); 3] = [(0, 0, 10), (0, 0, 11), (0, 1, 12)];
+
+    let mut dex = vec![0u8; 0x70];
+    dex[0..8].copy_from_slice(b"dex\n035\0");
+    put(&mut dex, 0x28, 0x1234_5678);
+
+    let section = |dex: &mut Vec<u8>, header_off: usize, count: usize, item_size: usize| {
+        let off = dex.len();
+        put(dex, header_off, count as u32);
+        put(dex, header_off + 4, off as u32);
+        dex.resize(off + count * item_size, 0);
+        off
+    };
+    let string_ids = section(&mut dex, 0x38, strings.len(), 4);
+    let type_ids = section(&mut dex, 0x40, types.len(), 4);
+    let proto_ids = section(&mut dex, 0x48, protos.len(), 12);
+    let field_ids = section(&mut dex, 0x50, fields.len(), 8);
+    let method_ids = section(&mut dex, 0x58, methods.len(), 8);
+    let class_defs = section(&mut dex, 0x60, 1, 32);
+
+    for (i, string) in strings.iter().enumerate() {
+        let off = dex.len() as u32;
+        put(&mut dex, string_ids + 4 * i, off);
+        uleb(&mut dex, string.len() as u32); // ASCII, so utf16_size is the length
+        dex.extend_from_slice(string);
+        dex.push(0);
+    }
+    for (i, &string_idx) in types.iter().enumerate() {
+        put(&mut dex, type_ids + 4 * i, string_idx);
+    }
+    for (i, &(shorty_idx, return_type_idx)) in protos.iter().enumerate() {
+        put(&mut dex, proto_ids + 12 * i, shorty_idx);
+        put(&mut dex, proto_ids + 12 * i + 4, return_type_idx); // parameters_off stays 0
+    }
+    for (i, &(class_idx, type_idx, name_idx)) in fields.iter().enumerate() {
+        dex[field_ids + 8 * i..][..2].copy_from_slice(&class_idx.to_le_bytes());
+        dex[field_ids + 8 * i + 2..][..2].copy_from_slice(&type_idx.to_le_bytes());
+        put(&mut dex, field_ids + 8 * i + 4, name_idx);
+    }
+    for (i, &(class_idx, proto_idx, name_idx)) in methods.iter().enumerate() {
+        dex[method_ids + 8 * i..][..2].copy_from_slice(&class_idx.to_le_bytes());
+        dex[method_ids + 8 * i + 2..][..2].copy_from_slice(&proto_idx.to_le_bytes());
+        put(&mut dex, method_ids + 8 * i + 4, name_idx);
+    }
+
+    let class_data = dex.len() as u32;
+    let direct_methods: &[(u32, u32)] = if with_clinit {
+        &[(0, 0x1_0008), (1, 0x1_0001)] // static constructor, public constructor
+    } else {
+        &[(1, 0x1_0001)]
+    };
+    for size in [4, 1, direct_methods.len() as u32, 1] {
+        uleb(&mut dex, size);
+    }
+    let field_flags = [0x19, 0x19, 0x19, 0x09, 0x01]; // public static final x3, public static, public
+    let field_idx_diffs = field_idx_diffs.unwrap_or([0, 1, 1, 1, 4]); // e restarts the instance field list
+    for (diff, flags) in field_idx_diffs.iter().zip(field_flags.iter()) {
+        uleb(&mut dex, *diff);
+        uleb(&mut dex, *flags);
+    }
+    let mut prev = 0;
+    for &(method_idx, flags) in direct_methods {
+        uleb(&mut dex, method_idx - prev);
+        uleb(&mut dex, flags);
+        uleb(&mut dex, 0); // code_off
+        prev = method_idx;
+    }
+    for value in [2, 0x01, 0] {
+        uleb(&mut dex, value); // get, public, code_off
+    }
+
+    let static_values = dex.len() as u32;
+    uleb(&mut dex, 4);
+    dex.extend_from_slice(&[0x04, 0]); // A: int 0
+    dex.extend_from_slice(&[0x04, 5]); // B: int 5
+    dex.extend_from_slice(&[0x17, 13]); // C: string "hi"
+    dex.extend_from_slice(&[0x04, 7]); // D: int 7
+
+    put(&mut dex, class_defs, 0); // class_idx
+    put(&mut dex, class_defs + 4, 0x0001); // public
+    put(&mut dex, class_defs + 8, 1); // superclass_idx
+    put(&mut dex, class_defs + 16, NO_INDEX); // source_file_idx
+    put(&mut dex, class_defs + 24, class_data);
+    put(&mut dex, class_defs + 28, static_values);
+    dex
+}
+
+#[test]
+fn round_trip() {
+    use jreflection::{field::Constant, Class};
+
+    for with_clinit in [true, false] {
+        let class_files = super::class_files(&test_dex(with_clinit, None)).unwrap();
+        assert_eq!(class_files.len(), 1);
+        let class = Class::read(&mut &class_files[0][..]).unwrap();
+
+        assert_eq!(class.path.as_str(), "com/example/Consts");
+        assert_eq!(class.super_path.as_ref().map(|s| s.as_str()), Some("java/lang/Object"));
+        assert!(class.is_public());
+
+        let fields: Vec<_> = class
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.descriptor_str(), f.flags.bits(), f.constant.clone()))
+            .collect();
+        // With a <clinit>, A's 0 might just be filler for B, C, and D's values to follow, so it's not a constant.
+        let a = if with_clinit { None } else { Some(Constant::Integer(0)) };
+        assert_eq!(
+            fields,
+            [
+                ("A", "I", 0x19, a),
+                ("B", "I", 0x19, Some(Constant::Integer(5))),
+                (
+                    "C",
+                    "Ljava/lang/String;",
+                    0x19,
+                    Some(Constant::String(Some("hi".to_string())))
+                ),
+                ("D", "I", 0x09, None), // Not final
+                ("e", "I", 0x01, None),
+            ]
+        );
+
+        let methods: Vec<_> = class
+            .methods
+            .iter()
+            .map(|m| (m.name.as_str(), m.descriptor_str(), m.flags.bits()))
+            .collect();
+        let mut expected = vec![("<init>", "()V", 0x01), ("get", "()I", 0x01)];
+        if with_clinit {
+            expected.insert(0, ("<clinit>", "()V", 0x08)); // Dex-only ACC_CONSTRUCTOR stripped
+        }
+        assert_eq!(methods, expected);
+    }
+}
+
+#[test]
+fn field_idx_overflow() {
+    let dex = test_dex(true, Some([0, 1, 0xFFFF_FFFF, 1, 4]));
+    let error = super::class_files(&dex).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert!(error.to_string().starts_with("field_idx overflowed"), "{}", error);
+}
@@ -0,0 +1,93 @@
+use std::collections::BTreeSet;
+
+use crate::emit_rust::Context;
+
+/// The set of Android API levels a class, method, or field was found in, when generating with `--android-api-levels`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct ApiLevels(BTreeSet<u32>);
+
+impl ApiLevels {
+    pub(crate) fn insert(&mut self, level: u32) {
+        self.0.insert(level);
+    }
+
+    pub(crate) fn extend(&mut self, other: &ApiLevels) {
+        self.0.extend(other.0.iter().cloned());
+    }
+
+    pub(crate) fn contains(&self, level: u32) -> bool {
+        self.0.contains(&level)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.0.iter().cloned()
+    }
+
+    /// Is this available at every level `other` is?
+    pub(crate) fn covers(&self, other: &ApiLevels) -> bool {
+        other.0.is_subset(&self.0)
+    }
+
+    /// A `#[cfg(...)] ` attribute (with trailing space) gating an item to these API levels, or an empty string if we're
+    /// not generating for multiple API levels.
+    pub(crate) fn attribute(levels: Option<&ApiLevels>, context: &Context) -> String {
+        match (levels, context.config.android_api_levels.as_ref()) {
+            (Some(levels), Some(all)) => format!("#[cfg({})] ", levels.cfg(&all[..])),
+            _ => String::new(),
+        }
+    }
+
+    /// Expresses these levels in terms of cumulative `api-level-N` features:  enabling `api-level-N` is expected to also
+    /// enable every lower `api-level-*` feature, so an item is gated on the level it first appeared at, and - if it was
+    /// later removed - `not(...)` the level it was removed at.
+    fn cfg(&self, all: &[u32]) -> String {
+        let mut ranges = Vec::new();
+        let mut start = None;
+        for &level in all {
+            match (start, self.0.contains(&level)) {
+                (None, true) => start = Some(level),
+                (Some(first), false) => {
+                    ranges.push(format!(
+                        "all(feature = \"api-level-{}\", not(feature = \"api-level-{}\"))",
+                        first, level
+                    ));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(first) = start {
+            ranges.push(format!("feature = \"api-level-{}\"", first));
+        }
+
+        if ranges.len() == 1 {
+            ranges.pop().unwrap()
+        } else {
+            format!("any({})", ranges.join(", "))
+        }
+    }
+}
+
+#[test]
+fn api_levels_cfg() {
+    let all = [7, 8, 9, 10, 11];
+    let levels = |ls: &[u32]| {
+        let mut result = ApiLevels::default();
+        for &l in ls {
+            result.insert(l);
+        }
+        result
+    };
+
+    assert_eq!(levels(&[7, 8, 9, 10, 11]).cfg(&all), "feature = \"api-level-7\"");
+    assert_eq!(levels(&[9, 10, 11]).cfg(&all), "feature = \"api-level-9\"");
+    assert_eq!(
+        levels(&[7, 8]).cfg(&all),
+        "all(feature = \"api-level-7\", not(feature = \"api-level-9\"))"
+    );
+    assert_eq!(
+        levels(&[8, 10, 11]).cfg(&all),
+        "any(all(feature = \"api-level-8\", not(feature = \"api-level-9\")), feature = \"api-level-10\")"
+    );
+    assert_eq!(levels(&[]).cfg(&all), "any()");
+}
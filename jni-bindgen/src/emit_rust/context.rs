@@ -1,20 +1,40 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::io;
+use std::path::Path;
 use std::sync::Mutex;
 use std::time::Duration;
 
 use jreflection::class;
 
+use super::api_levels::ApiLevels;
+use super::generics::substitute;
 use super::modules::Module;
 use super::preamble::write_preamble;
-use super::structs::Struct;
-use crate::{config, util};
+use super::structs::{Struct, StructPaths};
+use crate::class_file::signature::{ClassSignature, ClassType, JavaType, TypeArgument};
+use crate::{class_file, config, util};
 
 pub struct Context<'a> {
     pub(crate) config: &'a config::runtime::Config,
     pub(crate) module: Module,
     pub(crate) all_classes: HashSet<String>,
+    /// The classes bindings are being emitted for - [Context::all_classes] less those only on the classpath.
+    pub(crate) emitted_classes: HashSet<String>,
+    pub(crate) class_api_levels: HashMap<String, ApiLevels>,
+    /// How many generic parameters each emitted class has, if any.
+    pub(crate) class_type_parameters: HashMap<String, usize>,
+    /// The superclass of every gathered class, for ordering checked exceptions most-derived first.
+    pub(crate) class_supers: HashMap<String, String>,
+    /// The interfaces every gathered class declares it implements (or extends, for interfaces.)
+    pub(crate) class_interfaces: HashMap<String, Vec<String>>,
+    /// The generic signature of every gathered class that has one, for the type arguments of inherited interfaces.
+    pub(crate) class_signatures: HashMap<String, ClassSignature>,
+    /// The API levels every gathered class extends or implements each of its direct supertypes at, if generating for
+    /// multiple API levels.
+    pub(crate) supertype_api_levels: HashMap<String, HashMap<String, ApiLevels>>,
+    /// The Android API level currently being gathered, if generating for multiple API levels.
+    pub(crate) api_level: Option<u32>,
     pub(crate) progress: Mutex<util::Progress>,
 }
 
@@ -24,12 +44,56 @@ impl<'a> Context<'a> {
             config,
             module: Default::default(),
             all_classes: HashSet::new(),
+            emitted_classes: HashSet::new(),
+            class_api_levels: HashMap::new(),
+            class_type_parameters: HashMap::new(),
+            class_supers: HashMap::new(),
+            class_interfaces: HashMap::new(),
+            class_signatures: HashMap::new(),
+            supertype_api_levels: HashMap::new(),
+            api_level: None,
             progress: Mutex::new(util::Progress::with_duration(Duration::from_millis(
                 if config.logging_verbose { 0 } else { 300 },
             ))),
         }
     }
 
+    /// Is `java_class` being emitted, or bound by an \[[extern\]] crate?  Classes that are only on the classpath have no
+    /// Rust type to refer to.
+    pub(crate) fn is_class_available(&self, java_class: &str) -> bool {
+        self.emitted_classes.contains(java_class) || self.extern_for(java_class).is_some()
+    }
+
+    /// Is `java_class` on the classpath, but neither emitted nor bound by an \[[extern\]] crate?
+    fn is_classpath_only(&self, java_class: &str) -> bool {
+        self.all_classes.contains(java_class) && !self.is_class_available(java_class)
+    }
+
+    /// The \[[extern\]] with the longest `jni_prefix` matching `java_class`, if any.
+    pub(crate) fn extern_for(&self, java_class: &str) -> Option<&config::toml::Extern> {
+        self.config
+            .externs
+            .iter()
+            .filter(|e| java_class.starts_with(e.jni_prefix.as_str()))
+            .max_by_key(|e| e.jni_prefix.len())
+    }
+
+    /// The number of generic parameters the struct for `java_class` has.  Zero for classes we're not emitting, since we
+    /// can't know if e.g. an \[[extern\]] crate's bindings were generated with generics.
+    pub(crate) fn type_parameter_count(&self, java_class: &str) -> usize {
+        if !self.is_class_available("java/lang/Object") {
+            return 0; // Generic parameters default to Object
+        }
+        self.class_type_parameters.get(java_class).cloned().unwrap_or(0)
+    }
+
+    pub(crate) fn object_rust_path(&self, mod_: &str) -> Option<String> {
+        if !self.is_class_available("java/lang/Object") {
+            return None;
+        }
+        self.java_to_rust_path(class::Id("java/lang/Object"), mod_).ok()
+    }
+
     pub(crate) fn throwable_rust_path(&self, mod_: &str) -> String {
         self.java_to_rust_path(class::Id("java/lang/Throwable"), mod_).unwrap()
     }
@@ -37,6 +101,21 @@ impl<'a> Context<'a> {
     pub fn java_to_rust_path(&self, java_class: class::Id, mod_: &str) -> Result<String, Box<dyn Error>> {
         let m = Struct::mod_for(self, java_class)?;
         let s = Struct::name_for(self, java_class)?;
+
+        if let Some(ext) = self.extern_for(java_class.as_str()) {
+            return Ok(if m.is_empty() {
+                format!("{}::{}", ext.rust_path, s)
+            } else {
+                format!("{}::{}::{}", ext.rust_path, m, s)
+            });
+        }
+        if self.is_classpath_only(java_class.as_str()) {
+            return io_data_err!(
+                "{} is only on the classpath:  emit it, or bind it with an [[extern]]",
+                java_class.as_str()
+            )?;
+        }
+
         let fqn = format!("{}::{}", m, s);
 
         // Calculate relative path from B to A.
@@ -94,7 +173,11 @@ impl<'a> Context<'a> {
         return false;
     }
 
-    pub fn add_struct(&mut self, class: jreflection::Class) -> Result<(), Box<dyn Error>> {
+    pub fn add_struct(
+        &mut self,
+        class: jreflection::Class,
+        attributes: class_file::Attributes,
+    ) -> Result<(), Box<dyn Error>> {
         if self.config.ignore_classes.contains(class.path.as_str()) {
             return Ok(());
         }
@@ -102,15 +185,23 @@ impl<'a> Context<'a> {
             return Ok(());
         }
 
-        self.all_classes.insert(class.path.as_str().to_string());
-
-        let s = Struct::new(self, class)?;
+        self.gather_class(&class, &attributes);
+        self.emitted_classes.insert(class.path.as_str().to_string());
+        let s = Struct::new(self, class, attributes)?;
+        self.class_type_parameters.insert(
+            s.java.path.as_str().to_string(),
+            s.type_variables().rust_names().count(),
+        );
 
         let mut rust_mod = &mut self.module;
         for fragment in s.rust.mod_.split("::") {
             rust_mod = rust_mod.modules.entry(fragment.to_owned()).or_default();
         }
-        if rust_mod.structs.contains_key(&s.rust.struct_name) {
+        if let Some(existing) = rust_mod.structs.get_mut(&s.rust.struct_name) {
+            if self.api_level.is_some() && existing.java.path.as_str() == s.java.path.as_str() {
+                existing.merge_api_level(s);
+                return Ok(());
+            }
             return io_data_err!(
                 "Unable to add_struct(): java class name {:?} was already added",
                 &s.rust.struct_name
@@ -121,8 +212,435 @@ impl<'a> Context<'a> {
         Ok(())
     }
 
+    /// Make a class available for resolving argument, return, field, `extends`, and `implements` types, without emitting
+    /// any bindings for it.
+    pub fn add_classpath_class(
+        &mut self,
+        class: jreflection::Class,
+        attributes: class_file::Attributes,
+    ) -> Result<(), Box<dyn Error>> {
+        self.gather_class(&class, &attributes);
+        Ok(())
+    }
+
+    fn gather_class(&mut self, class: &jreflection::Class, attributes: &class_file::Attributes) {
+        self.all_classes.insert(class.path.as_str().to_string());
+        if let Some(level) = self.api_level {
+            self.class_api_levels
+                .entry(class.path.as_str().to_string())
+                .or_default()
+                .insert(level);
+            let supertypes = self
+                .supertype_api_levels
+                .entry(class.path.as_str().to_string())
+                .or_default();
+            for supertype in class.super_path.iter().chain(class.interfaces.iter()) {
+                supertypes
+                    .entry(supertype.as_str().to_string())
+                    .or_default()
+                    .insert(level);
+            }
+        }
+        match attributes.signature.as_deref().map(ClassSignature::parse) {
+            Some(Ok(signature)) => {
+                self.class_signatures.insert(class.path.as_str().to_string(), signature);
+            }
+            _ => {
+                self.class_signatures.remove(class.path.as_str());
+            }
+        }
+        if let Some(super_path) = class.super_path.as_ref() {
+            self.class_supers
+                .insert(class.path.as_str().to_string(), super_path.as_str().to_string());
+        }
+        let interfaces = self
+            .class_interfaces
+            .entry(class.path.as_str().to_string())
+            .or_default();
+        for interface in &class.interfaces {
+            if !interfaces.iter().any(|i| i == interface.as_str()) {
+                interfaces.push(interface.as_str().to_string());
+            }
+        }
+    }
+
+    /// Interfaces `class` implements (as far as the gathered classes tell) without declaring them itself:  those of its
+    /// superclasses, and the superinterfaces of all of them.  In breadth first order, without duplicates, and with type
+    /// arguments in terms of `class`'s own type variables - or raw, if those can't be worked out (e.g. because a
+    /// supertype along the way is raw.)
+    pub(crate) fn inherited_interfaces(&self, class: &str) -> Vec<ClassType> {
+        let declared = self.class_interfaces.get(class);
+        let is_declared = |interface: &str| declared.is_some_and(|d| d.iter().any(|i| i == interface));
+
+        // `class`'s own type variables stand for themselves.
+        let own = self.class_signatures.get(class).map(|signature| {
+            let arguments = signature
+                .type_parameters
+                .iter()
+                .map(|p| TypeArgument::Exactly(JavaType::TypeVariable(p.name.clone())))
+                .collect();
+            ClassType {
+                path: class.to_owned(),
+                arguments,
+            }
+        });
+        let mut pending: Vec<ClassType> = Vec::new();
+        let mut ancestor = own.unwrap_or_else(|| raw(class));
+        loop {
+            let (super_class, interfaces) = self.supertypes(&ancestor);
+            pending.extend(interfaces);
+            match super_class {
+                Some(super_class) => ancestor = super_class,
+                None => break,
+            }
+        }
+
+        let mut seen: Vec<ClassType> = Vec::new();
+        let mut next = 0;
+        while next < pending.len() {
+            let interface = pending[next].clone();
+            next += 1;
+            if seen.iter().any(|i| i.path == interface.path) {
+                continue;
+            }
+            pending.extend(self.supertypes(&interface).1);
+            seen.push(interface);
+        }
+        seen.retain(|interface| !is_declared(&interface.path));
+        seen
+    }
+
+    /// The superclass and interfaces of `class`, with its type arguments substituted into theirs.
+    fn supertypes(&self, class: &ClassType) -> (Option<ClassType>, Vec<ClassType>) {
+        let signature = self
+            .class_signatures
+            .get(class.path.as_str())
+            .filter(|s| s.type_parameters.len() == class.arguments.len());
+        match signature {
+            Some(signature) => {
+                let substitute = |supertype: &ClassType| {
+                    substitute(supertype, &signature.type_parameters, &class.arguments)
+                        .unwrap_or_else(|| raw(supertype.path.as_str()))
+                };
+                let super_class = Some(&signature.super_class)
+                    .filter(|_| self.class_supers.contains_key(class.path.as_str()))
+                    .map(substitute);
+                (super_class, signature.interfaces.iter().map(substitute).collect())
+            }
+            // Raw (or not generic), so the supertypes are too.
+            None => (
+                self.class_supers.get(class.path.as_str()).map(|s| raw(s)),
+                self.class_interfaces
+                    .get(class.path.as_str())
+                    .into_iter()
+                    .flatten()
+                    .map(|i| raw(i))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Does `class` implement `interface` - directly or not - at every API level it's found in?  Always true if we're not
+    /// generating for multiple API levels.
+    pub(crate) fn implements_at_every_level(&self, class: &str, interface: &str) -> bool {
+        let Some(levels) = self.class_api_levels.get(class) else {
+            return true;
+        };
+        levels.iter().all(|level| {
+            let mut pending = vec![class];
+            let mut seen = HashSet::new();
+            while let Some(class) = pending.pop() {
+                if class == interface {
+                    return true;
+                }
+                if !seen.insert(class) {
+                    continue;
+                }
+                pending.extend(
+                    self.supertype_api_levels
+                        .get(class)
+                        .into_iter()
+                        .flatten()
+                        .filter(|(_, levels)| levels.contains(level))
+                        .map(|(supertype, _)| supertype.as_str()),
+                );
+            }
+            false
+        })
+    }
+
+    /// The struct bindings are being emitted for `java_class` with, if any.
+    pub(crate) fn struct_for(&self, java_class: &str) -> Option<&Struct> {
+        if !self.emitted_classes.contains(java_class) {
+            return None;
+        }
+        let rust = StructPaths::new(self, class::Id(java_class)).ok()?;
+        let mut module = &self.module;
+        for fragment in rust.mod_.split("::") {
+            module = module.modules.get(fragment)?;
+        }
+        module.structs.get(&rust.struct_name)
+    }
+
+    /// Is `class` `ancestor`, or (as far as the gathered classes tell) derived from it?
+    pub(crate) fn is_subclass_of(&self, class: &str, ancestor: &str) -> bool {
+        let mut class = class;
+        loop {
+            if class == ancestor {
+                return true;
+            }
+            match self.class_supers.get(class) {
+                Some(super_path) => class = super_path.as_str(),
+                None => return false,
+            }
+        }
+    }
+
     pub fn write(&self, out: &mut impl io::Write) -> io::Result<()> {
-        write_preamble(out)?;
-        self.module.write(self, "", out)
+        self.check_throwable()?;
+        write_preamble(self, out)?;
+        self.module.write(self, "", out)?;
+        self.write_verify_bindings(out)
+    }
+
+    /// Every method's error type refers to `java.lang.Throwable`, so fail early if there's no Rust type for it.
+    fn check_throwable(&self) -> io::Result<()> {
+        if self.is_classpath_only("java/lang/Throwable") {
+            return io_data_err!(
+                "java/lang/Throwable is only on the classpath:  emit it, or bind it with an [[extern]]"
+            );
+        }
+        Ok(())
+    }
+
+    /// Writes `verify_bindings`, if enabled by [CodeGen::verify_bindings](crate::config::toml::CodeGen::verify_bindings).
+    fn write_verify_bindings(&self, out: &mut impl io::Write) -> io::Result<()> {
+        if !self.config.codegen.verify_bindings {
+            return Ok(());
+        }
+        writeln!(out)?;
+        writeln!(
+            out,
+            "/// Looks up every class, method, and field these bindings use in the running VM, reporting any that are"
+        )?;
+        writeln!(
+            out,
+            "/// missing - or found, but `static` where an instance member was expected (or vice versa.)"
+        )?;
+        writeln!(
+            out,
+            "pub fn verify_bindings(env: __jni_bindgen::Env) -> __jni_bindgen::BindingsReport {{"
+        )?;
+        writeln!(
+            out,
+            "    let mut __jni_verify = __jni_bindgen::VerifyBindings::new(env);"
+        )?;
+        self.module.write_verify_calls(self, out)?;
+        writeln!(out, "    __jni_verify.finish()")?;
+        writeln!(out, "}}")
+    }
+
+    /// Write [CodeShardingStyle::PerClass](crate::config::toml::CodeShardingStyle::PerClass) output rooted at `path`,
+    /// removing any previously generated files for classes that no longer exist.
+    pub fn write_per_class(&self, path: &Path) -> io::Result<()> {
+        self.check_throwable()?;
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| io_data_error!("{:?} has no file name", path))?;
+        let parent = path
+            .parent()
+            .ok_or_else(|| io_data_error!("{:?} has no parent directory", path))?;
+
+        // Submodules of lib.rs/main.rs/mod.rs live next to it, other files get a directory of their own.
+        let shares_dir = file_name == "lib.rs" || file_name == "main.rs" || file_name == "mod.rs";
+        let dir = if shares_dir {
+            parent.to_owned()
+        } else {
+            parent.join(path.file_stem().unwrap())
+        };
+
+        let mut written = HashSet::new();
+
+        let mut out = Vec::with_capacity(4096);
+        write_preamble(self, &mut out)?;
+        self.module.write_declarations(&mut out)?;
+        self.write_verify_bindings(&mut out)?;
+        util::write_generated(self, &path, &out[..])?;
+        written.insert(path.to_owned());
+
+        self.module.write_files(self, &dir, &mut written)?;
+        util::remove_stale_generated(self, &dir, &written, !shares_dir)
+    }
+}
+
+/// `class` as a raw type, without any type arguments.
+fn raw(class: &str) -> ClassType {
+    ClassType {
+        path: class.to_owned(),
+        arguments: Vec::new(),
+    }
+}
+
+#[test]
+fn classpath_only_classes_are_missing() {
+    use super::test_util::*;
+
+    let mut emit = java_lang();
+    emit.push(class(
+        ACC_PUBLIC,
+        "com/example/User",
+        &[],
+        None,
+        &[
+            member(ACC_PUBLIC, "bound", "(Landroid/os/Bundle;)V"),
+            member(ACC_PUBLIC, "unbound", "(Lorg/other/Thing;)V"),
+        ],
+    ));
+    let classpath = [
+        class(ACC_PUBLIC, "android/os/Bundle", &[], None, &[]),
+        class(ACC_PUBLIC, "org/other/Thing", &[], None, &[]),
+    ];
+    let out = generate(
+        "[[extern]]\njni_prefix = \"android/\"\nrust_path = \"jni_android_sys\"\n",
+        &emit,
+        &classpath,
+    );
+
+    // An [[extern]] gives a classpath class a Rust type...
+    assert!(out.contains("pub fn bound<'env>(&'env self, arg0: impl __jni_bindgen::std::convert::Into<__jni_bindgen::std::option::Option<&'env jni_android_sys::android::os::Bundle>>)"));
+    // ...without one, there's nothing to refer to.
+    assert!(out.contains("// Not emitting: ERROR:  missing class for argument type"));
+    assert!(!out.contains("Thing>"));
+    assert!(!out.contains("class Bundle") && !out.contains("class Thing"));
+}
+
+#[test]
+fn verify_bindings() {
+    use super::test_util::*;
+
+    let mut emit = java_lang();
+    emit.push(class(
+        ACC_PUBLIC,
+        "com/example/Checked",
+        &[],
+        None,
+        &[
+            member(ACC_PUBLIC, "<init>", "()V"),
+            member(ACC_PUBLIC, "get", "()I"),
+            member(ACC_PUBLIC | ACC_STATIC, "make", "()Lcom/example/Checked;"),
+            member(ACC_PUBLIC, "count", "I"),
+            member(ACC_PUBLIC | ACC_STATIC, "shared", "Ljava/lang/Object;"),
+            member(ACC_PUBLIC, "missing", "(Lorg/Missing;)V"),
+        ],
+    ));
+
+    assert!(!generate("", &emit, &[]).contains("verify"));
+    let out = generate("[codegen]\nverify_bindings = true\n", &emit, &[]);
+
+    // Each class checks itself and every member emitted for it, as static or not...
+    assert!(out.contains(
+        r#"        pub(crate) fn __jni_bindgen_verify_Checked(__jni_verify: &mut __jni_bindgen::VerifyBindings) {
+            unsafe {
+                if !__jni_verify.class("com/example/Checked\0") {
+                    return;
+                }
+                __jni_verify.method("<init>\0", "()V\0");
+                __jni_verify.method("get\0", "()I\0");
+                __jni_verify.static_method("make\0", "()Lcom/example/Checked;\0");
+                __jni_verify.field("count\0", "I\0");
+                __jni_verify.static_field("shared\0", "Ljava/lang/Object;\0");
+            }
+        }"#
+    ));
+    // ...but not members that weren't emitted.
+    assert!(!out.contains("__jni_verify.method(\"missing"));
+
+    // verify_bindings calls every class's function.
+    assert!(out.contains(
+        r#"pub fn verify_bindings(env: __jni_bindgen::Env) -> __jni_bindgen::BindingsReport {
+    let mut __jni_verify = __jni_bindgen::VerifyBindings::new(env);
+    com::example::__jni_bindgen_verify_Checked(&mut __jni_verify);
+    java::lang::__jni_bindgen_verify_Object(&mut __jni_verify);
+    java::lang::__jni_bindgen_verify_Throwable(&mut __jni_verify);
+    __jni_verify.finish()
+}"#
+    ));
+}
+
+#[test]
+fn per_class_files() {
+    use std::fs;
+
+    use super::test_util::*;
+
+    let classes = |names: &[&'static str]| {
+        let mut emit = java_lang();
+        emit.extend(names.iter().map(|name| class(ACC_PUBLIC, name, &[], None, &[])));
+        emit
+    };
+    let dir = test_dir("per_class_files");
+    let bindings = dir.join("bindings");
+    let example = bindings.join("com").join("example");
+
+    write_per_class(
+        &classes(&["com/example/Kept", "com/example/Gone", "org/old/Stale"]),
+        &dir.join("bindings.rs"),
+    )
+    .unwrap();
+    for file in ["mod.rs", "Kept.rs", "Gone.rs"] {
+        assert!(example.join(file).is_file());
+    }
+    assert!(bindings.join("org").join("old").join("Stale.rs").is_file());
+    fs::write(example.join("Notes.rs"), "// Hand written\n").unwrap();
+
+    // Files and modules that are no longer generated are removed - modules left empty along with their mod.rs - but
+    // hand written files are left alone.
+    write_per_class(&classes(&["com/example/Kept"]), &dir.join("bindings.rs")).unwrap();
+    assert!(example.join("Kept.rs").is_file());
+    assert!(!example.join("Gone.rs").exists());
+    assert!(!bindings.join("org").exists());
+    assert_eq!(
+        fs::read_to_string(example.join("Notes.rs")).unwrap(),
+        "// Hand written\n"
+    );
+
+    // Nor are hand written files overwritten.
+    fs::write(example.join("Kept.rs"), "// Hand written\n").unwrap();
+    assert!(write_per_class(&classes(&["com/example/Kept"]), &dir.join("bindings.rs")).is_err());
+    assert_eq!(
+        fs::read_to_string(example.join("Kept.rs")).unwrap(),
+        "// Hand written\n"
+    );
+}
+
+#[test]
+fn per_class_files_beside_lib_rs() {
+    use std::fs;
+
+    use super::test_util::*;
+
+    let classes = |names: &[&'static str]| {
+        let mut emit = java_lang();
+        emit.extend(names.iter().map(|name| class(ACC_PUBLIC, name, &[], None, &[])));
+        emit
+    };
+    let src = test_dir("per_class_files_beside_lib_rs").join("src");
+    fs::create_dir_all(src.join("helpers")).unwrap();
+    for file in ["lib.rs", "util.rs", "helpers/mod.rs"] {
+        fs::write(src.join(file), "// Hand written\n").unwrap();
+    }
+
+    // A hand written lib.rs isn't replaced...
+    assert!(write_per_class(&classes(&["com/example/Kept"]), &src.join("lib.rs")).is_err());
+    assert_eq!(fs::read_to_string(src.join("lib.rs")).unwrap(), "// Hand written\n");
+
+    // ...but once it's generated, its modules go in src/ alongside any others, which are left alone.
+    fs::remove_file(src.join("lib.rs")).unwrap();
+    write_per_class(&classes(&["com/example/Kept", "com/example/Gone"]), &src.join("lib.rs")).unwrap();
+    write_per_class(&classes(&["com/example/Kept"]), &src.join("lib.rs")).unwrap();
+    assert!(src.join("com").join("example").join("Kept.rs").is_file());
+    assert!(!src.join("com").join("example").join("Gone.rs").exists());
+    for file in ["util.rs", "helpers/mod.rs"] {
+        assert_eq!(fs::read_to_string(src.join(file)).unwrap(), "// Hand written\n");
     }
 }
@@ -2,7 +2,11 @@ use std::io;
 
 use jreflection::{class, field};
 
+use super::api_levels::ApiLevels;
+use super::generics::{needs_check, TypeVariables};
 use super::known_docs_url::KnownDocsUrl;
+use crate::class_file::signature::JavaType;
+use crate::config;
 use crate::emit_rust::Context;
 use crate::identifiers::{FieldMangling, IdentifierManglingError};
 
@@ -11,6 +15,11 @@ pub struct Field<'a> {
     pub java: &'a jreflection::Field,
     pub rust_names: Result<FieldMangling<'a>, IdentifierManglingError>,
     pub ignored: bool,
+    pub(crate) api_levels: Option<&'a ApiLevels>,
+    /// The field's generic type from its `Signature` attribute, if any.
+    pub(crate) signature: Option<&'a str>,
+    /// The type variables of the class, if it's bound as a generic struct.
+    pub(crate) class_type_variables: &'a TypeVariables,
 }
 
 impl<'a> Field<'a> {
@@ -28,11 +37,37 @@ impl<'a> Field<'a> {
             java,
             rust_names: context.config.codegen.field_naming_style.mangle(java, renamed_to),
             ignored,
+            api_levels: None,
+            signature: None,
+            class_type_variables: TypeVariables::NONE,
         };
         result
     }
 
     pub fn emit(&self, context: &Context, indent: &str, mod_: &str, out: &mut impl io::Write) -> io::Result<()> {
+        self.write(context, indent, mod_, out, false)
+    }
+
+    /// Emits a check of the field into `out` (the body of a class's `verify_bindings` function), if accessors were
+    /// emitted for it.  Constants are inlined, so never looked up.
+    pub(crate) fn emit_verify(
+        &self,
+        context: &Context,
+        indent: &str,
+        mod_: &str,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        self.write(context, indent, mod_, out, true)
+    }
+
+    fn write(
+        &self,
+        context: &Context,
+        indent: &str,
+        mod_: &str,
+        out: &mut impl io::Write,
+        verify: bool,
+    ) -> io::Result<()> {
         let mut emit_reject_reasons = Vec::new();
 
         if !self.java.is_public() {
@@ -41,6 +76,9 @@ impl<'a> Field<'a> {
         if self.ignored {
             emit_reject_reasons.push("[[ignore]]d");
         }
+        if self.java.is_static() && context.config.codegen.static_env == config::toml::StaticEnvStyle::__NonExhaustive {
+            emit_reject_reasons.push("ERROR:  StaticEnvStyle::__NonExhaustive is invalid, silly goose!");
+        }
 
         let descriptor = self.java.descriptor();
         let rust_set_type_buffer;
@@ -64,7 +102,7 @@ impl<'a> Field<'a> {
                 ("()", "()")
             }
             field::Descriptor::Single(field::BasicType::Class(class)) => {
-                if !context.all_classes.contains(class.as_str()) {
+                if !context.is_class_available(class.as_str()) {
                     emit_reject_reasons.push("ERROR:  missing class for field type");
                 }
                 if let Ok(fqn) = context.java_to_rust_path(class, mod_) {
@@ -97,7 +135,7 @@ impl<'a> Field<'a> {
                     field::BasicType::Float => buffer.push_str("__jni_bindgen::FloatArray"),
                     field::BasicType::Double => buffer.push_str("__jni_bindgen::DoubleArray"),
                     field::BasicType::Class(class) => {
-                        if !context.all_classes.contains(class.as_str()) {
+                        if !context.is_class_available(class.as_str()) {
                             emit_reject_reasons.push("ERROR:  missing class for field type");
                         }
 
@@ -138,6 +176,28 @@ impl<'a> Field<'a> {
             }
         };
 
+        let generic_set_type;
+        let generic_get_type;
+        let (rust_set_type, rust_get_type) = match self.generic_type(context, mod_) {
+            Some((generic_type, checked)) => {
+                generic_set_type = format!(
+                    "impl __jni_bindgen::std::convert::Into<__jni_bindgen::std::option::Option<&'obj {}>>",
+                    &generic_type
+                );
+                generic_get_type = format!(
+                    "__jni_bindgen::std::option::Option<__jni_bindgen::Local<'env, {}>>",
+                    &generic_type
+                );
+                // Getters have nowhere to report a value that isn't what the caller picked, so get the erased type.
+                if checked {
+                    (generic_set_type.as_str(), rust_get_type)
+                } else {
+                    (generic_set_type.as_str(), generic_get_type.as_str())
+                }
+            }
+            None => (rust_set_type, rust_get_type),
+        };
+
         let field_fragment = match self.java.descriptor() {
             // Contents of {get,set}_[static_]..._field
             field::Descriptor::Single(field::BasicType::Void) => "void",
@@ -153,6 +213,22 @@ impl<'a> Field<'a> {
             field::Descriptor::Array { .. } => "object",
         };
 
+        // Implicit static getters have no Env for the local they'd return to be tied to, so they return a global.
+        let implicit_get = self.java.is_static()
+            && field_fragment == "object"
+            && context.config.codegen.static_env == config::toml::StaticEnvStyle::Implicit;
+        let implicit_get_type;
+        let local_get_type = rust_get_type
+            .strip_prefix("__jni_bindgen::std::option::Option<")
+            .and_then(|t| t.strip_suffix('>'))
+            .unwrap_or(rust_get_type);
+        let rust_get_type = if implicit_get {
+            implicit_get_type = rust_get_type.replacen("__jni_bindgen::Local<'env, ", "__jni_bindgen::Global<", 1);
+            implicit_get_type.as_str()
+        } else {
+            rust_get_type
+        };
+
         if self.rust_names.is_err() {
             emit_reject_reasons.push(match self.java.name.as_str() {
                 "$VALUES" => "Failed to mangle field name: enum $VALUES", // Expected
@@ -161,7 +237,44 @@ impl<'a> Field<'a> {
             });
         }
 
+        let missing_member_results = match context.config.codegen.missing_members {
+            config::toml::MissingMemberStyle::Panic => false,
+            config::toml::MissingMemberStyle::Result => true,
+            config::toml::MissingMemberStyle::__NonExhaustive => {
+                emit_reject_reasons.push("ERROR:  MissingMemberStyle::__NonExhaustive is invalid, silly goose!");
+                false
+            }
+        };
+        let (get_result_type, set_result_type) = if missing_member_results {
+            (
+                format!(
+                    "__jni_bindgen::std::result::Result<{}, __jni_bindgen::MissingMember>",
+                    rust_get_type
+                ),
+                " -> __jni_bindgen::std::result::Result<(), __jni_bindgen::MissingMember>",
+            )
+        } else {
+            (rust_get_type.to_owned(), "")
+        };
+        // Wraps the result of an accessor's final expression, if need be
+        let (ok, ok_end) = if missing_member_results { ("Ok(", ")") } else { ("", "") };
+
         let emit_reject_reasons = emit_reject_reasons; // Freeze
+        if verify {
+            if !emit_reject_reasons.is_empty() || !matches!(self.rust_names, Ok(FieldMangling::GetSet(..))) {
+                return Ok(());
+            }
+            writeln!(
+                out,
+                "{}    {}__jni_verify.{}({}, {});",
+                indent,
+                ApiLevels::attribute(self.api_levels, context),
+                if self.java.is_static() { "static_field" } else { "field" },
+                emit_cstr(self.java.name.as_str()),
+                emit_cstr(self.java.descriptor_str())
+            )?;
+            return Ok(());
+        }
         let indent = if emit_reject_reasons.is_empty() {
             format!("{}        ", indent)
         } else {
@@ -179,7 +292,10 @@ impl<'a> Field<'a> {
             if self.java.is_volatile() { " volatile" } else { "" }
         );
 
-        let attributes = (if self.java.deprecated { "#[deprecated] " } else { "" }).to_string();
+        let mut attributes = ApiLevels::attribute(self.api_levels, context);
+        if self.java.deprecated {
+            attributes.push_str("#[deprecated] ");
+        }
 
         writeln!(out)?;
         for reason in &emit_reject_reasons {
@@ -187,10 +303,26 @@ impl<'a> Field<'a> {
         }
 
         let env_param = if self.java.is_static() {
-            "env: __jni_bindgen::Env<'env>"
+            match context.config.codegen.static_env {
+                config::toml::StaticEnvStyle::Explicit => "env: __jni_bindgen::Env<'env>",
+                config::toml::StaticEnvStyle::Implicit => "",
+                config::toml::StaticEnvStyle::__NonExhaustive => "env: ???",
+            }
         } else {
             "&'env self"
         };
+        let env_let = if !self.java.is_static() {
+            Some("let env = __jni_bindgen::Env::from_raw(self.0.env);")
+        } else if context.config.codegen.static_env == config::toml::StaticEnvStyle::Implicit {
+            Some("let env = __jni_bindgen::Env::ambient();")
+        } else {
+            None
+        };
+        let set_params = if env_param.is_empty() {
+            format!("value: {}", rust_set_type)
+        } else {
+            format!("{}, value: {}", env_param, rust_set_type)
+        };
 
         let url = KnownDocsUrl::from_field(
             context,
@@ -242,36 +374,33 @@ impl<'a> Field<'a> {
                 writeln!(
                     out,
                     "{}{}pub fn {}<'env>({}) -> {} {{",
-                    indent, &attributes, get, env_param, rust_get_type
+                    indent, &attributes, get, env_param, get_result_type
                 )?;
                 writeln!(out, "{}    unsafe {{", indent)?;
-                if !self.java.is_static() {
-                    writeln!(
-                        out,
-                        "{}        let env = __jni_bindgen::Env::from_raw(self.0.env);",
-                        indent
-                    )?;
+                if let Some(env_let) = env_let {
+                    writeln!(out, "{}        {}", indent, env_let)?;
                 }
-                writeln!(
-                    out,
-                    "{}        let (__jni_class, __jni_field) = env.require_class_{}field({}, {}, {});",
-                    indent,
-                    if self.java.is_static() { "static_" } else { "" },
-                    emit_cstr(self.class.path.as_str()),
-                    emit_cstr(self.java.name.as_str()),
-                    emit_cstr(self.java.descriptor_str())
-                )?;
+                self.write_lookup(context, &indent, out)?;
                 if self.java.is_static() {
                     writeln!(
                         out,
-                        "{}        env.get_static_{}_field(__jni_class, __jni_field)",
-                        indent, field_fragment
+                        "{}        {}env.get_static_{}_field(__jni_class, __jni_field){}{}",
+                        indent,
+                        ok,
+                        field_fragment,
+                        if implicit_get {
+                            // Nothing else pins down the `R` the field is read as a local of.
+                            format!(".map(|value: {}| value.as_global())", local_get_type)
+                        } else {
+                            String::new()
+                        },
+                        ok_end
                     )?;
                 } else {
                     writeln!(
                         out,
-                        "{}        env.get_{}_field(self.0.object, __jni_field)",
-                        indent, field_fragment
+                        "{}        {}env.get_{}_field(self.0.object, __jni_field){}",
+                        indent, ok, field_fragment, ok_end
                     )?;
                 }
                 writeln!(out, "{}    }}", indent)?;
@@ -293,37 +422,25 @@ impl<'a> Field<'a> {
                     }
                     writeln!(
                         out,
-                        "{}{}pub fn {}<{}>({}, value: {}) {{",
-                        indent, &attributes, set, lifetimes, env_param, rust_set_type
+                        "{}{}pub fn {}<{}>({}){} {{",
+                        indent, &attributes, set, lifetimes, set_params, set_result_type
                     )?;
                     writeln!(out, "{}    unsafe {{", indent)?;
-                    if !self.java.is_static() {
-                        writeln!(
-                            out,
-                            "{}        let env = __jni_bindgen::Env::from_raw(self.0.env);",
-                            indent
-                        )?;
+                    if let Some(env_let) = env_let {
+                        writeln!(out, "{}        {}", indent, env_let)?;
                     }
-                    writeln!(
-                        out,
-                        "{}        let (__jni_class, __jni_field) = env.require_class_{}field({}, {}, {});",
-                        indent,
-                        if self.java.is_static() { "static_" } else { "" },
-                        emit_cstr(self.class.path.as_str()),
-                        emit_cstr(self.java.name.as_str()),
-                        emit_cstr(self.java.descriptor_str())
-                    )?;
+                    self.write_lookup(context, &indent, out)?;
                     if self.java.is_static() {
                         writeln!(
                             out,
-                            "{}        env.set_static_{}_field(__jni_class, __jni_field, value)",
-                            indent, field_fragment
+                            "{}        {}env.set_static_{}_field(__jni_class, __jni_field, value){}",
+                            indent, ok, field_fragment, ok_end
                         )?;
                     } else {
                         writeln!(
                             out,
-                            "{}        env.set_{}_field(self.0.object, __jni_field, value)",
-                            indent, field_fragment
+                            "{}        {}env.set_{}_field(self.0.object, __jni_field, value){}",
+                            indent, ok, field_fragment, ok_end
                         )?;
                     }
                     writeln!(out, "{}    }}", indent)?;
@@ -358,8 +475,111 @@ impl<'a> Field<'a> {
     }
 }
 
+impl<'a> Field<'a> {
+    /// The field's generic type from its signature, and whether values read from it would [need checking](needs_check) -
+    /// or [None] to use the erased type from the descriptor.
+    fn generic_type(&self, context: &Context, mod_: &str) -> Option<(String, bool)> {
+        if self.java.is_constant() {
+            return None; // Constants are primitives or strings, never generic
+        }
+        let type_variables = if self.java.is_static() {
+            TypeVariables::NONE
+        } else {
+            self.class_type_variables
+        };
+        let java = JavaType::parse_field(self.signature?).ok()?;
+        let rust = type_variables.reference_type(context, mod_, &java, &mut Default::default())?;
+        Some((rust, needs_check(&java)))
+    }
+
+    /// Writes the `let (__jni_class, __jni_field) = ...;` of an accessor, looking them up through `env` - returning early
+    /// if missing and [MissingMemberStyle::Result](config::toml::MissingMemberStyle::Result).
+    fn write_lookup(&self, context: &Context, indent: &str, out: &mut impl io::Write) -> io::Result<()> {
+        let static_ = if self.java.is_static() { "static_" } else { "" };
+        let (try_, question) = match context.config.codegen.missing_members {
+            config::toml::MissingMemberStyle::Result => ("try_", "?"),
+            _ => ("", ""),
+        };
+        let lookup = if context.config.codegen.cache_lookups {
+            writeln!(
+                out,
+                "{}        static __JNI_FIELD: __jni_bindgen::FieldCache = __jni_bindgen::FieldCache::new();",
+                indent
+            )?;
+            format!("__JNI_FIELD.{}require_class_{}field(env, ", try_, static_)
+        } else {
+            format!("env.{}require_class_{}field(", try_, static_)
+        };
+        writeln!(
+            out,
+            "{}        let (__jni_class, __jni_field) = {}{}, {}, {}){};",
+            indent,
+            lookup,
+            emit_cstr(self.class.path.as_str()),
+            emit_cstr(self.java.name.as_str()),
+            emit_cstr(self.java.descriptor_str()),
+            question
+        )
+    }
+}
+
 fn emit_cstr(s: &str) -> String {
     let mut s = format!("{:?}", s); // XXX
     s.insert_str(s.len() - 1, "\\0");
     s
 }
+
+#[test]
+fn cached_lookups() {
+    use super::test_util::*;
+
+    let mut emit = java_lang();
+    emit.push(class(
+        ACC_PUBLIC,
+        "com/example/Cached",
+        &[],
+        None,
+        &[
+            member(ACC_PUBLIC, "get", "()I"),
+            member(ACC_PUBLIC | ACC_STATIC, "make", "()I"),
+            member(ACC_PUBLIC, "count", "I"),
+            member(ACC_PUBLIC | ACC_STATIC, "shared", "I"),
+        ],
+    ));
+
+    // By default, every method and field accessor looks its class and member up once, keeping them in a static...
+    let out = generate("", &emit, &[]);
+    assert_eq!(
+        out.matches("static __JNI_METHOD: __jni_bindgen::MethodCache = __jni_bindgen::MethodCache::new();")
+            .count(),
+        2
+    );
+    assert_eq!(
+        out.matches("static __JNI_FIELD: __jni_bindgen::FieldCache = __jni_bindgen::FieldCache::new();")
+            .count(),
+        4
+    );
+    assert!(out.contains(r#"let (__jni_class, __jni_method) = __JNI_METHOD.require_class_method(__jni_env, "com/example/Cached\0", "get\0", "()I\0");"#));
+    assert!(out.contains(r#"let (__jni_class, __jni_method) = __JNI_METHOD.require_class_static_method(__jni_env, "com/example/Cached\0", "make\0", "()I\0");"#));
+    assert!(out.contains(r#"let (__jni_class, __jni_field) = __JNI_FIELD.require_class_field(env, "com/example/Cached\0", "count\0", "I\0");"#));
+    assert!(out.contains(r#"let (__jni_class, __jni_field) = __JNI_FIELD.require_class_static_field(env, "com/example/Cached\0", "shared\0", "I\0");"#));
+
+    // ...using the fallible lookups if missing members are reported as errors...
+    let out = generate("[codegen]\nmissing_members = \"result\"\n", &emit, &[]);
+    assert!(out.contains(r#"let (__jni_class, __jni_method) = __JNI_METHOD.try_require_class_method(__jni_env, "com/example/Cached\0", "get\0", "()I\0")?;"#));
+    assert!(out.contains(r#"let (__jni_class, __jni_field) = __JNI_FIELD.try_require_class_static_field(env, "com/example/Cached\0", "shared\0", "I\0")?;"#));
+
+    // ...or looks them up every time, if asked not to cache them.
+    let out = generate("[codegen]\ncache_lookups = false\n", &emit, &[]);
+    assert!(!out.contains("Cache::new()"));
+    assert!(out.contains(
+        r#"let (__jni_class, __jni_method) = __jni_env.require_class_method("com/example/Cached\0", "get\0", "()I\0");"#
+    ));
+    assert!(out.contains(r#"let (__jni_class, __jni_method) = __jni_env.require_class_static_method("com/example/Cached\0", "make\0", "()I\0");"#));
+    assert!(out.contains(
+        r#"let (__jni_class, __jni_field) = env.require_class_field("com/example/Cached\0", "count\0", "I\0");"#
+    ));
+    assert!(out.contains(
+        r#"let (__jni_class, __jni_field) = env.require_class_static_field("com/example/Cached\0", "shared\0", "I\0");"#
+    ));
+}
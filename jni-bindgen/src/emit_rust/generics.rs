@@ -0,0 +1,225 @@
+use std::collections::BTreeSet;
+
+use jreflection::class;
+
+use crate::class_file::signature::{ClassType, JavaType, TypeArgument, TypeParameter};
+use crate::emit_rust::Context;
+use crate::identifiers::RustIdentifier;
+
+/// The Java type variables in scope at some point, and the Rust generic parameters they map to.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TypeVariables(Vec<(String, String)>);
+
+impl TypeVariables {
+    /// No type variables in scope, e.g. for static members.
+    pub(crate) const NONE: &'static TypeVariables = &TypeVariables(Vec::new());
+
+    /// Generic parameters of the generated code itself, which type variables mustn't clash with (besides anything
+    /// starting with `__jni_`.)
+    const RESERVED: &'static [&'static str] = &["N"];
+
+    /// Brings `parameters` into scope, renaming any that would shadow a type variable already in scope (Java allows a
+    /// generic method's `<T>` to shadow its class's `<T>`, Rust doesn't) or clash with a [RESERVED](Self::RESERVED)
+    /// name.  Returns [None] if any has no Rust equivalent, or uses the generator's `__jni_` prefix.
+    pub(crate) fn declare(&mut self, parameters: &[TypeParameter]) -> Option<()> {
+        for parameter in parameters {
+            let mut rust = match RustIdentifier::from_str(parameter.name.as_str()) {
+                RustIdentifier::Identifier(id) => id.to_owned(),
+                RustIdentifier::KeywordRawSafe(id) => id.to_owned(),
+                RustIdentifier::KeywordUnderscorePostfix(id) => id.to_owned(),
+                RustIdentifier::NonIdentifier(_) => return None,
+            };
+            if rust.starts_with("__jni_") {
+                return None;
+            }
+            while Self::RESERVED.contains(&rust.as_str()) || self.0.iter().any(|(_, r)| r == &rust) {
+                rust.push('_');
+            }
+            self.0.push((parameter.name.clone(), rust));
+        }
+        Some(())
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The Rust names of the type variables in scope, in declaration order.
+    pub(crate) fn rust_names(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|(_, rust)| rust.as_str())
+    }
+
+    fn get(&self, java: &str) -> Option<&str> {
+        // Innermost (last declared) wins
+        self.0
+            .iter()
+            .rev()
+            .find(|(j, _)| j == java)
+            .map(|(_, rust)| rust.as_str())
+    }
+
+    /// The Rust type for a reference type signature (as used inside `Local<'env, ...>`), or [None] if it can't be
+    /// expressed - typically because it references a type variable that's not in scope or a class that isn't
+    /// available.  Callers are expected to fall back on the erased type from the descriptor.
+    ///
+    /// The Rust names of any type variables referenced are added to `used`.
+    pub(crate) fn reference_type(
+        &self,
+        context: &Context,
+        mod_: &str,
+        java: &JavaType,
+        used: &mut BTreeSet<String>,
+    ) -> Option<String> {
+        match java {
+            JavaType::Base(_) => None,
+            JavaType::TypeVariable(name) => {
+                let rust = self.get(name)?;
+                used.insert(rust.to_owned());
+                Some(rust.to_owned())
+            }
+            JavaType::Class(class) => self.class_type(context, mod_, class, used),
+            JavaType::Array(element) => match element.as_ref() {
+                JavaType::Base(b'Z') => Some("__jni_bindgen::BooleanArray".to_owned()),
+                JavaType::Base(b'B') => Some("__jni_bindgen::ByteArray".to_owned()),
+                JavaType::Base(b'C') => Some("__jni_bindgen::CharArray".to_owned()),
+                JavaType::Base(b'S') => Some("__jni_bindgen::ShortArray".to_owned()),
+                JavaType::Base(b'I') => Some("__jni_bindgen::IntArray".to_owned()),
+                JavaType::Base(b'J') => Some("__jni_bindgen::LongArray".to_owned()),
+                JavaType::Base(b'F') => Some("__jni_bindgen::FloatArray".to_owned()),
+                JavaType::Base(b'D') => Some("__jni_bindgen::DoubleArray".to_owned()),
+                JavaType::Base(_) => None,
+                element => Some(format!(
+                    "__jni_bindgen::ObjectArray<{}, {}>",
+                    self.reference_type(context, mod_, element, used)?,
+                    context.throwable_rust_path(mod_)
+                )),
+            },
+        }
+    }
+
+    /// Like [reference_type](Self::reference_type), but for a `ClassTypeSignature`.  Falls back on the raw type if any
+    /// type argument can't be expressed, or the class isn't bound as generic.
+    pub(crate) fn class_type(
+        &self,
+        context: &Context,
+        mod_: &str,
+        java: &ClassType,
+        used: &mut BTreeSet<String>,
+    ) -> Option<String> {
+        if !context.is_class_available(java.path.as_str()) {
+            return None;
+        }
+        let path = context.java_to_rust_path(class::Id(java.path.as_str()), mod_).ok()?;
+        if java.arguments.is_empty() || context.type_parameter_count(java.path.as_str()) != java.arguments.len() {
+            return Some(path);
+        }
+
+        let mut arguments_used = BTreeSet::new();
+        let mut arguments = Vec::new();
+        for argument in &java.arguments {
+            let argument = match argument {
+                TypeArgument::Exactly(java) | TypeArgument::Extends(java) => {
+                    self.reference_type(context, mod_, java, &mut arguments_used)
+                }
+                // Rust has no wildcards:  `Foo<?>` and `Foo<? super T>` are as good as `Foo<Object>`
+                TypeArgument::Any | TypeArgument::Super(_) => context.object_rust_path(mod_),
+            };
+            match argument {
+                Some(argument) => arguments.push(argument),
+                None => return Some(path), // Raw type
+            }
+        }
+        used.extend(arguments_used);
+        Some(format!("{}<{}>", path, arguments.join(", ")))
+    }
+}
+
+/// Whether an object of the generic type `java` needs checking when returned to Rust:  whoever picked a type variable's
+/// Rust type can't be trusted to have picked it right, and Java erases it.  Arrays are reified (so checkable), but other
+/// generic classes aren't - their own generic returns get checked instead.
+pub(crate) fn needs_check(java: &JavaType) -> bool {
+    match java {
+        JavaType::TypeVariable(_) => true,
+        JavaType::Array(element) => needs_check(element),
+        JavaType::Base(_) | JavaType::Class(_) => false,
+    }
+}
+
+/// `java` with the type variables `parameters` replaced by `arguments`, or [None] if it uses any other type variables.
+/// Wildcard arguments are replaced by their bound (or `java/lang/Object`), as Rust has no wildcards.
+pub(crate) fn substitute(
+    java: &ClassType,
+    parameters: &[TypeParameter],
+    arguments: &[TypeArgument],
+) -> Option<ClassType> {
+    let substitute_type = |java: &JavaType| substitute_java_type(java, parameters, arguments);
+    let arguments = java
+        .arguments
+        .iter()
+        .map(|argument| {
+            Some(match argument {
+                TypeArgument::Any => TypeArgument::Any,
+                TypeArgument::Extends(java) => TypeArgument::Extends(substitute_type(java)?),
+                TypeArgument::Super(java) => TypeArgument::Super(substitute_type(java)?),
+                TypeArgument::Exactly(java) => TypeArgument::Exactly(substitute_type(java)?),
+            })
+        })
+        .collect::<Option<_>>()?;
+    Some(ClassType {
+        path: java.path.clone(),
+        arguments,
+    })
+}
+
+fn substitute_java_type(java: &JavaType, parameters: &[TypeParameter], arguments: &[TypeArgument]) -> Option<JavaType> {
+    Some(match java {
+        JavaType::Base(_) => java.clone(),
+        JavaType::TypeVariable(name) => match &arguments[parameters.iter().position(|p| &p.name == name)?] {
+            TypeArgument::Exactly(java) | TypeArgument::Extends(java) => java.clone(),
+            TypeArgument::Any | TypeArgument::Super(_) => JavaType::Class(ClassType {
+                path: "java/lang/Object".to_owned(),
+                arguments: Vec::new(),
+            }),
+        },
+        JavaType::Class(class) => JavaType::Class(substitute(class, parameters, arguments)?),
+        JavaType::Array(element) => JavaType::Array(Box::new(substitute_java_type(element, parameters, arguments)?)),
+    })
+}
+
+#[test]
+fn declare_shadowing() {
+    let parameter = |name: &str| TypeParameter {
+        name: name.to_owned(),
+        bounds: Vec::new(),
+    };
+
+    let mut class = TypeVariables::default();
+    class.declare(&[parameter("T"), parameter("type")]).unwrap();
+    assert_eq!(class.rust_names().collect::<Vec<_>>(), vec!["T", "r#type"]);
+
+    // e.g. `class Foo<T> { <T> T bar(); }`
+    let mut method = class.clone();
+    method.declare(&[parameter("T")]).unwrap();
+    assert_eq!(method.rust_names().collect::<Vec<_>>(), vec!["T", "r#type", "T_"]);
+    assert_eq!(method.get("T"), Some("T_"));
+    assert_eq!(class.get("T"), Some("T"));
+
+    assert!(TypeVariables::default().declare(&[parameter("a-b")]).is_none());
+}
+
+#[test]
+fn declare_reserved() {
+    let parameter = |name: &str| TypeParameter {
+        name: name.to_owned(),
+        bounds: Vec::new(),
+    };
+
+    // e.g. `interface Function<T, R>` - `R` used to clash with `static_with_jni_type<R>`, now `__jni_R`
+    let mut variables = TypeVariables::default();
+    variables
+        .declare(&[parameter("R"), parameter("N"), parameter("Self")])
+        .unwrap();
+    assert_eq!(variables.rust_names().collect::<Vec<_>>(), vec!["R", "N_", "Self_"]);
+
+    assert!(TypeVariables::default().declare(&[parameter("__jni_Self")]).is_none());
+}
@@ -1,17 +1,31 @@
+use std::collections::BTreeSet;
 use std::io;
 
-use jreflection::method;
+use jreflection::{class, method};
 
+use super::api_levels::ApiLevels;
+use super::generics::{needs_check, TypeVariables};
 use super::known_docs_url::KnownDocsUrl;
+use super::structs::Struct;
+use crate::class_file::signature::{JavaType, MethodSignature};
 use crate::config;
 use crate::emit_rust::Context;
-use crate::identifiers::MethodManglingStyle;
+use crate::identifiers::{native_fallback_name, native_symbol, MethodManglingStyle};
 
 pub struct Method<'a> {
     pub class: &'a jreflection::Class,
     pub java: &'a jreflection::Method,
     rust_name: Option<String>,
     mangling_style: MethodManglingStyle,
+    pub(crate) api_levels: Option<&'a ApiLevels>,
+    /// Classes listed by the method's `Exceptions` attribute (its `throws` clause.)
+    pub(crate) exceptions: &'a [String],
+    /// The method's generic signature from its `Signature` attribute, if any.
+    pub(crate) signature: Option<&'a str>,
+    /// The type variables of the class, if it's bound as a generic struct.
+    pub(crate) class_type_variables: &'a TypeVariables,
+    /// Whether the method's Rust name was already taken by another member of the class.
+    pub(crate) name_collision: bool,
 }
 
 impl<'a> Method<'a> {
@@ -21,6 +35,11 @@ impl<'a> Method<'a> {
             java,
             rust_name: None,
             mangling_style: MethodManglingStyle::Java, // Immediately overwritten bellow
+            api_levels: None,
+            exceptions: &[],
+            signature: None,
+            class_type_variables: TypeVariables::NONE,
+            name_collision: false,
         };
         result.set_mangling_style(context.config.codegen.method_naming_style); // rust_name + mangling_style
         result
@@ -42,25 +61,226 @@ impl<'a> Method<'a> {
         };
     }
 
-    pub fn emit(&self, context: &Context, indent: &str, mod_: &str, out: &mut impl io::Write) -> io::Result<()> {
-        let mut emit_reject_reasons = Vec::new();
+    /// The name the method is bound to in Rust (its `[[rename]]`, or else its mangled name), unless it's rejected no
+    /// matter its name.
+    pub(crate) fn emitted_name(&self, context: &Context) -> Option<String> {
+        if !self.java.is_public() || self.java.is_bridge() || self.java.is_static_init() || self.ignored(context) {
+            return None;
+        }
+        self.renamed_to(context).or(self.rust_name.as_ref()).cloned()
+    }
+
+    fn ignored(&self, context: &Context) -> bool {
+        context.config.ignore_class_methods.contains(&self.java_class_method())
+            || context
+                .config
+                .ignore_class_method_sigs
+                .contains(&self.java_class_method_sig())
+    }
+
+    fn renamed_to<'c>(&self, context: &'c Context) -> Option<&'c String> {
+        context
+            .config
+            .rename_class_methods
+            .get(&self.java_class_method())
+            .or_else(|| {
+                context
+                    .config
+                    .rename_class_method_sigs
+                    .get(&self.java_class_method_sig())
+            })
+    }
 
-        let java_class_method = format!("{}\x1f{}", self.class.path.as_str(), &self.java.name);
-        let java_class_method_sig = format!(
+    fn java_class_method(&self) -> String {
+        format!("{}\x1f{}", self.class.path.as_str(), &self.java.name)
+    }
+
+    fn java_class_method_sig(&self) -> String {
+        format!(
             "{}\x1f{}\x1f{}",
             self.class.path.as_str(),
             &self.java.name,
             self.java.descriptor_str()
+        )
+    }
+
+    /// Emits the method into `out` (the body of a `__jni_bindgen!` class), and any items it needs outside of the class
+    /// - such as a typed error enum - into `items`.
+    pub fn emit(
+        &self,
+        context: &Context,
+        indent: &str,
+        mod_: &str,
+        out: &mut impl io::Write,
+        items: &mut impl io::Write,
+    ) -> io::Result<()> {
+        self.write(context, indent, mod_, out, items, Target::Binding)
+    }
+
+    /// Emits a check of the method into `out` (the body of a class's `verify_bindings` function), if it was emitted for
+    /// the class itself.
+    pub(crate) fn emit_verify(
+        &self,
+        context: &Context,
+        indent: &str,
+        mod_: &str,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        self.write(context, indent, mod_, out, &mut io::sink(), Target::Verify)
+    }
+
+    /// Emits the method into `out` (the body of an interface's `...Ext` trait) as a default method forwarding to the
+    /// same method of `interface`.  Only emits public instance methods that were emitted for the interface itself.
+    pub(crate) fn emit_ext(
+        &self,
+        context: &Context,
+        indent: &str,
+        mod_: &str,
+        interface: &str,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        if !self.java.is_public() || self.java.is_static() || self.java.is_constructor() {
+            return Ok(());
+        }
+        self.write(context, indent, mod_, out, &mut io::sink(), Target::Ext { interface })
+    }
+
+    /// Emits the method into `out` (the body of an interface's `...Impl` trait) as a required method for Rust
+    /// implementations of the interface.  Only emits public instance methods that were emitted for the interface itself.
+    pub(crate) fn emit_impl(
+        &self,
+        context: &Context,
+        indent: &str,
+        mod_: &str,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        if !self.java.is_public() || self.java.is_static() || self.java.is_constructor() {
+            return Ok(());
+        }
+        self.write(context, indent, mod_, out, &mut io::sink(), Target::Impl)
+    }
+
+    /// Emits the method into `out` (the body of an interface's `from_rust` proxy handler) as a check for calls to it,
+    /// forwarding them to `__jni_implementation`'s method of `impl_trait`.  Emits the same methods as
+    /// [emit_impl](Self::emit_impl).
+    pub(crate) fn emit_proxy_dispatch(
+        &self,
+        context: &Context,
+        indent: &str,
+        mod_: &str,
+        impl_trait: &str,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        if !self.java.is_public() || self.java.is_static() || self.java.is_constructor() {
+            return Ok(());
+        }
+        self.write(
+            context,
+            indent,
+            mod_,
+            out,
+            &mut io::sink(),
+            Target::ProxyDispatch { impl_trait },
+        )
+    }
+
+    /// Emits the method into `out` (the body of a class's `...Natives` trait) as a required function implementing the
+    /// `native` method in Rust.  Only emits `native` methods.
+    pub(crate) fn emit_native(
+        &self,
+        context: &Context,
+        indent: &str,
+        mod_: &str,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        if !self.java.is_native() {
+            return Ok(());
+        }
+        self.write(context, indent, mod_, out, &mut io::sink(), Target::Native)
+    }
+
+    /// Emits an `extern "system"` trampoline into `out` (the body of a class's `register_natives`) forwarding to the
+    /// implementation of [emit_native](Self::emit_native)'s function by `N: natives_trait`, and the push of its
+    /// `JNINativeMethod` onto `__jni_natives` into `registration`.
+    pub(crate) fn emit_native_trampoline(
+        &self,
+        context: &Context,
+        indent: &str,
+        mod_: &str,
+        natives_trait: &str,
+        out: &mut impl io::Write,
+        registration: &mut impl io::Write,
+    ) -> io::Result<()> {
+        if !self.java.is_native() {
+            return Ok(());
+        }
+        self.write(
+            context,
+            indent,
+            mod_,
+            out,
+            registration,
+            Target::NativeTrampoline { natives_trait },
+        )
+    }
+
+    /// Emits a `#[no_mangle]` `Java_...` function into `out` (alongside the class's `...Natives` trait) for the JVM to
+    /// resolve the `native` method to, forwarding to `implementation`'s [emit_native](Self::emit_native) function.
+    /// `overloaded` selects the long, signature-qualified symbol.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn emit_native_export(
+        &self,
+        context: &Context,
+        indent: &str,
+        mod_: &str,
+        cfg: &str,
+        natives_trait: &str,
+        implementation: &str,
+        overloaded: bool,
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        if !self.java.is_native() {
+            return Ok(());
+        }
+        let symbol = native_symbol(
+            self.class.path.as_str(),
+            self.java.name.as_str(),
+            self.java.descriptor_str(),
+            overloaded,
         );
+        self.write(
+            context,
+            indent,
+            mod_,
+            out,
+            &mut io::sink(),
+            Target::NativeExport {
+                cfg,
+                natives_trait,
+                implementation,
+                symbol: &symbol,
+            },
+        )
+    }
 
-        let ignored = context.config.ignore_class_methods.contains(&java_class_method)
-            || context.config.ignore_class_method_sigs.contains(&java_class_method_sig);
+    fn write(
+        &self,
+        context: &Context,
+        indent: &str,
+        mod_: &str,
+        out: &mut impl io::Write,
+        items: &mut impl io::Write,
+        target: Target,
+    ) -> io::Result<()> {
+        let mut emit_reject_reasons = Vec::new();
+        let for_proxy = matches!(target, Target::Impl | Target::ProxyDispatch { .. });
+        let for_natives = matches!(
+            target,
+            Target::Native | Target::NativeTrampoline { .. } | Target::NativeExport { .. }
+        );
 
-        let renamed_to = context
-            .config
-            .rename_class_methods
-            .get(&java_class_method)
-            .or_else(|| context.config.rename_class_method_sigs.get(&java_class_method_sig));
+        let ignored = self.ignored(context);
+        let renamed_to = self.renamed_to(context);
 
         let descriptor = self.java.descriptor();
 
@@ -68,12 +288,18 @@ impl<'a> Method<'a> {
             renamed_to.clone()
         } else if let Some(name) = self.rust_name() {
             name.to_owned()
+        } else if for_natives {
+            // Dropping the native would leave it unimplemented, so make do with a name nobody would pick.
+            native_fallback_name(self.java.name.as_str(), self.java.descriptor_str())
         } else {
             emit_reject_reasons.push("ERROR:  Failed to mangle method name");
             self.java.name.to_owned()
         };
 
-        if !self.java.is_public() {
+        if self.name_collision && !for_natives {
+            emit_reject_reasons.push("ERROR:  Rust name collides with another member's");
+        }
+        if !self.java.is_public() && !for_natives {
             emit_reject_reasons.push("Non-public method");
         }
         if self.java.is_bridge() {
@@ -91,11 +317,31 @@ impl<'a> Method<'a> {
         // https://docs.oracle.com/javase/tutorial/reflect/member/methodparameterreflection.html
 
         let mut params_array = String::new(); // Contents of let __jni_args = [...];
+        let mut impl_params_decl = String::from("&self, env: __jni_bindgen::Env<'env>"); // Contents of ...Impl's fn name<'env>(...);
+
+        // Contents of ...Natives' fn name<'env>(...);, its trampoline's fn name<'env, N: ...Natives>(...), and the call
+        // forwarding the latter to the former.
+        let mut native_params_decl = String::from("env: __jni_bindgen::Env<'env>");
+        let mut trampoline_params_decl = String::from("__jni_env: __jni_bindgen::Env<'env>");
+        let mut trampoline_args = String::from("__jni_env");
+        if for_natives {
+            if self.java.is_static() {
+                trampoline_params_decl.push_str(", _: __jni_bindgen::sys::jclass");
+            } else {
+                let class = context
+                    .java_to_rust_path(self.class.path.as_id(), mod_)
+                    .map_err(|e| io_data_error!("{}", e))?;
+                native_params_decl.push_str(&format!(", this: __jni_bindgen::Ref<'env, {}>", class));
+                trampoline_params_decl.push_str(&format!(", __jni_this: __jni_bindgen::Argument<{}>", class));
+                trampoline_args.push_str(", __jni_this.into_ref(__jni_env).unwrap()");
+            }
+        }
 
         // Contents of fn name<'env>(...) {
         let mut params_decl = if self.java.is_constructor() || self.java.is_static() {
             match context.config.codegen.static_env {
                 config::toml::StaticEnvStyle::Explicit => String::from("__jni_env: __jni_bindgen::Env<'env>"),
+                config::toml::StaticEnvStyle::Implicit => String::new(),
                 config::toml::StaticEnvStyle::__NonExhaustive => {
                     emit_reject_reasons.push("ERROR:  StaticEnvStyle::__NonExhaustive is invalid, silly goose!");
                     String::new()
@@ -105,12 +351,30 @@ impl<'a> Method<'a> {
             String::from("&'env self")
         };
 
+        // Generic types from the method's signature, where we can express them.  Falls back on erased types otherwise.
+        // Proxies erase the method's own type variables:  nothing would pick them when dispatching to `...Impl`.  Natives
+        // erase all of them, as there's nothing to pick the class's either.
+        let (type_variables, method_type_variables, signature) = if for_natives {
+            (TypeVariables::default(), Vec::new(), None)
+        } else {
+            self.generic_signature(descriptor.arguments().count(), !for_proxy)
+        };
+        let mut used_type_variables = BTreeSet::new();
+        let mut generic_type =
+            |java: Option<&JavaType>| type_variables.reference_type(context, mod_, java?, &mut used_type_variables);
+
         for (arg_idx, arg) in descriptor.arguments().enumerate() {
             let arg_name = format!("arg{}", arg_idx);
 
             let mut param_is_object = false; // XXX
 
+            let is_boolean = arg == method::Type::Single(method::BasicType::Boolean);
+            let generic_arg_type = generic_type(signature.as_ref().map(|s| &s.parameters[arg_idx]));
             let arg_type = match arg {
+                _ if generic_arg_type.is_some() => {
+                    param_is_object = true;
+                    generic_arg_type.unwrap()
+                }
                 method::Type::Single(method::BasicType::Void) => {
                     emit_reject_reasons.push("ERROR:  Void arguments aren't a thing");
                     "()".to_owned()
@@ -124,15 +388,12 @@ impl<'a> Method<'a> {
                 method::Type::Single(method::BasicType::Float) => "f32".to_owned(),
                 method::Type::Single(method::BasicType::Double) => "f64".to_owned(),
                 method::Type::Single(method::BasicType::Class(class)) => {
-                    if !context.all_classes.contains(class.as_str()) {
+                    if !context.is_class_available(class.as_str()) {
                         emit_reject_reasons.push("ERROR:  missing class for argument type");
                     }
                     param_is_object = true;
                     match context.java_to_rust_path(class, mod_) {
-                        Ok(path) => format!(
-                            "impl __jni_bindgen::std::convert::Into<__jni_bindgen::std::option::Option<&'env {}>>",
-                            path
-                        ),
+                        Ok(path) => path,
                         Err(_) => {
                             emit_reject_reasons
                                 .push("ERROR:  Failed to resolve JNI path to Rust path for argument type");
@@ -141,8 +402,7 @@ impl<'a> Method<'a> {
                     }
                 }
                 method::Type::Array { levels, inner } => {
-                    let mut buffer =
-                        "impl __jni_bindgen::std::convert::Into<__jni_bindgen::std::option::Option<&'env ".to_owned();
+                    let mut buffer = String::new();
                     for _ in 0..(levels - 1) {
                         buffer.push_str("__jni_bindgen::ObjectArray<");
                     }
@@ -156,7 +416,7 @@ impl<'a> Method<'a> {
                         method::BasicType::Float => buffer.push_str("__jni_bindgen::FloatArray"),
                         method::BasicType::Double => buffer.push_str("__jni_bindgen::DoubleArray"),
                         method::BasicType::Class(class) => {
-                            if !context.all_classes.contains(class.as_str()) {
+                            if !context.is_class_available(class.as_str()) {
                                 emit_reject_reasons.push("ERROR:  missing class for argument type");
                             }
                             buffer.push_str("__jni_bindgen::ObjectArray<");
@@ -183,8 +443,6 @@ impl<'a> Method<'a> {
                         buffer.push_str(&context.throwable_rust_path(mod_));
                         buffer.push('>');
                     }
-                    buffer.push_str(">>"); // Option, Into
-
                     param_is_object = true;
                     buffer
                 }
@@ -208,11 +466,56 @@ impl<'a> Method<'a> {
 
             params_decl.push_str(arg_name.as_str());
             params_decl.push_str(": ");
-            params_decl.push_str(arg_type.as_str());
+            if param_is_object {
+                params_decl.push_str(&format!(
+                    "impl __jni_bindgen::std::convert::Into<__jni_bindgen::std::option::Option<&'env {}>>",
+                    arg_type
+                ));
+            } else {
+                params_decl.push_str(arg_type.as_str());
+            }
+
+            impl_params_decl.push_str(", ");
+            impl_params_decl.push_str(arg_name.as_str());
+            impl_params_decl.push_str(": ");
+            if param_is_object {
+                impl_params_decl.push_str(&format!(
+                    "__jni_bindgen::std::option::Option<__jni_bindgen::Local<'env, {}>>",
+                    arg_type
+                ));
+            } else {
+                impl_params_decl.push_str(arg_type.as_str());
+            }
+
+            native_params_decl.push_str(&format!(", {}: ", arg_name));
+            trampoline_params_decl.push_str(&format!(", {}: ", arg_name));
+            if param_is_object {
+                native_params_decl.push_str(&format!(
+                    "__jni_bindgen::std::option::Option<__jni_bindgen::Ref<'env, {}>>",
+                    arg_type
+                ));
+                trampoline_params_decl.push_str(&format!("__jni_bindgen::Argument<{}>", arg_type));
+                trampoline_args.push_str(&format!(", {}.into_ref(__jni_env)", arg_name));
+            } else if is_boolean {
+                native_params_decl.push_str(arg_type.as_str());
+                trampoline_params_decl.push_str("__jni_bindgen::sys::jboolean");
+                trampoline_args.push_str(&format!(", {} != __jni_bindgen::sys::JNI_FALSE", arg_name));
+            } else {
+                native_params_decl.push_str(arg_type.as_str());
+                trampoline_params_decl.push_str(arg_type.as_str());
+                trampoline_args.push_str(&format!(", {}", arg_name));
+            }
         }
 
+        let generic_ret_type = generic_type(signature.as_ref().map(|s| &s.return_type));
+        let check_return =
+            generic_ret_type.is_some() && signature.as_ref().is_some_and(|s| needs_check(&s.return_type));
         let mut ret_decl = match descriptor.return_type() {
             // Contents of fn name<'env>() -> Result<...> {
+            _ if generic_ret_type.is_some() => format!(
+                "__jni_bindgen::std::option::Option<__jni_bindgen::Local<'env, {}>>",
+                generic_ret_type.unwrap()
+            ),
             method::Type::Single(method::BasicType::Void) => "()".to_owned(),
             method::Type::Single(method::BasicType::Boolean) => "bool".to_owned(),
             method::Type::Single(method::BasicType::Byte) => "i8".to_owned(),
@@ -223,7 +526,7 @@ impl<'a> Method<'a> {
             method::Type::Single(method::BasicType::Float) => "f32".to_owned(),
             method::Type::Single(method::BasicType::Double) => "f64".to_owned(),
             method::Type::Single(method::BasicType::Class(class)) => {
-                if !context.all_classes.contains(class.as_str()) {
+                if !context.is_class_available(class.as_str()) {
                     emit_reject_reasons.push("ERROR:  missing class for return type");
                 }
                 match context.java_to_rust_path(class, mod_) {
@@ -259,7 +562,7 @@ impl<'a> Method<'a> {
                     method::BasicType::Float => buffer.push_str("__jni_bindgen::FloatArray"),
                     method::BasicType::Double => buffer.push_str("__jni_bindgen::DoubleArray"),
                     method::BasicType::Class(class) => {
-                        if !context.all_classes.contains(class.as_str()) {
+                        if !context.is_class_available(class.as_str()) {
                             emit_reject_reasons.push("ERROR:  missing class for return type");
                         }
                         buffer.push_str("__jni_bindgen::ObjectArray<");
@@ -315,8 +618,70 @@ impl<'a> Method<'a> {
             }
         }
 
+        let typed_exceptions = match context.config.codegen.checked_exceptions {
+            config::toml::CheckedExceptionStyle::Throwable => false,
+            config::toml::CheckedExceptionStyle::Typed => true,
+            config::toml::CheckedExceptionStyle::__NonExhaustive => {
+                emit_reject_reasons.push("ERROR:  CheckedExceptionStyle::__NonExhaustive is invalid, silly goose!");
+                false
+            }
+        };
+        let missing_member_results = match context.config.codegen.missing_members {
+            config::toml::MissingMemberStyle::Panic => false,
+            config::toml::MissingMemberStyle::Result => true,
+            config::toml::MissingMemberStyle::__NonExhaustive => {
+                emit_reject_reasons.push("ERROR:  MissingMemberStyle::__NonExhaustive is invalid, silly goose!");
+                false
+            }
+        };
+
         let emit_reject_reasons = emit_reject_reasons; // Freeze
-        let indent = if emit_reject_reasons.is_empty() {
+
+        // Implicit statics have no Env for the locals they'd return to be tied to - nothing stops the caller from picking
+        // an `'env` outliving the thread's attachment or local frame - so they return global references instead.
+        let implicit_env = matches!(target, Target::Binding)
+            && (self.java.is_constructor() || self.java.is_static())
+            && context.config.codegen.static_env == config::toml::StaticEnvStyle::Implicit;
+        let local_ret_decl = ret_decl.clone();
+        if implicit_env && ret_method_fragment == "object" {
+            ret_decl = ret_decl.replacen("__jni_bindgen::Local<'env, ", "__jni_bindgen::Global<", 1);
+        }
+
+        // Rejected methods are emitted commented out (if at all), so don't bother with an error enum for them.
+        // Rust implementations may throw anything:  a proxy wraps undeclared checked exceptions itself, and Java doesn't
+        // check what natives throw.  Error enums hold locals, so implicit statics make do with a global `Throwable`.
+        let error_enum =
+            if typed_exceptions && emit_reject_reasons.is_empty() && !for_proxy && !for_natives && !implicit_env {
+                self.emit_error_enum(context, indent, mod_, &method_name, items)?
+            } else {
+                None
+            };
+        let error_decl = match error_enum.as_ref() {
+            Some(name) => format!("{}<'env>", name),
+            None if implicit_env => format!("__jni_bindgen::Global<{}>", context.throwable_rust_path(mod_)),
+            None => format!("__jni_bindgen::Local<'env, {}>", context.throwable_rust_path(mod_)),
+        };
+
+        let indent = if !matches!(target, Target::Binding) {
+            if !emit_reject_reasons.is_empty() && for_natives {
+                // Java calls to a native left out would only fail at runtime, with an UnsatisfiedLinkError.
+                return io_data_err!(
+                    "Unable to bind native method {}.{}{}:  {}",
+                    self.class.path.as_str(),
+                    self.java.name,
+                    self.java.descriptor_str(),
+                    emit_reject_reasons.join(", ")
+                );
+            }
+            if !emit_reject_reasons.is_empty() {
+                return Ok(());
+            }
+            if let Target::NativeExport { .. } = target {
+                indent.to_owned() // Not nested in anything
+            } else {
+                format!("{}    ", indent)
+            }
+        } else if emit_reject_reasons.is_empty() {
             format!("{}        ", indent)
         } else {
             if !context.config.codegen.keep_rejected_emits {
@@ -324,8 +689,120 @@ impl<'a> Method<'a> {
             }
             format!("{}        // ", indent)
         };
-        let access = if self.java.is_public() { "pub " } else { "" };
-        let attributes = (if self.java.deprecated { "#[deprecated] " } else { "" }).to_string();
+        let access = if self.java.is_public() && matches!(target, Target::Binding) {
+            "pub "
+        } else {
+            ""
+        };
+        let mut attributes = ApiLevels::attribute(self.api_levels, context);
+        if self.java.deprecated && !for_proxy && !for_natives && !matches!(target, Target::Verify) {
+            attributes.push_str("#[deprecated] ");
+        }
+
+        if let Target::ProxyDispatch { impl_trait } = target {
+            let args: Vec<String> = (0..descriptor.arguments().count())
+                .map(|i| format!(", __jni_arg{}", i))
+                .collect();
+            writeln!(
+                out,
+                "{}{}if __jni_invocation.is({}, {}) {{",
+                indent,
+                attributes,
+                emit_cstr(self.java.name.as_str()),
+                emit_cstr(self.java.descriptor_str())
+            )?;
+            for i in 0..descriptor.arguments().count() {
+                writeln!(
+                    out,
+                    "{}    let __jni_arg{} = match __jni_invocation.arg({}) {{ Ok(arg) => arg, Err(response) => return response }};",
+                    indent, i, i
+                )?;
+            }
+            writeln!(
+                out,
+                "{}    return __jni_invocation.returns({}::{}(&__jni_implementation, __jni_invocation.env(){}));",
+                indent,
+                impl_trait,
+                method_name,
+                args.join("")
+            )?;
+            writeln!(out, "{}}}", indent)?;
+            return Ok(());
+        }
+
+        if let Target::Verify = target {
+            writeln!(
+                out,
+                "{}{}__jni_verify.{}({}, {});",
+                indent,
+                attributes,
+                if self.java.is_static() {
+                    "static_method"
+                } else {
+                    "method"
+                },
+                emit_cstr(self.java.name.as_str()),
+                emit_cstr(self.java.descriptor_str())
+            )?;
+            return Ok(());
+        }
+
+        if let Target::NativeTrampoline { .. } | Target::NativeExport { .. } = target {
+            let trampoline_ret = match ret_method_fragment {
+                "void" => String::new(),
+                "boolean" => " -> __jni_bindgen::sys::jboolean".to_owned(),
+                "object" => " -> __jni_bindgen::sys::jobject".to_owned(),
+                _ => format!(" -> {}", ret_decl),
+            };
+            writeln!(out)?;
+            let callee = match target {
+                Target::NativeExport {
+                    cfg,
+                    natives_trait,
+                    implementation,
+                    symbol,
+                } => {
+                    writeln!(out, "{}{}{}#[no_mangle]", indent, cfg, attributes)?;
+                    writeln!(
+                        out,
+                        "{}pub extern \"system\" fn {}<'env>({}){} {{",
+                        indent, symbol, trampoline_params_decl, trampoline_ret
+                    )?;
+                    format!("<{} as {}>", implementation, natives_trait)
+                }
+                Target::NativeTrampoline { natives_trait } => {
+                    writeln!(
+                        out,
+                        "{}{}extern \"system\" fn {}<'env, N: {}>({}){} {{",
+                        indent, attributes, method_name, natives_trait, trampoline_params_decl, trampoline_ret
+                    )?;
+                    "N".to_owned()
+                }
+                _ => unreachable!(),
+            };
+            let mut call = format!("{}::{}({})", callee, method_name, trampoline_args);
+            if trampoline_args.contains(".into_ref(") {
+                // Sound as Java called us with these arguments and env
+                call = format!("unsafe {{ {} }}", call);
+            }
+            // Catches panics, which mustn't unwind into Java
+            writeln!(out, "{}    __jni_bindgen::native_call(__jni_env, || {})", indent, call)?;
+            writeln!(out, "{}}}", indent)?;
+
+            if let Target::NativeExport { .. } = target {
+                return Ok(());
+            }
+            writeln!(
+                items,
+                "{}{}__jni_natives.push(__jni_bindgen::sys::JNINativeMethod {{ name: {}.as_ptr() as *mut _, signature: {}.as_ptr() as *mut _, fnPtr: {}::<N> as *mut _ }});",
+                indent,
+                attributes,
+                emit_cstr(self.java.name.as_str()),
+                emit_cstr(self.java.descriptor_str()),
+                method_name
+            )?;
+            return Ok(());
+        }
 
         writeln!(out)?;
         for reason in &emit_reject_reasons {
@@ -336,17 +813,56 @@ impl<'a> Method<'a> {
         } else {
             writeln!(out, "{}/// {}", indent, self.java.name.as_str())?;
         }
+        let mut generics = String::from("'env");
+        for name in method_type_variables
+            .iter()
+            .filter(|name| used_type_variables.contains(*name))
+        {
+            generics.push_str(&format!(", {}: __jni_bindgen::ReferenceType", name));
+        }
+        if let Target::Impl = target {
+            writeln!(
+                out,
+                "{}{}fn {}<{}>({}) -> __jni_bindgen::std::result::Result<{}, {}>;",
+                indent, attributes, method_name, generics, impl_params_decl, ret_decl, error_decl
+            )?;
+            return Ok(());
+        }
+        if let Target::Native = target {
+            writeln!(
+                out,
+                "{}{}fn {}<{}>({}) -> __jni_bindgen::std::result::Result<{}, {}>;",
+                indent, attributes, method_name, generics, native_params_decl, ret_decl, error_decl
+            )?;
+            return Ok(());
+        }
+        let mut result_decl = format!("__jni_bindgen::std::result::Result<{}, {}>", ret_decl, error_decl);
+        if missing_member_results {
+            result_decl = format!(
+                "__jni_bindgen::std::result::Result<{}, __jni_bindgen::MissingMember>",
+                result_decl
+            );
+        }
         writeln!(
             out,
-            "{}{}{}fn {}<'env>({}) -> __jni_bindgen::std::result::Result<{}, __jni_bindgen::Local<'env, {}>> {{",
-            indent,
-            attributes,
-            access,
-            method_name,
-            params_decl,
-            ret_decl,
-            context.throwable_rust_path(mod_)
+            "{}{}{}fn {}<{}>({}) -> {} {{",
+            indent, attributes, access, method_name, generics, params_decl, result_decl
         )?;
+        if let Target::Ext { interface } = target {
+            let args: Vec<String> = (0..descriptor.arguments().count())
+                .map(|i| format!("arg{}", i))
+                .collect();
+            writeln!(
+                out,
+                "{}    __jni_bindgen::std::convert::AsRef::<{}>::as_ref(self).{}({})",
+                indent,
+                interface,
+                method_name,
+                args.join(", ")
+            )?;
+            writeln!(out, "{}}}", indent)?;
+            return Ok(());
+        }
         writeln!(
             out,
             "{}    // class.path == {:?}, java.flags == {:?}, .name == {:?}, .descriptor == {:?}",
@@ -361,6 +877,9 @@ impl<'a> Method<'a> {
         if self.java.is_constructor() || self.java.is_static() {
             match context.config.codegen.static_env {
                 config::toml::StaticEnvStyle::Explicit => {}
+                config::toml::StaticEnvStyle::Implicit => {
+                    writeln!(out, "{}        let __jni_env = __jni_bindgen::Env::ambient();", indent)?
+                }
                 config::toml::StaticEnvStyle::__NonExhaustive => writeln!(out, "{}    let __jni_env = ...?;", indent)?, // XXX
             };
         } else {
@@ -371,38 +890,311 @@ impl<'a> Method<'a> {
             )?;
         }
 
+        let static_ = if self.java.is_static() { "static_" } else { "" };
+        let (try_, question) = if missing_member_results {
+            ("try_", "?")
+        } else {
+            ("", "")
+        };
+        let lookup = if context.config.codegen.cache_lookups {
+            writeln!(
+                out,
+                "{}        static __JNI_METHOD: __jni_bindgen::MethodCache = __jni_bindgen::MethodCache::new();",
+                indent
+            )?;
+            format!("__JNI_METHOD.{}require_class_{}method(__jni_env, ", try_, static_)
+        } else {
+            format!("__jni_env.{}require_class_{}method(", try_, static_)
+        };
         writeln!(
             out,
-            "{}        let (__jni_class, __jni_method) = __jni_env.require_class_{}method({}, {}, {});",
+            "{}        let (__jni_class, __jni_method) = {}{}, {}, {}){};",
             indent,
-            if self.java.is_static() { "static_" } else { "" },
+            lookup,
             emit_cstr(self.class.path.as_str()),
             emit_cstr(self.java.name.as_str()),
-            emit_cstr(self.java.descriptor_str())
+            emit_cstr(self.java.descriptor_str()),
+            question
         )?;
 
-        if self.java.is_constructor() {
-            writeln!(
-                out,
-                "{}        __jni_env.new_object_a(__jni_class, __jni_method, __jni_args.as_ptr())",
-                indent
-            )?;
-        } else if self.java.is_static() {
+        let map_err = match error_enum.as_ref() {
+            Some(name) => format!(".map_err({}::from)", name),
+            None => String::new(),
+        };
+        let mut call = if self.java.is_constructor() {
+            format!(
+                "__jni_env.new_object_a(__jni_class, __jni_method, __jni_args.as_ptr()){}",
+                map_err
+            )
+        } else {
+            let mut call = if self.java.is_static() {
+                format!(
+                    "__jni_env.call_static_{}_method_a(__jni_class, __jni_method, __jni_args.as_ptr())",
+                    ret_method_fragment
+                )
+            } else {
+                format!(
+                    "__jni_env.call_{}_method_a(self.0.object, __jni_method, __jni_args.as_ptr())",
+                    ret_method_fragment
+                )
+            };
+            if check_return {
+                call = format!("__jni_env.check_return({})", call);
+            }
+            call.push_str(&map_err);
+            call
+        };
+        if implicit_env {
+            // Nothing else pins down the `R` and `E` the call returns locals of.
+            if self.java.is_constructor() {
+                call.push_str(&format!(".map(|__jni_ret: {}| __jni_ret.as_global())", local_ret_decl));
+            } else if ret_method_fragment == "object" {
+                call.push_str(&format!(
+                    ".map(|__jni_ret: {}| __jni_ret.map(|__jni_ret| __jni_ret.as_global()))",
+                    local_ret_decl
+                ));
+            }
+            call.push_str(&format!(
+                ".map_err(|__jni_err: __jni_bindgen::Local<'env, {}>| __jni_err.as_global())",
+                context.throwable_rust_path(mod_)
+            ));
+        }
+        if missing_member_results {
+            writeln!(out, "{}        Ok({})", indent, call)?;
+        } else {
+            writeln!(out, "{}        {}", indent, call)?;
+        }
+        writeln!(out, "{}    }}", indent)?;
+        writeln!(out, "{}}}", indent)?;
+        Ok(())
+    }
+}
+
+/// What [Method::write] emits the method as.
+#[derive(Clone, Copy)]
+enum Target<'t> {
+    /// A method of the class's own bindings.
+    Binding,
+    /// A default method of `{Interface}Ext`, forwarding to `interface`.
+    Ext { interface: &'t str },
+    /// A required method of `{Interface}Impl`.
+    Impl,
+    /// A check in `{Interface}::from_rust`'s proxy, forwarding matching calls to `impl_trait`.
+    ProxyDispatch { impl_trait: &'t str },
+    /// A required function of `{Class}Natives`.
+    Native,
+    /// An `extern "system"` fn in `{Class}::register_natives`, forwarding to `natives_trait`.
+    NativeTrampoline { natives_trait: &'t str },
+    /// A `#[no_mangle]` fn named `symbol`, forwarding to `implementation`'s `natives_trait` function.
+    NativeExport {
+        cfg: &'t str,
+        natives_trait: &'t str,
+        implementation: &'t str,
+        symbol: &'t str,
+    },
+    /// A lookup of the method in `{Class}`'s part of `verify_bindings`.
+    Verify,
+}
+
+impl<'a> Method<'a> {
+    /// Parses the method's generic signature, returning the type variables in scope for it, the Rust names of those the
+    /// method itself declares, and the signature - or just the class's type variables if the signature is missing,
+    /// can't be expressed in Rust, or doesn't line up with the descriptor (e.g. the implicit outer `this` parameter of
+    /// inner class constructors is missing from their signatures.)
+    ///
+    /// Type variables the method declares itself are left out of scope (and thus erased) unless
+    /// `method_type_parameters`.
+    fn generic_signature(
+        &self,
+        arguments: usize,
+        method_type_parameters: bool,
+    ) -> (TypeVariables, Vec<String>, Option<MethodSignature>) {
+        // Static methods and constructors are implemented on the raw type, so `Class::new()` doesn't need annotations.
+        let type_variables = if self.java.is_static() || self.java.is_constructor() {
+            TypeVariables::default()
+        } else {
+            self.class_type_variables.clone()
+        };
+        let class_count = type_variables.rust_names().count();
+
+        let signature = match self.signature.map(MethodSignature::parse) {
+            Some(Ok(signature)) if signature.parameters.len() == arguments => signature,
+            _ => return (type_variables, Vec::new(), None),
+        };
+        if !method_type_parameters {
+            return (type_variables, Vec::new(), Some(signature));
+        }
+        let mut declared = type_variables.clone();
+        if declared.declare(&signature.type_parameters).is_none() {
+            return (type_variables, Vec::new(), None);
+        }
+        let method_type_variables = declared.rust_names().skip(class_count).map(String::from).collect();
+        (declared, method_type_variables, Some(signature))
+    }
+
+    /// Emits a `{Class}_{method}_Error<'env>` enum with a variant per declared exception, returning its name - or
+    /// returns [None] if none of the declared exceptions are available to bind.
+    fn emit_error_enum(
+        &self,
+        context: &Context,
+        indent: &str,
+        mod_: &str,
+        method_name: &str,
+        out: &mut impl io::Write,
+    ) -> io::Result<Option<String>> {
+        // Most derived first, so e.g. a FileNotFoundException isn't matched as its IOException superclass.
+        let mut exceptions: Vec<&str> = Vec::new();
+        for exception in self.exceptions {
+            let available = context.is_class_available(exception)
+                && match (self.api_levels, context.class_api_levels.get(exception.as_str())) {
+                    (Some(method_levels), Some(exception_levels)) => exception_levels.covers(method_levels),
+                    _ => true,
+                };
+            if !available || exceptions.contains(&exception.as_str()) {
+                continue;
+            }
+            let position = exceptions
+                .iter()
+                .position(|&e| context.is_subclass_of(exception, e))
+                .unwrap_or(exceptions.len());
+            exceptions.insert(position, exception.as_str());
+        }
+
+        let mut variants = Vec::new();
+        for exception in exceptions {
+            let name = Struct::name_for(context, class::Id(exception)).map_err(|e| io_data_error!("{}", e))?;
+            let path = context
+                .java_to_rust_path(class::Id(exception), mod_)
+                .map_err(|e| io_data_error!("{}", e))?;
+            // Two exceptions with the same name from different packages:  the second one is left to `Other`.
+            if name != "Other" && !variants.iter().any(|(n, _)| n == &name) {
+                variants.push((name, path));
+            }
+        }
+        if variants.is_empty() {
+            return Ok(None);
+        }
+
+        let struct_name = Struct::name_for(context, self.class.path.as_id()).map_err(|e| io_data_error!("{}", e))?;
+        let name = format!("{}_{}_Error", struct_name, method_name.trim_start_matches("r#"));
+        let throwable = context.throwable_rust_path(mod_);
+        let cfg = ApiLevels::attribute(self.api_levels, context);
+
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}/// The exceptions {}.{} declares it `throws`, or [Other]({}::Other) for anything else.",
+            indent,
+            self.class.path.as_str().replace('/', "."),
+            self.java.name,
+            name
+        )?;
+        writeln!(out, "{}{}pub enum {}<'env> {{", indent, cfg, name)?;
+        for (variant, path) in &variants {
+            writeln!(out, "{}    {}(__jni_bindgen::Local<'env, {}>),", indent, variant, path)?;
+        }
+        writeln!(out, "{}    Other(__jni_bindgen::Local<'env, {}>),", indent, throwable)?;
+        writeln!(out, "{}}}", indent)?;
+
+        writeln!(out)?;
+        writeln!(out, "{}{}impl<'env> {}<'env> {{", indent, cfg, name)?;
+        writeln!(out, "{}    pub fn as_throwable(&self) -> &{} {{", indent, throwable)?;
+        writeln!(out, "{}        match self {{", indent)?;
+        for (variant, _) in &variants {
+            writeln!(out, "{}            {}::{}(e) => e,", indent, name, variant)?;
+        }
+        writeln!(out, "{}            {}::Other(e) => e,", indent, name)?;
+        writeln!(out, "{}        }}", indent)?;
+        writeln!(out, "{}    }}", indent)?;
+        writeln!(out, "{}}}", indent)?;
+
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}{}impl<'env> __jni_bindgen::std::convert::From<__jni_bindgen::Local<'env, {}>> for {}<'env> {{",
+            indent, cfg, throwable, name
+        )?;
+        writeln!(
+            out,
+            "{}    fn from(thrown: __jni_bindgen::Local<'env, {}>) -> Self {{",
+            indent, throwable
+        )?;
+        for (variant, path) in &variants {
             writeln!(
                 out,
-                "{}        __jni_env.call_static_{}_method_a(__jni_class, __jni_method, __jni_args.as_ptr())",
-                indent, ret_method_fragment
+                "{}        if let Ok(e) = thrown.cast::<{}>() {{ return {}::{}(e); }}",
+                indent, path, name, variant
             )?;
-        } else {
+        }
+        writeln!(out, "{}        {}::Other(thrown)", indent, name)?;
+        writeln!(out, "{}    }}", indent)?;
+        writeln!(out, "{}}}", indent)?;
+
+        // Lets `?` convert back to a plain Throwable in functions that don't care which exception was thrown.
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}{}impl<'env> __jni_bindgen::std::convert::From<{}<'env>> for __jni_bindgen::Local<'env, {}> {{",
+            indent, cfg, name, throwable
+        )?;
+        writeln!(out, "{}    fn from(error: {}<'env>) -> Self {{", indent, name)?;
+        writeln!(out, "{}        let (env, object) = match error {{", indent)?;
+        for (variant, _) in &variants {
             writeln!(
                 out,
-                "{}        __jni_env.call_{}_method_a(self.0.object, __jni_method, __jni_args.as_ptr())",
-                indent, ret_method_fragment
+                "{}            {}::{}(e) => (e.env(), e.into_raw()),",
+                indent, name, variant
             )?;
         }
+        writeln!(
+            out,
+            "{}            {}::Other(e) => (e.env(), e.into_raw()),",
+            indent, name
+        )?;
+        writeln!(out, "{}        }};", indent)?;
+        writeln!(
+            out,
+            "{}        unsafe {{ __jni_bindgen::Local::from_raw(env, object) }}",
+            indent
+        )?;
         writeln!(out, "{}    }}", indent)?;
         writeln!(out, "{}}}", indent)?;
-        Ok(())
+
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}{}impl<'env> __jni_bindgen::std::convert::From<{}<'env>> for __jni_bindgen::JavaException {{",
+            indent, cfg, name
+        )?;
+        writeln!(out, "{}    fn from(error: {}<'env>) -> Self {{", indent, name)?;
+        writeln!(
+            out,
+            "{}        __jni_bindgen::JavaException::new(error.as_throwable())",
+            indent
+        )?;
+        writeln!(out, "{}    }}", indent)?;
+        writeln!(out, "{}}}", indent)?;
+
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}{}impl<'env> __jni_bindgen::std::fmt::Debug for {}<'env> {{",
+            indent, cfg, name
+        )?;
+        writeln!(
+            out,
+            "{}    fn fmt(&self, f: &mut __jni_bindgen::std::fmt::Formatter<'_>) -> __jni_bindgen::std::fmt::Result {{",
+            indent
+        )?;
+        writeln!(
+            out,
+            "{}        __jni_bindgen::std::fmt::Debug::fmt(self.as_throwable(), f)",
+            indent
+        )?;
+        writeln!(out, "{}    }}", indent)?;
+        writeln!(out, "{}}}", indent)?;
+
+        Ok(Some(name))
     }
 }
 
@@ -411,3 +1203,86 @@ fn emit_cstr(s: &str) -> String {
     s.insert_str(s.len() - 1, "\\0");
     s
 }
+
+#[test]
+fn generic_returns_checked() {
+    use super::test_util::*;
+
+    let mut emit = java_lang();
+    emit.push(class(
+        ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT,
+        "java/util/function/Function",
+        &[],
+        Some("<T:Ljava/lang/Object;R:Ljava/lang/Object;>Ljava/lang/Object;"),
+        &[
+            generic_member(
+                ACC_PUBLIC | ACC_ABSTRACT,
+                "apply",
+                "(Ljava/lang/Object;)Ljava/lang/Object;",
+                "(TT;)TR;",
+            ),
+            generic_member(
+                ACC_PUBLIC,
+                "andThen",
+                "(Ljava/util/function/Function;)Ljava/util/function/Function;",
+                "<V:Ljava/lang/Object;>(Ljava/util/function/Function<-TR;+TV;>;)Ljava/util/function/Function<TT;TV;>;",
+            ),
+        ],
+    ));
+    let out = generate("", &emit, &[]);
+
+    // `R` is whatever the caller picked, so the returned object is checked...
+    assert!(out.contains("public interface Function<T = super::super::lang::Object, R = super::super::lang::Object>"));
+    assert!(out.contains(
+        "__jni_env.check_return(__jni_env.call_object_method_a(self.0.object, __jni_method, __jni_args.as_ptr()))\n"
+    ));
+    // ...while a `Function<T, V>` is a `Function` regardless of `V`, and checks its own `apply`.
+    assert!(out.contains("-> __jni_bindgen::std::result::Result<__jni_bindgen::std::option::Option<__jni_bindgen::Local<'env, Function<T, V>>>"));
+    assert_eq!(out.matches("check_return").count(), 1);
+}
+
+#[test]
+fn implicit_statics_return_globals() {
+    use super::test_util::*;
+
+    let mut emit = java_lang_for_preamble();
+    emit.push(class(
+        ACC_PUBLIC,
+        "com/example/Ambient",
+        &[],
+        None,
+        &[
+            member(ACC_PUBLIC, "<init>", "()V"),
+            member(ACC_PUBLIC | ACC_STATIC, "make", "()Ljava/lang/Object;"),
+            member(ACC_PUBLIC | ACC_STATIC, "count", "()I"),
+            member(ACC_PUBLIC | ACC_STATIC, "shared", "Ljava/lang/Object;"),
+            member(ACC_PUBLIC, "get", "()Ljava/lang/Object;"),
+        ],
+    ));
+    // The preamble calls `Throwable::getMessage` and friends by their Java names.
+    let toml = "[codegen]\nstatic_env = \"implicit\"\nmethod_naming_style = \"java\"\n";
+    let out = generate(toml, &emit, &[]);
+    assert_compiles("implicit_statics_return_globals", &out);
+
+    // Nothing ties the ambient env's locals to a scope, so statics, constructors and static getters return globals...
+    let throwable = "__jni_bindgen::Global<super::super::java::lang::Throwable>";
+    assert!(out.contains(&format!(
+        "pub fn new<'env>() -> __jni_bindgen::std::result::Result<__jni_bindgen::Global<Self>, {}>",
+        throwable
+    )));
+    let map_err = ".map_err(|__jni_err: __jni_bindgen::Local<'env, super::super::java::lang::Throwable>| __jni_err.as_global())\n";
+    assert!(out.contains(&format!(
+        ".map(|__jni_ret: __jni_bindgen::Local<'env, Self>| __jni_ret.as_global()){}",
+        map_err
+    )));
+    assert!(out.contains(&format!("pub fn make<'env>() -> __jni_bindgen::std::result::Result<__jni_bindgen::std::option::Option<__jni_bindgen::Global<super::super::java::lang::Object>>, {}>", throwable)));
+    assert!(out.contains(&format!(".map(|__jni_ret: __jni_bindgen::std::option::Option<__jni_bindgen::Local<'env, super::super::java::lang::Object>>| __jni_ret.map(|__jni_ret| __jni_ret.as_global())){}", map_err)));
+    assert!(out.contains(&format!(
+        "pub fn count<'env>() -> __jni_bindgen::std::result::Result<i32, {}>",
+        throwable
+    )));
+    assert!(out.contains("pub fn shared<'env>() -> __jni_bindgen::std::option::Option<__jni_bindgen::Global<super::super::java::lang::Object>>"));
+    assert!(out.contains("env.get_static_object_field(__jni_class, __jni_field).map(|value: __jni_bindgen::Local<'env, super::super::java::lang::Object>| value.as_global())\n"));
+    // ...while instance methods borrow their env from `self`.
+    assert!(out.contains("pub fn get<'env>(&'env self) -> __jni_bindgen::std::result::Result<__jni_bindgen::std::option::Option<__jni_bindgen::Local<'env, super::super::java::lang::Object>>, __jni_bindgen::Local<'env, super::super::java::lang::Throwable>>"));
+}
@@ -1,11 +1,15 @@
 //! Rust generation logic
 
+mod api_levels;
 mod context;
 mod fields;
+mod generics;
 mod known_docs_url;
 mod methods;
 mod modules;
 mod preamble;
 mod structs;
+#[cfg(test)]
+mod test_util;
 
 pub use context::Context;
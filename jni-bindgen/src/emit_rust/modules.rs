@@ -1,8 +1,11 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
+use super::api_levels::ApiLevels;
 use super::structs::Struct;
 use crate::emit_rust::Context;
+use crate::util;
 
 #[derive(Debug, Default)]
 pub(crate) struct Module {
@@ -30,4 +33,86 @@ impl Module {
 
         Ok(())
     }
+
+    /// Writes the `pub mod ...;` + `include!(...);` declarations pulling in the files written by [Module::write_files].
+    pub(crate) fn write_declarations(&self, out: &mut impl Write) -> io::Result<()> {
+        if !self.modules.is_empty() {
+            writeln!(out)?;
+        }
+        for name in self.modules.keys() {
+            writeln!(out, "pub mod {};", name)?;
+        }
+
+        if !self.structs.is_empty() {
+            writeln!(out)?;
+        }
+        for name in self.structs.keys() {
+            writeln!(out, "include!({:?});", file_name(name))?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the calls of `verify_bindings` (at the crate root) to each struct's part of it.
+    pub(crate) fn write_verify_calls(&self, context: &Context, out: &mut impl Write) -> io::Result<()> {
+        for module in self.modules.values() {
+            module.write_verify_calls(context, out)?;
+        }
+
+        for structure in self.structs.values() {
+            let cfg = ApiLevels::attribute(context.class_api_levels.get(structure.java.path.as_str()), context);
+            let mod_ = structure.rust.mod_.as_str();
+            let separator = if mod_.is_empty() { "" } else { "::" };
+            writeln!(
+                out,
+                "    {}{}{}{}(&mut __jni_verify);",
+                cfg,
+                mod_,
+                separator,
+                structure.verify_fn_name()
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes one `.rs` file per struct into `dir`, and a `mod.rs` + subdirectory per submodule.
+    pub(crate) fn write_files(&self, context: &Context, dir: &Path, written: &mut HashSet<PathBuf>) -> io::Result<()> {
+        for (name, structure) in self.structs.iter() {
+            let mut out = Vec::with_capacity(4096);
+            util::write_marker_comment(&mut out)?;
+            structure.write(context, "", &mut out)?;
+
+            let path = dir.join(file_name(name));
+            util::write_generated(context, &path, &out[..])?;
+            written.insert(path);
+        }
+
+        for (name, module) in self.modules.iter() {
+            let dir = dir.join(strip_raw(name));
+
+            let mut out = Vec::with_capacity(4096);
+            util::write_marker_comment(&mut out)?;
+            writeln!(out)?;
+            writeln!(out, "use super::__jni_bindgen;")?;
+            module.write_declarations(&mut out)?;
+
+            let path = dir.join("mod.rs");
+            util::write_generated(context, &path, &out[..])?;
+            written.insert(path);
+
+            module.write_files(context, &dir, written)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn file_name(struct_name: &str) -> String {
+    format!("{}.rs", strip_raw(struct_name))
+}
+
+/// `r#match` lives in `match.rs` / `match/mod.rs`, same as rustc expects for `mod r#match;`.
+fn strip_raw(name: &str) -> &str {
+    name.strip_prefix("r#").unwrap_or(name)
 }
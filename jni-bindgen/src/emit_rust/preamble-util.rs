@@ -0,0 +1,178 @@
+mod util {
+    use std::char::{self, DecodeUtf16Error};
+    use std::ffi::CStr;
+    use std::fmt;
+
+    use jni_glue::{
+        decode_modified_utf8, encode_modified_utf8, Env, Local, MissingMember, ReferenceType, StringChars,
+        StringUtfChars, ThrowableType,
+    };
+
+    use super::java::lang::{String as JString, Throwable};
+
+    /// Uniformly handles the results of bindings generated with or without `missing_members = "result"`.
+    trait FlattenResult<T> {
+        fn flatten_result(self) -> Result<T, ()>;
+    }
+
+    impl<'env, T, E: ReferenceType> FlattenResult<T> for Result<T, Local<'env, E>> {
+        fn flatten_result(self) -> Result<T, ()> {
+            self.map_err(|_| ())
+        }
+    }
+
+    impl<T, E> FlattenResult<T> for Result<Result<T, E>, MissingMember> {
+        fn flatten_result(self) -> Result<T, ()> {
+            self.map_err(|_| ())?.map_err(|_| ())
+        }
+    }
+
+    impl fmt::Debug for Throwable {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "java::lang::Throwable")?;
+
+            match self.getMessage().flatten_result() {
+                Ok(Some(message)) => writeln!(f, "    getMessage:            {:?}", message)?,
+                Ok(None) => writeln!(f, "    getMessage:            N/A (returned null)")?,
+                Err(_) => writeln!(f, "    getMessage:            N/A (threw an exception!)")?,
+            }
+
+            match self.getLocalizedMessage().flatten_result() {
+                Ok(Some(message)) => writeln!(f, "    getLocalizedMessage:   {:?}", message)?,
+                Ok(None) => writeln!(f, "    getLocalizedMessage:   N/A (returned null)")?,
+                Err(_) => writeln!(f, "    getLocalizedMessage:   N/A (threw an exception!)")?,
+            }
+
+            match self.getStackTrace().flatten_result() {
+                Err(_) => writeln!(f, "    getStackTrace:         N/A (threw an exception!)")?,
+                Ok(None) => writeln!(f, "    getStackTrace:         N/A (returned null)")?,
+                Ok(Some(stack_trace)) => {
+                    writeln!(f, "    getStackTrace:")?;
+                    for frame in stack_trace.iter() {
+                        match frame {
+                            None => writeln!(f, "        N/A (frame was null)")?,
+                            Some(frame) => {
+                                let file_line = match (
+                                    frame.getFileName().flatten_result(),
+                                    frame.getLineNumber().flatten_result(),
+                                ) {
+                                    (Ok(Some(file)), Ok(line)) => {
+                                        format!("{}({}):", file.to_string_lossy(), line)
+                                    }
+                                    (Ok(Some(file)), _) => format!("{}:", file.to_string_lossy()),
+                                    (_, _) => "N/A (getFileName threw an exception or returned null)".to_owned(),
+                                };
+
+                                let class_method = match (
+                                    frame.getClassName().flatten_result(),
+                                    frame.getMethodName().flatten_result(),
+                                ) {
+                                    (Ok(Some(class)), Ok(Some(method))) => {
+                                        format!("{}.{}", class.to_string_lossy(), method.to_string_lossy())
+                                    }
+                                    (Ok(Some(class)), _) => class.to_string_lossy(),
+                                    (_, Ok(Some(method))) => method.to_string_lossy(),
+                                    (_, _) => "N/A (getClassName + getMethodName threw exceptions or returned null)"
+                                        .to_owned(),
+                                };
+
+                                writeln!(f, "        {:120}{}", file_line, class_method)?;
+                            }
+                        }
+                    }
+                }
+            }
+
+            // For getCause() and getSuppressed() as well, see jni_glue::JavaException.
+
+            Ok(())
+        }
+    }
+
+    /// Strings up to this many characters long are read with GetStringRegion into a buffer on the stack, instead of being
+    /// pinned or copied with GetStringChars.
+    const SMALL_STRING_LEN: usize = 64;
+
+    impl JString {
+        /// Create new local string from an Env + AsRef<str>, via Modified UTF-8 - which mostly-ASCII strings already are.
+        pub fn from_env_str<'env, S: AsRef<str>>(env: Env<'env>, string: S) -> Local<'env, Self> {
+            let string = encode_modified_utf8(string.as_ref());
+            unsafe { Self::from_env_modified_utf8_unchecked(env, &string) }
+        }
+
+        /// Create new local string from an Env + Modified UTF-8 (such as a `c"literal"` without NULs or supplementary
+        /// characters), without re-encoding it.  Panics if `string` isn't valid Modified UTF-8.
+        pub fn from_env_modified_utf8<'env>(env: Env<'env>, string: &CStr) -> Local<'env, Self> {
+            assert!(
+                decode_modified_utf8(string.to_bytes()).is_ok(),
+                "Invalid Modified UTF-8"
+            );
+            unsafe { Self::from_env_modified_utf8_unchecked(env, string) }
+        }
+
+        unsafe fn from_env_modified_utf8_unchecked<'env>(env: Env<'env>, string: &CStr) -> Local<'env, Self> {
+            let string = env.new_string_utf(string.as_ptr());
+            Local::from_raw(env, string)
+        }
+
+        fn string_chars(&self) -> StringChars {
+            unsafe {
+                let env = Env::from_raw(self.0.env);
+                StringChars::from_env_jstring(env, self.0.object)
+            }
+        }
+
+        /// Returns the string as Modified UTF-8, with env.GetStringUTFChars.  Cheaper than [to_string](Self::to_string)
+        /// for long, mostly-ASCII strings, which [StringUtfChars::to_str] borrows without decoding.
+        pub fn utf_chars(&self) -> StringUtfChars {
+            unsafe {
+                let env = Env::from_raw(self.0.env);
+                StringUtfChars::from_env_jstring(env, self.0.object)
+            }
+        }
+
+        fn with_chars<R>(&self, f: impl FnOnce(&[u16]) -> R) -> R {
+            let env = unsafe { Env::from_raw(self.0.env) };
+            let len = unsafe { env.get_string_length(self.0.object) };
+            if len as usize <= SMALL_STRING_LEN {
+                let mut chars = [0; SMALL_STRING_LEN];
+                unsafe { env.get_string_region(self.0.object, 0, len, chars.as_mut_ptr()) };
+                f(&chars[..len as usize])
+            } else {
+                f(self.string_chars().chars())
+            }
+        }
+
+        /// Returns a new [Ok]\([String]\), or an [Err]\([DecodeUtf16Error]\) if if it contained any invalid UTF16.
+        ///
+        /// [Ok]:                       https://doc.rust-lang.org/std/result/enum.Result.html#variant.Ok
+        /// [Err]:                      https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+        /// [DecodeUtf16Error]:         https://doc.rust-lang.org/std/char/struct.DecodeUtf16Error.html
+        /// [String]:                   https://doc.rust-lang.org/std/string/struct.String.html
+        /// [REPLACEMENT_CHARACTER]:    https://doc.rust-lang.org/std/char/constant.REPLACEMENT_CHARACTER.html
+        pub fn to_string(&self) -> Result<String, DecodeUtf16Error> {
+            self.with_chars(|chars| char::decode_utf16(chars.iter().cloned()).collect())
+        }
+
+        /// Returns a new [String] with any invalid UTF16 characters replaced with [REPLACEMENT_CHARACTER]s (`'\u{FFFD}'`.)
+        ///
+        /// [String]:                   https://doc.rust-lang.org/std/string/struct.String.html
+        /// [REPLACEMENT_CHARACTER]:    https://doc.rust-lang.org/std/char/constant.REPLACEMENT_CHARACTER.html
+        pub fn to_string_lossy(&self) -> String {
+            self.with_chars(|chars| {
+                char::decode_utf16(chars.iter().cloned())
+                    .map(|r| r.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect()
+            })
+        }
+    }
+
+    // OsString doesn't implement Display, so neither does java::lang::String.
+    impl fmt::Debug for JString {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt::Debug::fmt(&self.to_string_lossy(), f) // XXX: Unneccessary alloc?  Shouldn't use lossy here?
+        }
+    }
+
+    impl ThrowableType for Throwable {}
+}
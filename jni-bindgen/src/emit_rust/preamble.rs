@@ -1,7 +1,18 @@
 use std::io::{self, Write};
 
-pub fn write_preamble(out: &mut impl Write) -> io::Result<()> {
+use super::Context;
+
+pub fn write_preamble(context: &Context, out: &mut impl Write) -> io::Result<()> {
     write!(out, "{}", include_str!("preamble-contents.rs"))?;
+
+    // Trait impls for java.lang.String/Throwable can only live in the crate that defines them.
+    let extern_lang =
+        context.extern_for("java/lang/String").is_some() || context.extern_for("java/lang/Throwable").is_some();
+    if !extern_lang {
+        writeln!(out)?;
+        write!(out, "{}", include_str!("preamble-util.rs"))?;
+    }
+
     writeln!(out)?;
     writeln!(out)?;
     Ok(())
@@ -1,13 +1,17 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fmt::Write;
 use std::io;
 
 use jreflection::class;
 
+use super::api_levels::ApiLevels;
 use super::fields::Field;
+use super::generics::TypeVariables;
 use super::known_docs_url::KnownDocsUrl;
 use super::methods::Method;
+use crate::class_file;
+use crate::class_file::signature::ClassSignature;
 use crate::emit_rust::Context;
 use crate::identifiers::{FieldMangling, RustIdentifier};
 
@@ -30,6 +34,20 @@ impl StructPaths {
 pub(crate) struct Struct {
     pub rust: StructPaths,
     pub java: jreflection::Class,
+    pub attributes: class_file::Attributes,
+
+    // Which Android API levels each member was found in, when generating for multiple API levels.
+    pub method_api_levels: HashMap<String, ApiLevels>,
+    pub field_api_levels: HashMap<String, ApiLevels>,
+    pub interface_api_levels: HashMap<String, ApiLevels>,
+}
+
+fn method_key(method: &jreflection::Method) -> String {
+    format!("{}\x1f{}", method.name, method.descriptor_str())
+}
+
+fn field_key(field: &jreflection::Field) -> String {
+    format!("{}\x1f{}", field.name, field.descriptor_str())
 }
 
 fn rust_id(id: &str) -> Result<&str, Box<dyn Error>> {
@@ -80,94 +98,539 @@ impl Struct {
         Ok(buf)
     }
 
-    pub(crate) fn new(context: &mut Context, java: jreflection::Class) -> Result<Self, Box<dyn Error>> {
+    pub(crate) fn new(
+        context: &mut Context,
+        java: jreflection::Class,
+        attributes: class_file::Attributes,
+    ) -> Result<Self, Box<dyn Error>> {
         let rust = StructPaths::new(context, java.path.as_id())?;
 
-        Ok(Self { rust, java })
+        let mut method_api_levels = HashMap::new();
+        let mut field_api_levels = HashMap::new();
+        let mut interface_api_levels = HashMap::new();
+        if let Some(level) = context.api_level {
+            for method in &java.methods {
+                method_api_levels
+                    .entry(method_key(method))
+                    .or_insert_with(ApiLevels::default)
+                    .insert(level);
+            }
+            for field in &java.fields {
+                field_api_levels
+                    .entry(field_key(field))
+                    .or_insert_with(ApiLevels::default)
+                    .insert(level);
+            }
+            for interface in &java.interfaces {
+                interface_api_levels
+                    .entry(interface.as_str().to_string())
+                    .or_insert_with(ApiLevels::default)
+                    .insert(level);
+            }
+        }
+
+        Ok(Self {
+            rust,
+            java,
+            attributes,
+            method_api_levels,
+            field_api_levels,
+            interface_api_levels,
+        })
     }
 
-    pub(crate) fn write(&self, context: &Context, indent: &str, out: &mut impl io::Write) -> io::Result<()> {
-        writeln!(out)?;
+    fn signature(&self) -> Option<ClassSignature> {
+        ClassSignature::parse(self.attributes.signature.as_ref()?).ok()
+    }
 
-        // Ignored access_flags: SUPER, SYNTHETIC, ANNOTATION, ABSTRACT
+    /// The class's own generic parameters, if it's bound as a generic struct.
+    pub(crate) fn type_variables(&self) -> TypeVariables {
+        let mut result = TypeVariables::default();
+        if let Some(signature) = self.signature() {
+            // Static (unit) structs have nowhere to put a PhantomData.
+            if self.java.is_static() || result.declare(&signature.type_parameters).is_none() {
+                return TypeVariables::default();
+            }
+        }
+        result
+    }
 
-        let keyword = if self.java.is_interface() {
-            "interface"
-        } else if self.java.is_enum() {
-            "enum"
-        } else if self.java.is_static() {
-            "static java"
-        } else if self.java.is_final() {
-            "final class"
+    /// Writes `{Interface}Ext`:  a trait with the interface's methods, implemented for everything that implements the
+    /// interface (as in `AsRef<Interface>`), so they can be called on implementing classes without `as_ref()`.
+    #[allow(clippy::too_many_arguments)]
+    fn write_ext_trait(
+        &self,
+        context: &Context,
+        indent: &str,
+        type_variables: &TypeVariables,
+        cfg: &str,
+        methods: &[Method],
+        interfaces: &[(&str, String)],
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        // e.g. `trait ListExt<E: ReferenceType>: AsRef<List<E>>` + `impl<E: ReferenceType, T: AsRef<List<E>>> ListExt<E>`
+        let generics: Vec<&str> = type_variables.rust_names().collect();
+        let bounds: Vec<String> = generics
+            .iter()
+            .map(|name| format!("{}: __jni_bindgen::ReferenceType", name))
+            .collect();
+        let (trait_generics, declare_generics, impl_generics) = if generics.is_empty() {
+            (String::new(), String::new(), String::new())
         } else {
-            "class"
+            (
+                format!("<{}>", generics.join(", ")),
+                format!("<{}>", bounds.join(", ")),
+                format!("{}, ", bounds.join(", ")),
+            )
         };
+        let interface = format!("{}{}", self.rust.struct_name, trait_generics);
+        let trait_name = format!("{}Ext", self.rust.struct_name);
+        let visibility = if self.java.is_public() { "pub " } else { "" };
 
-        let visibility = if self.java.is_public() { "public" } else { "private" };
+        // The superinterfaces' Ext traits are supertraits, and methods they already have are left to them:  a class
+        // inheriting e.g. `Closeable` implements `AutoCloseable` too, so both traits having `close` would be ambiguous.
+        let mut supertraits = String::new();
+        let mut inherited = HashSet::new();
+        for (java, rust) in interfaces {
+            let superinterface = context.struct_for(java);
+            if superinterface.is_some_and(|s| self.java.is_public() && !s.java.is_public()) {
+                continue;
+            }
+            let ext = match rust.find('<') {
+                Some(generics) => format!("{}Ext{}", &rust[..generics], &rust[generics..]),
+                None if context.type_parameter_count(java) == 0 => format!("{}Ext", rust),
+                None => continue, // Raw, so there are no type arguments to give its Ext trait
+            };
+            write!(supertraits, " + {}", ext).unwrap();
+            if let Some(superinterface) = superinterface {
+                let (methods, _, _) = superinterface.members(context, TypeVariables::NONE);
+                inherited.extend(
+                    methods
+                        .iter()
+                        .filter(|m| !m.name_collision && !m.java.is_static() && !m.java.is_constructor())
+                        .filter_map(|m| m.emitted_name(context)),
+                );
+            }
+        }
 
-        let attributes = (if self.java.deprecated { "#[deprecated] " } else { "" }).to_string();
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}/// The methods of [{}], for anything implementing it.",
+            indent, self.rust.struct_name
+        )?;
+        writeln!(
+            out,
+            "{}{}{}trait {}{}: __jni_bindgen::std::convert::AsRef<{}>{} {{",
+            indent, cfg, visibility, trait_name, declare_generics, interface, supertraits
+        )?;
+        for method in methods {
+            if method
+                .emitted_name(context)
+                .is_some_and(|name| inherited.contains(&name))
+            {
+                continue;
+            }
+            method.emit_ext(context, indent, &self.rust.mod_, &interface, out)?;
+        }
+        writeln!(out, "{}}}", indent)?;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}{}impl<{}__jni_Self: ?Sized + __jni_bindgen::std::convert::AsRef<{}>{}> {}{} for __jni_Self {{}}",
+            indent, cfg, impl_generics, interface, supertraits, trait_name, trait_generics
+        )?;
+        Ok(())
+    }
 
-        let super_path = if let Some(super_path) = self.java.super_path.as_ref() {
-            context.java_to_rust_path(super_path.as_id(), &self.rust.mod_).unwrap()
+    /// Writes `{Interface}Impl`:  a trait with the interface's methods for Rust to implement, and `{Interface}::from_rust`
+    /// to implement the interface with one, via [jni_glue::new_proxy](https://docs.rs/jni-glue/*/jni_glue/fn.new_proxy.html).
+    fn write_from_rust(
+        &self,
+        context: &Context,
+        indent: &str,
+        type_variables: &TypeVariables,
+        cfg: &str,
+        methods: &[Method],
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        let trait_name = format!("{}Impl", self.rust.struct_name);
+        if context
+            .emitted_classes
+            .contains(&format!("{}Impl", self.java.path.as_str()))
+        {
+            writeln!(out)?;
+            writeln!(
+                out,
+                "{}// Not emitting {} or {}::from_rust:  {}Impl is a class already",
+                indent,
+                trait_name,
+                self.rust.struct_name,
+                self.java.path.as_str()
+            )?;
+            return Ok(());
+        }
+
+        // e.g. `trait ListImpl<E: ReferenceType>` + `impl<E: ReferenceType> List<E> { fn from_rust(...) }`
+        let generics: Vec<&str> = type_variables.rust_names().collect();
+        let bounds: Vec<String> = generics
+            .iter()
+            .map(|name| format!("{}: __jni_bindgen::ReferenceType", name))
+            .collect();
+        let (trait_generics, declare_generics) = if generics.is_empty() {
+            (String::new(), String::new())
         } else {
-            "()".to_owned() // This might only happen for java.lang.Object
+            (format!("<{}>", generics.join(", ")), format!("<{}>", bounds.join(", ")))
         };
-
-        writeln!(out, "{}__jni_bindgen! {{", indent)?;
-        if let Some(url) = KnownDocsUrl::from_class(context, self.java.path.as_id()) {
-            writeln!(out, "{}    /// {} {} {}", indent, visibility, keyword, url)?;
+        let impl_trait = format!("{}{}", trait_name, trait_generics);
+        let impl_trait_expr = if generics.is_empty() {
+            trait_name.clone()
         } else {
+            format!("{}::{}", trait_name, trait_generics)
+        };
+
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}/// The methods of [{}], for Rust to implement.  See [{}::from_rust].",
+            indent, self.rust.struct_name, self.rust.struct_name
+        )?;
+        writeln!(
+            out,
+            "{}{}pub trait {}{}: __jni_bindgen::std::marker::Send + __jni_bindgen::std::marker::Sync + 'static {{",
+            indent, cfg, trait_name, declare_generics
+        )?;
+        for method in methods {
+            method.emit_impl(context, indent, &self.rust.mod_, out)?;
+        }
+        writeln!(out, "{}}}", indent)?;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}{}impl{} {}{} {{",
+            indent, cfg, declare_generics, self.rust.struct_name, trait_generics
+        )?;
+        writeln!(
+            out,
+            "{}    /// Implement [{}] in Rust:  creates a `java.lang.reflect.Proxy` forwarding calls to `implementation`, which",
+            indent, self.rust.struct_name
+        )?;
+        writeln!(
+            out,
+            "{}    /// is dropped once the proxy is garbage collected.  Calls to methods `{}` doesn't have (such as those of",
+            indent, trait_name
+        )?;
+        writeln!(
+            out,
+            "{}    /// superinterfaces) throw `java.lang.UnsupportedOperationException`.",
+            indent
+        )?;
+        writeln!(
+            out,
+            "{}    pub fn from_rust<'env>(env: __jni_bindgen::Env<'env>, implementation: impl {}) -> __jni_bindgen::std::result::Result<__jni_bindgen::Local<'env, Self>, __jni_bindgen::JavaException> {{",
+            indent, impl_trait
+        )?;
+        writeln!(out, "{}        let __jni_implementation = implementation;", indent)?;
+        writeln!(
+            out,
+            "{}        __jni_bindgen::new_proxy(env, __jni_bindgen::invocation_handler(move |__jni_invocation| {{",
+            indent
+        )?;
+        let dispatch_indent = format!("{}        ", indent);
+        for method in methods {
+            method.emit_proxy_dispatch(context, &dispatch_indent, &self.rust.mod_, &impl_trait_expr, out)?;
+        }
+        writeln!(out, "{}            __jni_invocation.unhandled()", indent)?;
+        writeln!(out, "{}        }}))", indent)?;
+        writeln!(out, "{}    }}", indent)?;
+        writeln!(out, "{}}}", indent)?;
+        Ok(())
+    }
+
+    /// Writes `{Class}Natives`:  a trait with the class's `native` methods for Rust to implement, and
+    /// `{Class}::register_natives` to bind an implementation to them with `RegisterNatives` - or `Java_...` functions
+    /// exporting the \[[native\]] section's `export` implementation, if any.
+    fn write_natives(
+        &self,
+        context: &Context,
+        indent: &str,
+        cfg: &str,
+        methods: &[Method],
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        let trait_name = format!("{}Natives", self.rust.struct_name);
+        let visibility = if self.java.is_public() { "pub " } else { "" };
+        if !methods.iter().any(|m| m.java.is_native()) {
+            writeln!(out)?;
             writeln!(
                 out,
-                "{}    /// {} {} {}",
+                "{}// Not emitting {}:  {} has no native methods",
                 indent,
-                visibility,
-                keyword,
+                trait_name,
                 self.java.path.as_str()
             )?;
+            return Ok(());
         }
-        write!(
+
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}/// The `native` methods of [{}], for Rust to implement.  See [{}::register_natives].",
+            indent, self.rust.struct_name, self.rust.struct_name
+        )?;
+        writeln!(out, "{}{}{}trait {} {{", indent, cfg, visibility, trait_name)?;
+        for method in methods {
+            method.emit_native(context, indent, &self.rust.mod_, out)?;
+        }
+        writeln!(out, "{}}}", indent)?;
+
+        let body_indent = format!("{}    ", indent);
+        let mut trampolines = Vec::new();
+        let mut registration = Vec::new();
+        for method in methods {
+            method.emit_native_trampoline(
+                context,
+                &body_indent,
+                &self.rust.mod_,
+                &trait_name,
+                &mut trampolines,
+                &mut registration,
+            )?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "{}{}impl {} {{", indent, cfg, self.rust.struct_name)?;
+        writeln!(
             out,
-            "{}    {}{} {} {} ({:?}) extends {}",
+            "{}    /// Binds `N`'s implementations of [{}] to the `native` methods of [{}], with `RegisterNatives`.",
+            indent, trait_name, self.rust.struct_name
+        )?;
+        writeln!(
+            out,
+            "{}    {}fn register_natives<'env, N: {}>(env: __jni_bindgen::Env<'env>) -> __jni_bindgen::std::result::Result<(), __jni_bindgen::Local<'env, {}>> {{",
             indent,
-            attributes,
             visibility,
-            keyword,
-            &self.rust.struct_name,
-            self.java.path.as_str().to_string() + "\0",
-            super_path
+            trait_name,
+            context.throwable_rust_path(&self.rust.mod_)
         )?;
-        let mut implements = false;
-        for interface in &self.java.interfaces {
-            if !context.all_classes.contains(interface.as_str()) {
-                continue;
-            }
-            write!(out, ", ")?;
-            if !implements {
-                write!(out, "implements ")?;
-                implements = true;
+        out.write_all(&trampolines[..])?;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}        let mut __jni_natives = __jni_bindgen::std::vec::Vec::new();",
+            indent
+        )?;
+        out.write_all(&registration[..])?;
+        writeln!(
+            out,
+            "{}        static __JNI_CLASS: __jni_bindgen::ClassCache = __jni_bindgen::ClassCache::new();",
+            indent
+        )?;
+        writeln!(
+            out,
+            "{}        unsafe {{ env.register_natives(__JNI_CLASS.require(env, {:?}), &__jni_natives[..]) }}",
+            indent,
+            format!("{}\0", self.java.path.as_str())
+        )?;
+        writeln!(out, "{}    }}", indent)?;
+        writeln!(out, "{}}}", indent)?;
+
+        if let Some(implementation) = context.config.native_exports.get(self.java.path.as_str()) {
+            for method in methods {
+                // The JVM only falls back on the long symbol for natives overloaded by other natives
+                let overloaded = methods
+                    .iter()
+                    .filter(|m| m.java.is_native() && m.java.name == method.java.name)
+                    .count()
+                    > 1;
+                method.emit_native_export(
+                    context,
+                    indent,
+                    &self.rust.mod_,
+                    cfg,
+                    &trait_name,
+                    implementation,
+                    overloaded,
+                    out,
+                )?;
             }
-            write!(
+        }
+        Ok(())
+    }
+
+    /// Writes `{Exception}::throw_new` into `out` (the body of the class), if it can be constructed from a message, and
+    /// `impl ThrowableType for {Exception}` into `items` - except for `java.lang.Throwable`, which the preamble covers.
+    fn write_throwable(
+        &self,
+        indent: &str,
+        cfg: &str,
+        id_repeats: &HashMap<String, usize>,
+        out: &mut impl io::Write,
+        items: &mut impl io::Write,
+    ) -> io::Result<()> {
+        let constructible = !self.java.is_abstract()
+            && self
+                .java
+                .methods
+                .iter()
+                .any(|m| m.is_public() && m.is_constructor() && m.descriptor_str() == "(Ljava/lang/String;)V");
+        if constructible && !id_repeats.contains_key("throw_new") {
+            writeln!(out)?;
+            writeln!(
                 out,
-                "{}",
-                &context.java_to_rust_path(interface.as_id(), &self.rust.mod_).unwrap()
+                "{}        /// Throws a new [{}] with `message`, e.g. from a native method.  See [Env::throw_new](__jni_bindgen::Env::throw_new).",
+                indent, self.rust.struct_name
             )?;
+            writeln!(
+                out,
+                "{}        pub fn throw_new<'env>(env: __jni_bindgen::Env<'env>, message: &str) {{",
+                indent
+            )?;
+            writeln!(out, "{}            env.throw_new::<Self>(message)", indent)?;
+            writeln!(out, "{}        }}", indent)?;
         }
-        writeln!(out, " {{")?;
 
+        if self.java.path.as_str() != "java/lang/Throwable" {
+            writeln!(items)?;
+            writeln!(
+                items,
+                "{}{}impl __jni_bindgen::ThrowableType for {} {{}}",
+                indent, cfg, self.rust.struct_name
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes `impl ByteBufferType for {ByteBuffer}` into `items`, which gives access to direct buffers' memory.
+    fn write_byte_buffer(&self, indent: &str, cfg: &str, items: &mut impl io::Write) -> io::Result<()> {
+        writeln!(items)?;
+        writeln!(
+            items,
+            "{}{}impl __jni_bindgen::ByteBufferType for {} {{}}",
+            indent, cfg, self.rust.struct_name
+        )
+    }
+
+    /// The name of the function [write_verify](Self::write_verify) writes, called by `verify_bindings`.
+    pub(crate) fn verify_fn_name(&self) -> String {
+        let struct_name = self.rust.struct_name.as_str();
+        format!(
+            "__jni_bindgen_verify_{}",
+            struct_name.strip_prefix("r#").unwrap_or(struct_name)
+        )
+    }
+
+    /// Writes the class's part of `verify_bindings`:  a function looking up the class, and every method and field emitted
+    /// for it.
+    fn write_verify(
+        &self,
+        context: &Context,
+        indent: &str,
+        cfg: &str,
+        methods: &[Method],
+        fields: &[Field],
+        out: &mut impl io::Write,
+    ) -> io::Result<()> {
+        writeln!(out)?;
+        writeln!(out, "{}{}#[doc(hidden)]", indent, cfg)?;
+        writeln!(
+            out,
+            "{}pub(crate) fn {}(__jni_verify: &mut __jni_bindgen::VerifyBindings) {{",
+            indent,
+            self.verify_fn_name()
+        )?;
+        writeln!(out, "{}    unsafe {{", indent)?;
+        writeln!(
+            out,
+            "{}        if !__jni_verify.class({:?}) {{",
+            indent,
+            format!("{}\0", self.java.path.as_str())
+        )?;
+        writeln!(out, "{}            return;", indent)?;
+        writeln!(out, "{}        }}", indent)?;
+        let body_indent = format!("{}    ", indent);
+        for method in methods {
+            method.emit_verify(context, &body_indent, &self.rust.mod_, out)?;
+        }
+        for field in fields {
+            field.emit_verify(context, &body_indent, &self.rust.mod_, out)?;
+        }
+        writeln!(out, "{}    }}", indent)?;
+        writeln!(out, "{}}}", indent)?;
+        Ok(())
+    }
+
+    /// Merge in the same class as found in a later Android API level.  The newer definition wins, but members removed
+    /// since are kept so they can still be emitted for the API levels that had them.
+    pub(crate) fn merge_api_level(&mut self, mut newer: Struct) {
+        let older = std::mem::take(&mut self.java);
+        newer.attributes.merge_older(std::mem::take(&mut self.attributes));
+        for method in older.methods {
+            if !newer.method_api_levels.contains_key(&method_key(&method)) {
+                newer.java.methods.push(method);
+            }
+        }
+        for field in older.fields {
+            if !newer.field_api_levels.contains_key(&field_key(&field)) {
+                newer.java.fields.push(field);
+            }
+        }
+        for interface in older.interfaces {
+            if !newer.interface_api_levels.contains_key(interface.as_str()) {
+                newer.java.interfaces.push(interface);
+            }
+        }
+
+        for (older, newer) in [
+            (&self.method_api_levels, &mut newer.method_api_levels),
+            (&self.field_api_levels, &mut newer.field_api_levels),
+            (&self.interface_api_levels, &mut newer.interface_api_levels),
+        ] {
+            for (key, levels) in older {
+                newer.entry(key.clone()).or_default().extend(levels);
+            }
+        }
+
+        *self = newer;
+    }
+
+    /// The class's methods and fields, with overloaded methods renamed by `method_naming_style_collision` - and any
+    /// whose Rust names still collide marked as such - plus how many public members each Rust name was first given to.
+    fn members<'s>(
+        &'s self,
+        context: &Context,
+        type_variables: &'s TypeVariables,
+    ) -> (Vec<Method<'s>>, Vec<Field<'s>>, HashMap<String, usize>) {
         let mut id_repeats = HashMap::new();
 
         let mut methods: Vec<Method> = self
             .java
             .methods
             .iter()
-            .map(|m| Method::new(context, &self.java, m))
+            .map(|m| {
+                let mut method = Method::new(context, &self.java, m);
+                method.api_levels = self.method_api_levels.get(&method_key(m));
+                method.class_type_variables = type_variables;
+                if let Some(attributes) = self.attributes.methods.get(&method_key(m)) {
+                    method.exceptions = &attributes.exceptions[..];
+                    method.signature = attributes.signature.as_deref();
+                }
+                method
+            })
             .collect();
-        let mut fields: Vec<Field> = self
+        let fields: Vec<Field> = self
             .java
             .fields
             .iter()
-            .map(|f| Field::new(context, &self.java, f))
+            .map(|f| {
+                let mut field = Field::new(context, &self.java, f);
+                field.api_levels = self.field_api_levels.get(&field_key(f));
+                field.class_type_variables = type_variables;
+                if let Some(attributes) = self.attributes.fields.get(&field_key(f)) {
+                    field.signature = attributes.signature.as_deref();
+                }
+                field
+            })
             .collect();
 
         for method in &methods {
@@ -179,6 +642,8 @@ impl Struct {
             }
         }
 
+        // Field accessors keep their names, so they claim them first.
+        let mut claimed = HashSet::new();
         for field in &fields {
             if !field.java.is_public() {
                 continue;
@@ -186,33 +651,738 @@ impl Struct {
             match field.rust_names.as_ref() {
                 Ok(FieldMangling::ConstValue(name, _)) => {
                     *id_repeats.entry(name.to_owned()).or_insert(0) += 1;
+                    claimed.insert(name.to_string());
                 }
                 Ok(FieldMangling::GetSet(get, set)) => {
                     *id_repeats.entry(get.to_owned()).or_insert(0) += 1;
                     *id_repeats.entry(set.to_owned()).or_insert(0) += 1;
+                    claimed.insert(get.to_string());
+                    claimed.insert(set.to_string());
                 }
                 Err(_) => {}
             }
         }
 
+        let mut overloaded = Vec::new();
         for method in &mut methods {
-            if let Some(name) = method.rust_name() {
-                let repeats = *id_repeats.get(name).unwrap_or(&0);
-                let overloaded = repeats > 1;
-                if overloaded {
-                    method.set_mangling_style(context.config.codegen.method_naming_style_collision);
+            let repeats = method.rust_name().map_or(0, |name| *id_repeats.get(name).unwrap_or(&0));
+            if repeats > 1 {
+                method.set_mangling_style(context.config.codegen.method_naming_style_collision);
+            }
+            overloaded.push(repeats > 1);
+        }
+
+        // Mangling overloads can still land on a name something else has - e.g. `DataOutput`'s `write(int)` becomes
+        // `write_int`, as does `writeInt(int)` - so methods keeping their names claim them next, then overloads in
+        // declaration order.  Anything left without a name of its own is rejected.
+        for renamed in [false, true] {
+            for (method, _) in methods.iter_mut().zip(&overloaded).filter(|(_, &o)| o == renamed) {
+                if !method.java.is_public() || method.java.is_static_init() {
+                    continue;
+                }
+                if let Some(name) = method.emitted_name(context) {
+                    method.name_collision = !claimed.insert(name);
                 }
             }
+        }
+
+        (methods, fields, id_repeats)
+    }
+
+    pub(crate) fn write(&self, context: &Context, indent: &str, out: &mut impl io::Write) -> io::Result<()> {
+        writeln!(out)?;
+
+        // Ignored access_flags: SUPER, SYNTHETIC, ANNOTATION, ABSTRACT
 
-            method.emit(context, indent, &self.rust.mod_, out)?;
+        let keyword = if self.java.is_interface() {
+            "interface"
+        } else if self.java.is_enum() {
+            "enum"
+        } else if self.java.is_static() {
+            "static java"
+        } else if self.java.is_final() {
+            "final class"
+        } else {
+            "class"
+        };
+
+        let visibility = if self.java.is_public() { "public" } else { "private" };
+
+        let attributes = (if self.java.deprecated { "#[deprecated] " } else { "" }).to_string();
+
+        let signature = self.signature();
+        let object_path = context.object_rust_path(&self.rust.mod_);
+        let type_variables = match object_path.as_ref() {
+            Some(_) => self.type_variables(),
+            None => TypeVariables::default(), // Generic parameters default to Object
+        };
+        let generic_type = |class: &str| {
+            let java = signature.as_ref().and_then(|s| {
+                std::iter::once(&s.super_class)
+                    .chain(s.interfaces.iter())
+                    .find(|t| t.path == class)
+            })?;
+            type_variables.class_type(context, &self.rust.mod_, java, &mut Default::default())
+        };
+
+        let super_path = if let Some(super_path) = self.java.super_path.as_ref() {
+            generic_type(super_path.as_str())
+                .map(Ok)
+                .unwrap_or_else(|| context.java_to_rust_path(super_path.as_id(), &self.rust.mod_))
+                .map_err(|e| io_data_error!("{}", e))?
+        } else {
+            "()".to_owned() // This might only happen for java.lang.Object
+        };
+
+        let mut generics = String::new();
+        if let (false, Some(object_path)) = (type_variables.is_empty(), object_path.as_ref()) {
+            let parameters: Vec<String> = type_variables
+                .rust_names()
+                .map(|name| format!("{} = {}", name, object_path))
+                .collect();
+            generics = format!("<{}>", parameters.join(", "));
+        }
+
+        let class_api_levels = context.class_api_levels.get(self.java.path.as_str());
+
+        // Attributes on the struct itself wouldn't reach the impls __jni_bindgen! generates, so gate the whole thing.
+        let cfg = ApiLevels::attribute(class_api_levels, context);
+        if !cfg.is_empty() {
+            writeln!(out, "{}{}", indent, cfg.trim_end())?;
+        }
+        writeln!(out, "{}__jni_bindgen! {{", indent)?;
+        if let Some(url) = KnownDocsUrl::from_class(context, self.java.path.as_id()) {
+            writeln!(out, "{}    /// {} {} {}", indent, visibility, keyword, url)?;
+        } else {
+            writeln!(
+                out,
+                "{}    /// {} {} {}",
+                indent,
+                visibility,
+                keyword,
+                self.java.path.as_str()
+            )?;
+        }
+        write!(
+            out,
+            "{}    {}{} {} {}{} ({:?}) extends {}",
+            indent,
+            attributes,
+            visibility,
+            keyword,
+            &self.rust.struct_name,
+            generics,
+            self.java.path.as_str().to_string() + "\0",
+            super_path
+        )?;
+        let mut interfaces = Vec::new(); // (Java, Rust) paths of every interface implemented
+        for interface in &self.java.interfaces {
+            if !context.is_class_available(interface.as_str()) {
+                continue;
+            }
+            if let Some(class_api_levels) = class_api_levels {
+                // Only implement interfaces available everywhere this class is, and implemented by it everywhere.  We
+                // can't know which API levels an [[extern]] crate has an interface for, so assume it matches ours.
+                let available = match context.class_api_levels.get(interface.as_str()) {
+                    Some(levels) => levels.covers(class_api_levels),
+                    None => context.extern_for(interface.as_str()).is_some(),
+                };
+                let implemented = self
+                    .interface_api_levels
+                    .get(interface.as_str())
+                    .is_some_and(|levels| levels.covers(class_api_levels));
+                if !available || !implemented {
+                    continue;
+                }
+            }
+            let rust = generic_type(interface.as_str())
+                .unwrap_or_else(|| context.java_to_rust_path(interface.as_id(), &self.rust.mod_).unwrap());
+            interfaces.push((interface.as_str(), rust));
+        }
+        // Also implement inherited interfaces, so e.g. `&impl java::lang::IterableExt<E>` accepts an ArrayList<E>.
+        let inherited = context.inherited_interfaces(self.java.path.as_str());
+        for interface in &inherited {
+            if !context.is_class_available(interface.path.as_str()) {
+                continue;
+            }
+            if let Some(class_api_levels) = class_api_levels {
+                let available = match context.class_api_levels.get(interface.path.as_str()) {
+                    Some(levels) => levels.covers(class_api_levels),
+                    None => context.extern_for(interface.path.as_str()).is_some(),
+                };
+                if !available || !context.implements_at_every_level(self.java.path.as_str(), interface.path.as_str()) {
+                    continue;
+                }
+            }
+            let rust = type_variables
+                .class_type(context, &self.rust.mod_, interface, &mut Default::default())
+                .ok_or_else(|| io_data_error!("no Rust path for {}", interface.path))?;
+            interfaces.push((interface.path.as_str(), rust));
+        }
+        for (i, (_, interface)) in interfaces.iter().enumerate() {
+            write!(out, "{}{}", if i == 0 { ", implements " } else { ", " }, interface)?;
+        }
+        writeln!(out, " {{")?;
+
+        let mut items = Vec::new(); // Emitted after __jni_bindgen! { ... }
+        let mut statics = Vec::new(); // Static members and constructors of generic classes, for their static { ... } block
+
+        let (mut methods, mut fields, id_repeats) = self.members(context, &type_variables);
+        for method in &mut methods {
+            if (method.java.is_static() || method.java.is_constructor()) && !type_variables.is_empty() {
+                method.emit(context, indent, &self.rust.mod_, &mut statics, &mut items)?;
+            } else {
+                method.emit(context, indent, &self.rust.mod_, out, &mut items)?;
+            }
         }
 
         for field in &mut fields {
-            field.emit(context, indent, &self.rust.mod_, out)?;
+            if field.java.is_static() && !type_variables.is_empty() {
+                field.emit(context, indent, &self.rust.mod_, &mut statics)?;
+            } else {
+                field.emit(context, indent, &self.rust.mod_, out)?;
+            }
         }
 
-        writeln!(out, "{}    }}", indent)?;
+        if context.is_subclass_of(self.java.path.as_str(), "java/lang/Throwable") && type_variables.is_empty() {
+            self.write_throwable(indent, &cfg, &id_repeats, out, &mut items)?;
+        }
+
+        if context.is_subclass_of(self.java.path.as_str(), "java/nio/ByteBuffer") && type_variables.is_empty() {
+            self.write_byte_buffer(indent, &cfg, &mut items)?;
+        }
+
+        if self.java.is_interface() {
+            self.write_ext_trait(
+                context,
+                indent,
+                &type_variables,
+                &cfg,
+                &methods,
+                &interfaces,
+                &mut items,
+            )?;
+            if self.java.is_public() {
+                self.write_from_rust(context, indent, &type_variables, &cfg, &methods, &mut items)?;
+            }
+        }
+
+        if context.config.native_classes.contains(self.java.path.as_str()) {
+            self.write_natives(context, indent, &cfg, &methods, &mut items)?;
+        }
+
+        if context.config.codegen.verify_bindings {
+            self.write_verify(context, indent, &cfg, &methods, &fields, &mut items)?;
+        }
+
+        if statics.is_empty() {
+            writeln!(out, "{}    }}", indent)?;
+        } else {
+            writeln!(out, "{}    }} static {{", indent)?;
+            out.write_all(&statics[..])?;
+            writeln!(out, "{}    }}", indent)?;
+        }
         writeln!(out, "{}}}", indent)?;
+        out.write_all(&items[..])?;
         Ok(())
     }
 }
+
+#[test]
+fn from_rust_checks_arguments() {
+    use super::test_util::*;
+
+    let mut emit = java_lang();
+    emit.push(class(ACC_PUBLIC, "java/lang/String", &[], None, &[]));
+    emit.push(class(
+        ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT,
+        "com/example/Listener",
+        &[],
+        None,
+        &[member(ACC_PUBLIC | ACC_ABSTRACT, "onEvent", "(ILjava/lang/String;)V")],
+    ));
+    let out = generate("", &emit, &[]);
+
+    assert!(out.contains("pub fn from_rust<'env>(env: __jni_bindgen::Env<'env>, implementation: impl ListenerImpl) -> __jni_bindgen::std::result::Result<__jni_bindgen::Local<'env, Self>, __jni_bindgen::JavaException> {"));
+    // Arguments that aren't what `ListenerImpl` expects throw instead of reaching it
+    assert!(out.contains(
+        "let __jni_arg1 = match __jni_invocation.arg(1) { Ok(arg) => arg, Err(response) => return response };"
+    ));
+    assert!(out.contains("return __jni_invocation.returns(ListenerImpl::on_event(&__jni_implementation, __jni_invocation.env(), __jni_arg0, __jni_arg1));"));
+}
+
+#[test]
+fn natives_never_dropped() {
+    use super::test_util::*;
+
+    let mut emit = java_lang();
+    emit.push(class(
+        ACC_PUBLIC,
+        "com/example/Native",
+        &[],
+        None,
+        &[
+            member(ACC_PUBLIC | ACC_STATIC | ACC_NATIVE, "café_x", "([I)V"),
+            member(ACC_PUBLIC | ACC_NATIVE, "plain", "()I"),
+        ],
+    ));
+    let natives = "[[native]]\nclass = \"com/example/Native\"\n";
+    let out = generate(natives, &emit, &[]);
+
+    // `café_x` can't be a Rust identifier, but still needs implementing and registering
+    assert!(out.contains("fn caf_000e9_1x___3I<'env>(env: __jni_bindgen::Env<'env>, arg0: "));
+    assert!(out.contains("__jni_natives.push(__jni_bindgen::sys::JNINativeMethod { name: \"café_x\\0\".as_ptr() as *mut _, signature: \"([I)V\\0\".as_ptr() as *mut _, fnPtr: caf_000e9_1x___3I::<N> as *mut _ });"));
+    assert!(out.contains("fnPtr: plain::<N> as *mut _"));
+    assert!(
+        out.contains("env.register_natives(__JNI_CLASS.require(env, \"com/example/Native\\0\"), &__jni_natives[..])")
+    );
+
+    // Leaving a native out is an error, rather than an UnsatisfiedLinkError waiting to happen
+    let ignored = format!(
+        "{}[[ignore]]\nclass = \"com/example/Native\"\nmethod = \"plain\"\n",
+        natives
+    );
+    let error = try_generate(&ignored, &emit, &[]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Unable to bind native method com/example/Native.plain()I:  [[ignore]]d"
+    );
+}
+
+#[test]
+fn native_exports() {
+    use super::test_util::*;
+
+    let mut emit = java_lang();
+    emit.push(class(
+        ACC_PUBLIC,
+        "com/example/Native",
+        &[],
+        None,
+        &[
+            member(ACC_PUBLIC | ACC_STATIC | ACC_NATIVE, "café_x", "([I)V"),
+            member(ACC_PUBLIC | ACC_NATIVE, "over", "()I"),
+            member(ACC_PUBLIC | ACC_NATIVE, "over", "(I)I"),
+        ],
+    ));
+    let natives = "[[native]]\nclass = \"com/example/Native\"\nexport = \"crate::Implementation\"\n";
+    let out = generate(natives, &emit, &[]);
+
+    // Exported under the JVM's name for the method, whatever it's called in Rust
+    assert!(out.contains("pub extern \"system\" fn Java_com_example_Native_caf_000e9_1x<'env>(__jni_env: __jni_bindgen::Env<'env>, _: __jni_bindgen::sys::jclass, arg0: __jni_bindgen::Argument<__jni_bindgen::IntArray>) {"));
+    assert!(out
+        .contains("<crate::Implementation as NativeNatives>::caf_000e9_1x___3I(__jni_env, arg0.into_ref(__jni_env))"));
+    // Overloaded natives get the long, signature-qualified symbols
+    assert!(out.contains("pub extern \"system\" fn Java_com_example_Native_over__<'env>("));
+    assert!(out.contains("pub extern \"system\" fn Java_com_example_Native_over__I<'env>("));
+    assert_eq!(out.matches("#[no_mangle]").count(), 3);
+}
+
+#[test]
+fn byte_buffers() {
+    use super::test_util::*;
+
+    let mut emit = java_lang();
+    emit.push(class(
+        ACC_PUBLIC | ACC_ABSTRACT,
+        "java/nio/ByteBuffer",
+        &[],
+        None,
+        &[member(
+            ACC_PUBLIC,
+            "put",
+            "(Ljava/nio/ByteBuffer;)Ljava/nio/ByteBuffer;",
+        )],
+    ));
+    emit.push(subclass(
+        ACC_PUBLIC | ACC_ABSTRACT,
+        "java/nio/MappedByteBuffer",
+        "java/nio/ByteBuffer",
+        &[],
+    ));
+    emit.push(class(ACC_PUBLIC, "java/nio/CharBuffer", &[], None, &[]));
+    let out = generate("", &emit, &[]);
+
+    // ByteBuffer and its subclasses get direct buffer access...
+    assert!(out.contains("impl __jni_bindgen::ByteBufferType for ByteBuffer {}"));
+    assert!(out.contains("impl __jni_bindgen::ByteBufferType for MappedByteBuffer {}"));
+    assert_eq!(out.matches("ByteBufferType").count(), 2);
+    // ...and take `ByteBuffer`s as `impl Into<Option<&ByteBuffer>>`, which `&DirectByteBuffer` converts to.
+    assert!(out.contains("pub fn put<'env>(&'env self, arg0: impl __jni_bindgen::std::convert::Into<__jni_bindgen::std::option::Option<&'env ByteBuffer>>)"));
+}
+
+#[test]
+fn ext_traits() {
+    use super::test_util::*;
+
+    let mut emit = java_lang();
+    emit.push(class(
+        ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT,
+        "com/example/Shape",
+        &[],
+        None,
+        &[
+            member(ACC_PUBLIC | ACC_ABSTRACT, "area", "()D"),
+            member(ACC_PUBLIC, "scaled", "(D)Lcom/example/Shape;"),
+            member(ACC_PUBLIC | ACC_STATIC, "unit", "()Lcom/example/Shape;"),
+        ],
+    ));
+    emit.push(class(
+        ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT,
+        "com/example/Solid",
+        &["com/example/Shape"],
+        None,
+        &[member(ACC_PUBLIC | ACC_ABSTRACT, "volume", "()D")],
+    ));
+    emit.push(class(ACC_PUBLIC, "com/example/Cube", &["com/example/Solid"], None, &[]));
+    emit.push(subclass(ACC_PUBLIC, "com/example/BigCube", "com/example/Cube", &[]));
+    let out = generate("", &emit, &[]);
+
+    // Every interface gets an Ext trait, implemented for anything that converts to it...
+    assert!(out.contains("pub trait ShapeExt: __jni_bindgen::std::convert::AsRef<Shape> {"));
+    assert!(
+        out.contains("impl<__jni_Self: ?Sized + __jni_bindgen::std::convert::AsRef<Shape>> ShapeExt for __jni_Self {}")
+    );
+    assert!(out.contains("pub trait SolidExt: __jni_bindgen::std::convert::AsRef<Solid> + ShapeExt {"));
+    assert!(out.contains(
+        "impl<__jni_Self: ?Sized + __jni_bindgen::std::convert::AsRef<Solid> + ShapeExt> SolidExt for __jni_Self {}"
+    ));
+    // ...forwarding instance methods, but not static ones.
+    assert!(out.contains("            fn scaled<'env>(&'env self, arg0: f64) -> __jni_bindgen::std::result::Result<__jni_bindgen::std::option::Option<__jni_bindgen::Local<'env, Shape>>, __jni_bindgen::Local<'env, super::super::java::lang::Throwable>> {\n                __jni_bindgen::std::convert::AsRef::<Shape>::as_ref(self).scaled(arg0)\n            }"));
+    assert!(out.contains("__jni_bindgen::std::convert::AsRef::<Shape>::as_ref(self).area()"));
+    assert!(out.contains("__jni_bindgen::std::convert::AsRef::<Solid>::as_ref(self).volume()"));
+    assert_eq!(out.matches("fn unit<").count(), 1);
+
+    // Classes implement the interfaces they inherit, as well as those they declare, so they get their Ext traits too.
+    assert!(out.contains("public interface Solid (\"com/example/Solid\\0\") extends super::super::java::lang::Object, implements Shape {"));
+    assert!(out.contains("public class Cube (\"com/example/Cube\\0\") extends super::super::java::lang::Object, implements Solid, Shape {"));
+    assert!(out.contains("public class BigCube (\"com/example/BigCube\\0\") extends Cube, implements Solid, Shape {"));
+}
+
+#[test]
+fn inherited_ext_methods() {
+    use super::test_util::*;
+
+    let mut emit = java_lang_for_preamble();
+    emit.push(class(
+        ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT,
+        "java/lang/AutoCloseable",
+        &[],
+        None,
+        &[member(ACC_PUBLIC | ACC_ABSTRACT, "close", "()V")],
+    ));
+    emit.push(class(
+        ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT,
+        "java/io/Closeable",
+        &["java/lang/AutoCloseable"],
+        None,
+        &[member(ACC_PUBLIC | ACC_ABSTRACT, "close", "()V")],
+    ));
+    emit.push(class(
+        ACC_PUBLIC,
+        "com/example/Base",
+        &["java/io/Closeable"],
+        None,
+        &[member(ACC_PUBLIC, "close", "()V")],
+    ));
+    emit.push(subclass(ACC_PUBLIC, "com/example/Derived", "com/example/Base", &[]));
+    // The preamble calls `Throwable::getMessage` and friends by their Java names.
+    let mut out = generate("[codegen]\nmethod_naming_style = \"java\"\n", &emit, &[]);
+
+    // CloseableExt leaves `close` to AutoCloseableExt, so neither a Derived nor a generic Closeable is ambiguous.
+    assert!(out.contains(
+        "pub trait CloseableExt: __jni_bindgen::std::convert::AsRef<Closeable> + super::lang::AutoCloseableExt {"
+    ));
+    assert!(out.contains("impl<__jni_Self: ?Sized + __jni_bindgen::std::convert::AsRef<Closeable> + super::lang::AutoCloseableExt> CloseableExt for __jni_Self {}"));
+    assert_eq!(out.matches("::as_ref(self).close()").count(), 1);
+    out.push_str(
+        r#"
+pub mod usage {
+    use crate::com::example::*;
+    use crate::java::io::*;
+    use crate::java::lang::*;
+
+    pub fn close_both(derived: &Derived, closeable: &impl CloseableExt) {
+        let _ = derived.close();
+        let _ = closeable.close();
+    }
+}
+"#,
+    );
+    assert_compiles("inherited_ext_methods", &out);
+}
+
+#[test]
+fn inherited_generic_interfaces() {
+    use super::test_util::*;
+
+    let mut emit = java_lang_for_preamble();
+    emit.push(class(
+        ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT,
+        "java/lang/Iterable",
+        &[],
+        Some("<T:Ljava/lang/Object;>Ljava/lang/Object;"),
+        &[],
+    ));
+    emit.push(class(
+        ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT,
+        "java/util/Collection",
+        &["java/lang/Iterable"],
+        Some("<E:Ljava/lang/Object;>Ljava/lang/Object;Ljava/lang/Iterable<TE;>;"),
+        &[
+            member(ACC_PUBLIC | ACC_ABSTRACT, "size", "()I"),
+            generic_member(ACC_PUBLIC | ACC_ABSTRACT, "add", "(Ljava/lang/Object;)Z", "(TE;)Z"),
+        ],
+    ));
+    emit.push(class(
+        ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT,
+        "java/util/List",
+        &["java/util/Collection"],
+        Some("<E:Ljava/lang/Object;>Ljava/lang/Object;Ljava/util/Collection<TE;>;"),
+        &[],
+    ));
+    emit.push(class(
+        ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT,
+        "java/util/RandomAccess",
+        &[],
+        None,
+        &[],
+    ));
+    emit.push(class(
+        ACC_PUBLIC | ACC_ABSTRACT,
+        "java/util/AbstractList",
+        &["java/util/List"],
+        Some("<X:Ljava/lang/Object;>Ljava/lang/Object;Ljava/util/List<TX;>;"),
+        &[],
+    ));
+    emit.push(generic_subclass(
+        ACC_PUBLIC,
+        "java/util/ArrayList",
+        "java/util/AbstractList",
+        &["java/util/RandomAccess"],
+        Some("<E:Ljava/lang/Object;>Ljava/util/AbstractList<TE;>;Ljava/util/RandomAccess;"),
+        &[],
+    ));
+    emit.push(generic_subclass(
+        ACC_PUBLIC,
+        "com/example/Strings",
+        "java/util/ArrayList",
+        &[],
+        Some("Ljava/util/ArrayList<Ljava/lang/String;>;"),
+        &[],
+    ));
+    emit.push(subclass(ACC_PUBLIC, "com/example/Names", "java/util/ArrayList", &[]));
+    // The preamble calls `Throwable::getMessage` and friends by their Java names.
+    let mut out = generate("[codegen]\nmethod_naming_style = \"java\"\n", &emit, &[]);
+
+    // Type arguments are substituted through the hierarchy (or erased by raw supertypes.)
+    assert!(out.contains("public class ArrayList<E = super::lang::Object> (\"java/util/ArrayList\\0\") extends AbstractList<E>, implements RandomAccess, List<E>, Collection<E>, super::lang::Iterable<E> {"));
+    assert!(out.contains("public class Strings (\"com/example/Strings\\0\") extends super::super::java::util::ArrayList<super::super::java::lang::String>, implements super::super::java::util::RandomAccess, super::super::java::util::List<super::super::java::lang::String>, super::super::java::util::Collection<super::super::java::lang::String>, super::super::java::lang::Iterable<super::super::java::lang::String> {"));
+    assert!(out.contains("public class Names (\"com/example/Names\\0\") extends super::super::java::util::ArrayList, implements super::super::java::util::RandomAccess, super::super::java::util::List, super::super::java::util::Collection, super::super::java::lang::Iterable {"));
+    out.push_str(
+        r#"
+pub mod usage {
+    use crate::com::example::*;
+    use crate::java::lang::*;
+    use crate::java::util::*;
+
+    fn strings(strings: &impl CollectionExt<String>) {
+        iterable(strings);
+        let _ = strings.size();
+    }
+
+    fn iterable<T: jni_glue::ReferenceType>(_: &impl IterableExt<T>) {}
+
+    pub fn all(list: &ArrayList<String>, subclass: &Strings, raw: &Names) {
+        let _ = list.size();
+        let _ = subclass.size();
+        let _ = raw.size();
+        strings(list);
+        strings(subclass);
+        iterable::<Object>(raw);
+    }
+}
+"#,
+    );
+    assert_compiles("inherited_generic_interfaces", &out);
+}
+
+#[test]
+fn inherited_interfaces_at_api_levels() {
+    use super::test_util::*;
+
+    let closeable = || {
+        vec![
+            class(
+                ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT,
+                "java/lang/AutoCloseable",
+                &[],
+                None,
+                &[member(ACC_PUBLIC | ACC_ABSTRACT, "close", "()V")],
+            ),
+            class(
+                ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT,
+                "java/io/Closeable",
+                &["java/lang/AutoCloseable"],
+                None,
+                &[],
+            ),
+            class(ACC_PUBLIC, "com/example/Stream", &["java/io/Closeable"], None, &[]),
+            subclass(ACC_PUBLIC, "com/example/FileStream", "com/example/Stream", &[]),
+        ]
+    };
+    let mut level_1 = java_lang();
+    level_1.extend(closeable());
+    level_1.push(class(
+        ACC_PUBLIC,
+        "com/example/Channel",
+        &["java/io/Closeable"],
+        None,
+        &[],
+    ));
+    level_1.push(subclass(
+        ACC_PUBLIC,
+        "com/example/FileChannel",
+        "com/example/Channel",
+        &[],
+    ));
+    let mut level_2 = java_lang();
+    level_2.extend(closeable());
+    level_2.push(class(ACC_PUBLIC, "com/example/Channel", &[], None, &[]));
+    level_2.push(subclass(
+        ACC_PUBLIC,
+        "com/example/FileChannel",
+        "com/example/Channel",
+        &[],
+    ));
+    let out = generate_api_levels("", &[(1, level_1), (2, level_2)]);
+
+    // Inherited interfaces are implemented if they're inherited at every API level...
+    assert!(out.contains("public class FileStream (\"com/example/FileStream\\0\") extends Stream, implements super::super::java::io::Closeable, super::super::java::lang::AutoCloseable {"));
+    // ...but not if they're only inherited at some.
+    assert!(out.contains("public class FileChannel (\"com/example/FileChannel\\0\") extends Channel {"));
+}
+
+#[test]
+fn mangled_name_collisions() {
+    use super::test_util::*;
+
+    let mut emit = java_lang_for_preamble();
+    emit.push(class(
+        ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT,
+        "com/example/Output",
+        &[],
+        None,
+        &[
+            member(ACC_PUBLIC | ACC_ABSTRACT, "write", "(I)V"),
+            member(ACC_PUBLIC | ACC_ABSTRACT, "write", "([B)V"),
+            member(ACC_PUBLIC | ACC_ABSTRACT, "write_int", "(I)V"),
+        ],
+    ));
+    // The preamble calls `Throwable::getMessage` and friends by their Java names.
+    let toml = "[codegen]\nmethod_naming_style = \"java\"\nmethod_naming_style_collision = \"java_short_signature\"\n";
+    let out = generate(toml, &emit, &[]);
+    assert_compiles("mangled_name_collisions", &out);
+
+    // `write(int)` mangles to the name `write_int(int)` already has, so it's rejected - and left out of OutputExt and
+    // OutputImpl - while its overload keeps its mangled name.
+    assert_eq!(
+        out.matches("ERROR:  Rust name collides with another member's").count(),
+        1
+    );
+    assert!(out.contains("__jni_invocation.is(\"write_int\\0\", \"(I)V\\0\")"));
+    assert!(!out.contains("__jni_invocation.is(\"write\\0\", \"(I)V\\0\")"));
+    assert_eq!(out.matches("fn write_byte_array<").count(), 3);
+}
+
+#[test]
+fn native_trampolines() {
+    use super::test_util::*;
+
+    let mut emit = java_lang();
+    emit.push(class(
+        ACC_PUBLIC,
+        "com/example/Native",
+        &[],
+        None,
+        &[
+            member(ACC_PUBLIC | ACC_STATIC | ACC_NATIVE, "count", "(Ljava/lang/Object;)I"),
+            member(ACC_PUBLIC | ACC_NATIVE, "run", "()V"),
+        ],
+    ));
+    let natives = "[[native]]\nclass = \"com/example/Native\"\nexport = \"crate::Implementation\"\n";
+    let out = generate(natives, &emit, &[]);
+
+    // Registered trampolines and exports both convert their arguments, and catch panics with native_call...
+    assert!(out.contains(
+        r#"                extern "system" fn count<'env, N: NativeNatives>(__jni_env: __jni_bindgen::Env<'env>, _: __jni_bindgen::sys::jclass, arg0: __jni_bindgen::Argument<super::super::java::lang::Object>) -> i32 {
+                    __jni_bindgen::native_call(__jni_env, || unsafe { N::count(__jni_env, arg0.into_ref(__jni_env)) })
+                }"#
+    ));
+    assert!(out.contains(
+        r#"        pub extern "system" fn Java_com_example_Native_count<'env>(__jni_env: __jni_bindgen::Env<'env>, _: __jni_bindgen::sys::jclass, arg0: __jni_bindgen::Argument<super::super::java::lang::Object>) -> i32 {
+            __jni_bindgen::native_call(__jni_env, || unsafe { <crate::Implementation as NativeNatives>::count(__jni_env, arg0.into_ref(__jni_env)) })
+        }"#
+    ));
+    // ...with `this` in place of the jclass for instance methods.
+    assert!(out.contains(
+        r#"                extern "system" fn run<'env, N: NativeNatives>(__jni_env: __jni_bindgen::Env<'env>, __jni_this: __jni_bindgen::Argument<Native>) {
+                    __jni_bindgen::native_call(__jni_env, || unsafe { N::run(__jni_env, __jni_this.into_ref(__jni_env).unwrap()) })
+                }"#
+    ));
+    assert!(out.contains("fn run<'env>(env: __jni_bindgen::Env<'env>, this: __jni_bindgen::Ref<'env, Native>) -> "));
+    assert_eq!(out.matches("__jni_bindgen::native_call(").count(), 4);
+}
+
+#[test]
+fn throwables() {
+    use super::test_util::*;
+
+    let with_message = || member(ACC_PUBLIC, "<init>", "(Ljava/lang/String;)V");
+    let emit = [
+        class(ACC_PUBLIC, "java/lang/Object", &[], None, &[]),
+        class(ACC_PUBLIC, "java/lang/String", &[], None, &[with_message()]),
+        class(ACC_PUBLIC, "java/lang/Throwable", &[], None, &[with_message()]),
+        subclass(
+            ACC_PUBLIC,
+            "java/lang/Exception",
+            "java/lang/Throwable",
+            &[with_message()],
+        ),
+        subclass(
+            ACC_PUBLIC | ACC_ABSTRACT,
+            "com/example/AbstractException",
+            "java/lang/Exception",
+            &[with_message()],
+        ),
+        subclass(
+            ACC_PUBLIC,
+            "com/example/SilentException",
+            "java/lang/Exception",
+            &[member(ACC_PUBLIC, "<init>", "()V")],
+        ),
+    ];
+    let out = generate("", &emit, &[]);
+
+    // Exceptions that can be constructed from a message can be thrown with one...
+    let throw_new = |name: &str| {
+        format!(
+            "/// Throws a new [{}] with `message`, e.g. from a native method.  See [Env::throw_new](__jni_bindgen::Env::throw_new).",
+            name
+        )
+    };
+    assert!(out.contains(&throw_new("Throwable")));
+    assert!(out.contains(&throw_new("Exception")));
+    assert_eq!(
+        out.matches("pub fn throw_new<'env>(env: __jni_bindgen::Env<'env>, message: &str) {")
+            .count(),
+        2
+    );
+    assert_eq!(out.matches("env.throw_new::<Self>(message)").count(), 2);
+
+    // ...and every subclass of Throwable is a ThrowableType, except Throwable itself, which the preamble covers.
+    assert!(out.contains("impl __jni_bindgen::ThrowableType for Exception {}"));
+    assert!(out.contains("impl __jni_bindgen::ThrowableType for AbstractException {}"));
+    assert!(out.contains("impl __jni_bindgen::ThrowableType for SilentException {}"));
+    assert_eq!(out.matches("impl __jni_bindgen::ThrowableType for").count(), 3);
+}
@@ -0,0 +1,272 @@
+//! Fixtures for emit tests:  code-less class files built from a few declarations, and the bindings generated for them.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{env, fs, io};
+
+use jreflection::Class;
+
+use crate::class_file::Attributes;
+use crate::config::{runtime::Config, toml};
+use crate::dex::{write_class_file, ClassDef, MemberDef};
+use crate::emit_rust::Context;
+
+pub(crate) const ACC_PUBLIC: u16 = 0x0001;
+pub(crate) const ACC_STATIC: u16 = 0x0008;
+pub(crate) const ACC_NATIVE: u16 = 0x0100;
+pub(crate) const ACC_INTERFACE: u16 = 0x0200;
+pub(crate) const ACC_ABSTRACT: u16 = 0x0400;
+
+/// A field or method of a [class], told apart by its descriptor.
+pub(crate) struct Member {
+    pub flags: u16,
+    pub name: &'static str,
+    pub descriptor: &'static str,
+    pub signature: Option<&'static str>,
+}
+
+pub(crate) fn member(flags: u16, name: &'static str, descriptor: &'static str) -> Member {
+    Member {
+        flags,
+        name,
+        descriptor,
+        signature: None,
+    }
+}
+
+pub(crate) fn generic_member(
+    flags: u16,
+    name: &'static str,
+    descriptor: &'static str,
+    signature: &'static str,
+) -> Member {
+    Member {
+        signature: Some(signature),
+        ..member(flags, name, descriptor)
+    }
+}
+
+/// A class file for `name`, extending `java/lang/Object` (unless it is `java/lang/Object`.)
+pub(crate) fn class(
+    flags: u16,
+    name: &'static str,
+    interfaces: &[&'static str],
+    signature: Option<&'static str>,
+    members: &[Member],
+) -> Vec<u8> {
+    let super_name = if name == "java/lang/Object" {
+        None
+    } else {
+        Some("java/lang/Object")
+    };
+    class_file(flags, name, super_name, interfaces, signature, members)
+}
+
+/// A class file for `name`, extending `super_name`.
+pub(crate) fn subclass(flags: u16, name: &'static str, super_name: &'static str, members: &[Member]) -> Vec<u8> {
+    class_file(flags, name, Some(super_name), &[], None, members)
+}
+
+/// A class file for `name`, extending `super_name` and implementing `interfaces` - as `signature` may parameterize.
+pub(crate) fn generic_subclass(
+    flags: u16,
+    name: &'static str,
+    super_name: &'static str,
+    interfaces: &[&'static str],
+    signature: Option<&'static str>,
+    members: &[Member],
+) -> Vec<u8> {
+    class_file(flags, name, Some(super_name), interfaces, signature, members)
+}
+
+fn class_file(
+    flags: u16,
+    name: &'static str,
+    super_name: Option<&'static str>,
+    interfaces: &[&'static str],
+    signature: Option<&'static str>,
+    members: &[Member],
+) -> Vec<u8> {
+    let member_def = |member: &Member| MemberDef {
+        access_flags: member.flags,
+        name: member.name.as_bytes(),
+        descriptor: member.descriptor.as_bytes().to_vec(),
+        constant: None,
+        deprecated: false,
+        exceptions: Vec::new(),
+        signature: member.signature.map(|s| s.as_bytes().to_vec()),
+    };
+    write_class_file(&ClassDef {
+        access_flags: flags,
+        name: name.as_bytes(),
+        super_name: super_name.map(|s| s.as_bytes()),
+        interfaces: interfaces.iter().map(|i| i.as_bytes()).collect(),
+        fields: members
+            .iter()
+            .filter(|m| !m.descriptor.starts_with('('))
+            .map(member_def)
+            .collect(),
+        methods: members
+            .iter()
+            .filter(|m| m.descriptor.starts_with('('))
+            .map(member_def)
+            .collect(),
+        deprecated: false,
+        signature: signature.map(|s| s.as_bytes().to_vec()),
+    })
+}
+
+/// `java/lang/Object` and `java/lang/Throwable`, which most bindings refer to.
+pub(crate) fn java_lang() -> Vec<Vec<u8>> {
+    vec![
+        class(ACC_PUBLIC, "java/lang/Object", &[], None, &[]),
+        class(ACC_PUBLIC, "java/lang/Throwable", &[], None, &[]),
+    ]
+}
+
+/// The bindings generated for `emit` (with `classpath` available to refer to) by a `jni-bindgen.toml` of `toml`, plus
+/// the `[input]` and `[output]` tables it needs anyways.
+pub(crate) fn generate(toml: &str, emit: &[Vec<u8>], classpath: &[Vec<u8>]) -> String {
+    try_generate(toml, emit, classpath).unwrap()
+}
+
+/// Like [generate], but returns the error generating fails with.
+pub(crate) fn try_generate(toml: &str, emit: &[Vec<u8>], classpath: &[Vec<u8>]) -> io::Result<String> {
+    let config = config(toml);
+    let mut context = Context::new(&config);
+    gather(&mut context, emit, classpath);
+
+    let mut out = Vec::new();
+    context.write(&mut out)?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+/// Like [try_generate], but writes the bindings for `emit` to `path` and the files beside it, as the `per-class` code
+/// sharding style does.
+pub(crate) fn write_per_class(emit: &[Vec<u8>], path: &Path) -> io::Result<()> {
+    let config = config("");
+    let mut context = Context::new(&config);
+    gather(&mut context, emit, &[]);
+    context.write_per_class(path)
+}
+
+/// An empty directory (under `target/`) for `test` to write files to.
+pub(crate) fn test_dir(test: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("target")
+        .join("test-output")
+        .join(test);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Like [generate], but for multiple Android API levels:  `levels` are the API levels, each with the classes to emit
+/// at that level.
+pub(crate) fn generate_api_levels(toml: &str, levels: &[(u32, Vec<Vec<u8>>)]) -> String {
+    let mut config = config(toml);
+    config.android_api_levels = Some(levels.iter().map(|(level, _)| *level).collect());
+    let mut context = Context::new(&config);
+    for (level, emit) in levels {
+        context.api_level = Some(*level);
+        gather(&mut context, emit, &[]);
+    }
+
+    let mut out = Vec::new();
+    context.write(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+fn config(toml: &str) -> Config {
+    let toml = format!("{}\n[input]\nfiles = []\n\n[output]\npath = \"bindings.rs\"\n", toml);
+    toml::FileWithContext {
+        file: toml::File::read_str(&toml).unwrap(),
+        directory: Default::default(),
+    }
+    .into()
+}
+
+fn gather(context: &mut Context, emit: &[Vec<u8>], classpath: &[Vec<u8>]) {
+    for class_file in classpath {
+        context
+            .add_classpath_class(
+                Class::read(&mut &class_file[..]).unwrap(),
+                Attributes::read(class_file).unwrap(),
+            )
+            .unwrap();
+    }
+    for class_file in emit {
+        context
+            .add_struct(
+                Class::read(&mut &class_file[..]).unwrap(),
+                Attributes::read(class_file).unwrap(),
+            )
+            .unwrap();
+    }
+}
+
+/// Like [java_lang], plus `java/lang/String` and the members of `java/lang/Throwable` the preamble's `Debug` impls
+/// call, for bindings that have to [compile](assert_compiles).
+pub(crate) fn java_lang_for_preamble() -> Vec<Vec<u8>> {
+    let string = "()Ljava/lang/String;";
+    vec![
+        class(ACC_PUBLIC, "java/lang/Object", &[], None, &[]),
+        class(ACC_PUBLIC, "java/lang/String", &[], None, &[]),
+        class(
+            ACC_PUBLIC,
+            "java/lang/Throwable",
+            &[],
+            None,
+            &[
+                member(ACC_PUBLIC, "getMessage", string),
+                member(ACC_PUBLIC, "getLocalizedMessage", string),
+                member(ACC_PUBLIC, "getStackTrace", "()[Ljava/lang/StackTraceElement;"),
+            ],
+        ),
+        class(
+            ACC_PUBLIC,
+            "java/lang/StackTraceElement",
+            &[],
+            None,
+            &[
+                member(ACC_PUBLIC, "getFileName", string),
+                member(ACC_PUBLIC, "getLineNumber", "()I"),
+                member(ACC_PUBLIC, "getClassName", string),
+                member(ACC_PUBLIC, "getMethodName", string),
+            ],
+        ),
+    ]
+}
+
+/// Type-checks `bindings` as the `src/lib.rs` of a crate named after `test`, depending on jni-glue - panicking with
+/// rustc's errors if it doesn't compile.  Crates share a target directory, so only the first check builds jni-glue.
+pub(crate) fn assert_compiles(test: &str, bindings: &str) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("target")
+        .join("check-bindings");
+    let dir = root.join(test);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    let manifest = format!(
+        "[package]\nname = {:?}\nversion = \"0.0.0\"\nedition = \"2021\"\n\n[dependencies]\njni-glue = {{ path = {:?} }}\n\n[workspace]\n",
+        test.replace('_', "-"),
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join("jni-glue")
+    );
+    fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    fs::write(dir.join("src").join("lib.rs"), bindings).unwrap();
+
+    let output = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+        .args(["check", "--offline", "--quiet", "--manifest-path"])
+        .arg(dir.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(root.join("target"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "bindings for {} don't compile:\n{}",
+        test,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -2,8 +2,10 @@
 
 mod field_mangling_style;
 mod method_mangling_style;
+mod native_symbol;
 mod rust_identifier;
 
 pub use field_mangling_style::*;
 pub use method_mangling_style::*;
+pub use native_symbol::*;
 pub use rust_identifier::*;
@@ -0,0 +1,81 @@
+/// The symbol the JVM looks up to find the implementation of a `native` method exported from a shared library, as per
+/// the "Resolving Native Method Names" section of the JNI specification.
+///
+/// The short name (`Java_{class}_{method}`) is used unless `overloaded`, in which case the long name is used instead,
+/// with the mangled argument types of `descriptor` appended after a double underscore.
+///
+/// # Examples:
+///
+/// | Method                  | Descriptor             | Overloaded | Symbol                                         |
+/// | ----------------------- | ---------------------- | ---------- | ---------------------------------------------- |
+/// | com/example/Foo.bar     | (I)V                   | false      | Java_com_example_Foo_bar                       |
+/// | com/example/Foo.bar     | (ILjava/lang/String;)V | true       | Java_com_example_Foo_bar__ILjava_lang_String_2 |
+/// | com/example/Foo_X.do_it | ()V                    | false      | Java_com_example_Foo_1X_do_1it                 |
+pub fn native_symbol(class: &str, method: &str, descriptor: &str, overloaded: bool) -> String {
+    let mut symbol = String::from("Java_");
+    mangle_into(&mut symbol, class);
+    symbol.push('_');
+    mangle_into(&mut symbol, method);
+    if overloaded {
+        let arguments = &descriptor[1..descriptor.rfind(')').unwrap_or(1)];
+        symbol.push_str("__");
+        mangle_into(&mut symbol, arguments);
+    }
+    symbol
+}
+
+/// A Rust identifier for a `native` method whose name can't be mangled into one (e.g. `café`), escaped the way a long
+/// [native_symbol] is:  `caf_000e9__I` for `café(I)V`.  Distinct for every overload.
+pub fn native_fallback_name(method: &str, descriptor: &str) -> String {
+    let symbol = native_symbol("", method, descriptor, true);
+    symbol["Java__".len()..].to_owned()
+}
+
+/// Escapes `java` (a class path, method name, or argument descriptors) for use in a [native_symbol].
+fn mangle_into(out: &mut String, java: &str) {
+    for ch in java.chars() {
+        match ch {
+            '/' => out.push('_'),
+            '_' => out.push_str("_1"),
+            ';' => out.push_str("_2"),
+            '[' => out.push_str("_3"),
+            'a'..='z' | 'A'..='Z' | '0'..='9' => out.push(ch),
+            _ => {
+                let mut utf16 = [0u16; 2];
+                for unit in ch.encode_utf16(&mut utf16) {
+                    out.push_str(&format!("_0{:04x}", unit));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn native_symbol_test() {
+    assert_eq!(
+        native_symbol("com/example/Foo", "bar", "(I)V", false),
+        "Java_com_example_Foo_bar"
+    );
+    assert_eq!(
+        native_symbol("com/example/Foo", "bar", "(ILjava/lang/String;)V", true),
+        "Java_com_example_Foo_bar__ILjava_lang_String_2"
+    );
+    assert_eq!(
+        native_symbol("com/example/Foo", "bar", "()V", true),
+        "Java_com_example_Foo_bar__"
+    );
+    assert_eq!(
+        native_symbol("com/example/Foo", "bar", "([[B)J", true),
+        "Java_com_example_Foo_bar___3_3B"
+    );
+    assert_eq!(
+        native_symbol("com/example_co/Foo$Bar", "do_it", "()V", false),
+        "Java_com_example_1co_Foo_00024Bar_do_1it"
+    );
+    assert_eq!(
+        native_symbol("com/exämple/Foo", "été", "()V", false),
+        "Java_com_ex_000e4mple_Foo__000e9t_000e9"
+    );
+    assert_eq!(native_symbol("Foo", "\u{1F600}", "()V", false), "Java_Foo__0d83d_0de00");
+    assert_eq!(native_fallback_name("café_x", "([I)V"), "caf_000e9_1x___3I");
+}
@@ -1,7 +1,9 @@
 // must go first because macros.
 mod util;
 
+mod class_file;
 mod config;
+mod dex;
 mod emit_rust;
 mod identifiers;
 mod run;
@@ -24,6 +26,10 @@ mod entry {
     struct Cli {
         #[command(subcommand)]
         cmd: Cmd,
+
+        /// The Android API level(s) to generate, gated by `api-level-N` features (e.g. `7-28`)
+        #[arg(long, global = true)]
+        android_api_levels: Option<String>,
     }
 
     /// Doc comment
@@ -49,8 +55,24 @@ mod entry {
         match cli.cmd {
             Cmd::Generate(cmd) => {
                 let config_file = config::toml::File::from_directory(&cmd.directory).unwrap();
-                run(config_file).unwrap();
+                let mut config = config::runtime::Config::from(config_file);
+                if let Some(levels) = cli.android_api_levels.as_ref() {
+                    config.set_android_api_levels(levels).unwrap();
+                }
+                run(config).unwrap();
             }
         }
     }
+
+    #[test]
+    fn android_api_levels_anywhere() {
+        // scripts/test.sh passes it before the subcommand.
+        for args in [
+            &["jni-bindgen", "--android-api-levels=7-28", "generate"][..],
+            &["jni-bindgen", "generate", "--android-api-levels=7-28"][..],
+        ] {
+            let cli = Cli::try_parse_from(args).unwrap();
+            assert_eq!(cli.android_api_levels.as_deref(), Some("7-28"));
+        }
+    }
 }
@@ -1,12 +1,14 @@
 use std::error::Error;
 use std::fs::File;
-use std::io;
+use std::io::{self, Read};
 use std::path::Path;
 
 use jreflection::Class;
 
+use crate::class_file::Attributes;
 use crate::config::runtime::Config;
-use crate::{emit_rust, util};
+use crate::config::toml::CodeShardingStyle;
+use crate::{dex, emit_rust, util};
 
 /// The core function of this library: Generate Rust code to access Java APIs.
 pub fn run(config: impl Into<Config>) -> Result<(), Box<dyn Error>> {
@@ -14,20 +16,66 @@ pub fn run(config: impl Into<Config>) -> Result<(), Box<dyn Error>> {
     println!("output: {}", config.output_path.display());
 
     let mut context = emit_rust::Context::new(&config);
-    for file in config.input_files.iter() {
-        gather_file(&mut context, file)?;
+    if let Some(levels) = config.android_api_levels.as_ref() {
+        for &level in levels {
+            context.api_level = Some(level);
+            for file in config.classpath_files_for_api_level(level).iter() {
+                gather_file(&mut context, file, Gather::Classpath)?;
+            }
+            for file in config.input_files_for_api_level(level).iter() {
+                gather_file(&mut context, file, Gather::Emit)?;
+            }
+        }
+    } else {
+        for file in config.classpath_files.iter() {
+            gather_file(&mut context, file, Gather::Classpath)?;
+        }
+        for file in config.input_files.iter() {
+            gather_file(&mut context, file, Gather::Emit)?;
+        }
     }
 
-    {
-        let mut out = Vec::with_capacity(4096);
-        context.write(&mut out)?;
-        util::write_generated(&context, &config.output_path, &out[..])?;
+    match config.codegen.code_sharding {
+        CodeShardingStyle::None => {
+            let mut out = Vec::with_capacity(4096);
+            context.write(&mut out)?;
+            util::write_generated(&context, &config.output_path, &out[..])?;
+        }
+        CodeShardingStyle::PerClass => {
+            context.write_per_class(&config.output_path)?;
+        }
+        CodeShardingStyle::__NonExhaustive => {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "CodeShardingStyle::__NonExhaustive is invalid, silly goose!",
+            ))?;
+        }
     }
 
     Ok(())
 }
 
-fn gather_file(context: &mut emit_rust::Context, path: &Path) -> Result<(), Box<dyn Error>> {
+/// What to do with gathered classes.
+#[derive(Clone, Copy)]
+enum Gather {
+    /// Generate bindings for them.
+    Emit,
+
+    /// Only use them to resolve types referenced by emitted classes.
+    Classpath,
+}
+
+impl Gather {
+    fn add(self, context: &mut emit_rust::Context, class_file: &[u8]) -> Result<(), Box<dyn Error>> {
+        let class = Class::read(&mut &class_file[..])?;
+        match self {
+            Gather::Emit => context.add_struct(class, Attributes::read(class_file)?),
+            Gather::Classpath => context.add_classpath_class(class, Attributes::read(class_file)?),
+        }
+    }
+}
+
+fn gather_file(context: &mut emit_rust::Context, path: &Path, gather: Gather) -> Result<(), Box<dyn Error>> {
     context
         .progress
         .lock()
@@ -45,33 +93,66 @@ fn gather_file(context: &mut emit_rust::Context, path: &Path) -> Result<(), Box<
 
     match ext.to_string_lossy().to_ascii_lowercase().as_str() {
         "class" => {
-            let mut file = io::BufReader::new(File::open(path)?);
-            let class = Class::read(&mut file)?;
-            context.add_struct(class)?;
+            let class_file = std::fs::read(path)?;
+            gather.add(context, &class_file[..])?;
         }
         "jar" => {
-            let mut jar = zip::ZipArchive::new(io::BufReader::new(File::open(path)?))?;
-            let n = jar.len();
+            let jar = zip::ZipArchive::new(io::BufReader::new(File::open(path)?))?;
+            gather_jar(context, jar, gather)?;
+        }
+        "aar" => {
+            // Android libraries: classes.jar, plus any libs/*.jar dependencies bundled alongside it.
+            let mut aar = zip::ZipArchive::new(io::BufReader::new(File::open(path)?))?;
+            let n = aar.len();
 
             for i in 0..n {
-                let mut file = jar.by_index(i)?;
-                if !file.name().ends_with(".class") {
+                let mut file = aar.by_index(i)?;
+                let name = file.name().to_owned();
+                let is_jar = name == "classes.jar" || (name.starts_with("libs/") && name.ends_with(".jar"));
+                if !is_jar {
                     continue;
                 }
                 context
                     .progress
                     .lock()
                     .unwrap()
-                    .update(format!("  reading {:3}/{}: {}...", i, n, file.name()).as_str());
-                let class = Class::read(&mut file)?;
-                context.add_struct(class)?;
+                    .update(format!("  reading {}...", name).as_str());
+
+                let mut buffer = Vec::with_capacity(file.size() as usize);
+                file.read_to_end(&mut buffer)?;
+                let jar = zip::ZipArchive::new(io::Cursor::new(buffer))?;
+                gather_jar(context, jar, gather)?;
+            }
+        }
+        "dex" => {
+            let dex = std::fs::read(path)?;
+            gather_dex(context, &dex[..], gather)?;
+        }
+        "apk" => {
+            let mut apk = zip::ZipArchive::new(io::BufReader::new(File::open(path)?))?;
+            let n = apk.len();
+
+            for i in 0..n {
+                let mut file = apk.by_index(i)?;
+                if !dex::is_apk_classes_dex(file.name()) {
+                    continue;
+                }
+                context
+                    .progress
+                    .lock()
+                    .unwrap()
+                    .update(format!("  reading {}...", file.name()).as_str());
+
+                let mut dex = Vec::with_capacity(file.size() as usize);
+                file.read_to_end(&mut dex)?;
+                gather_dex(context, &dex[..], gather)?;
             }
         }
         unknown => {
             Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 format!(
-                    "Input files must have a '.class' or '.jar' extension, not a '.{}' extension",
+                    "Input files must have a '.class', '.jar', '.aar', '.dex', or '.apk' extension, not a '.{}' extension",
                     unknown
                 ),
             ))?;
@@ -79,3 +160,34 @@ fn gather_file(context: &mut emit_rust::Context, path: &Path) -> Result<(), Box<
     }
     Ok(())
 }
+
+fn gather_jar(
+    context: &mut emit_rust::Context,
+    mut jar: zip::ZipArchive<impl io::Read + io::Seek>,
+    gather: Gather,
+) -> Result<(), Box<dyn Error>> {
+    let n = jar.len();
+
+    for i in 0..n {
+        let mut file = jar.by_index(i)?;
+        if !file.name().ends_with(".class") {
+            continue;
+        }
+        context
+            .progress
+            .lock()
+            .unwrap()
+            .update(format!("  reading {:3}/{}: {}...", i, n, file.name()).as_str());
+        let mut class_file = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut class_file)?;
+        gather.add(context, &class_file[..])?;
+    }
+    Ok(())
+}
+
+fn gather_dex(context: &mut emit_rust::Context, dex: &[u8], gather: Gather) -> Result<(), Box<dyn Error>> {
+    for class_file in dex::class_files(dex)? {
+        gather.add(context, &class_file[..])?;
+    }
+    Ok(())
+}
@@ -1,6 +1,7 @@
+use std::collections::HashSet;
 use std::fs::{self, *};
-use std::io::{self, BufRead, BufReader, Cursor, ErrorKind};
-use std::path::Path;
+use std::io::{self, BufRead, BufReader, Cursor, ErrorKind, Write};
+use std::path::{Path, PathBuf};
 
 use super::Difference;
 use crate::emit_rust;
@@ -8,6 +9,11 @@ use crate::emit_rust;
 const MARKER_COMMENT: &str =
     "WARNING:  This file was autogenerated by jni-bindgen.  Any changes to this file may be lost!!!";
 
+/// Writes the `// {MARKER_COMMENT}` line every generated file must start with to be overwritable.
+pub fn write_marker_comment(out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "// {}", MARKER_COMMENT)
+}
+
 pub fn write_generated(context: &emit_rust::Context, path: &impl AsRef<Path>, contents: &[u8]) -> io::Result<()> {
     let path = path.as_ref();
     let dir = path
@@ -21,15 +27,7 @@ pub fn write_generated(context: &emit_rust::Context, path: &impl AsRef<Path>, co
             let mut first_line = String::new();
             read_line_no_eol(&mut original, &mut first_line)?;
 
-            let mut found_marker = false;
-            for prefix in &["// ", "# "] {
-                if first_line.starts_with(prefix) && (&first_line[prefix.len()..] == MARKER_COMMENT) {
-                    found_marker = true;
-                    break;
-                }
-            }
-
-            if !found_marker {
+            if !is_marker_line(&first_line) {
                 return io_data_err!("Cannot overwrite {:?}:  File exists, and first line {:?} doesn't match expected MARKER_COMMENT {:?}", path, first_line, MARKER_COMMENT);
             }
 
@@ -67,6 +65,67 @@ pub fn write_generated(context: &emit_rust::Context, path: &impl AsRef<Path>, co
     fs::write(path, contents)
 }
 
+/// Deletes every generated `.rs` file under `dir` that isn't in `keep`, then any directories left empty.
+///
+/// Only files starting with the marker comment are touched, and only directories holding a generated `mod.rs` are
+/// recursed into.  Loose files directly in `dir` are left alone unless `scan_files` is set, as `dir` might be shared
+/// with other code (e.g. when generating into `src/lib.rs`.)
+pub fn remove_stale_generated(
+    context: &emit_rust::Context,
+    dir: &Path,
+    keep: &HashSet<PathBuf>,
+    scan_files: bool,
+) -> io::Result<()> {
+    let entries = match read_dir(dir) {
+        Ok(entries) => entries,
+        Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            if is_generated(&path.join("mod.rs"))? {
+                remove_stale_generated(context, &path, keep, true)?;
+                if read_dir(&path)?.next().is_none() {
+                    fs::remove_dir(&path)?;
+                }
+            }
+        } else if scan_files
+            && path.extension().is_some_and(|ext| ext == "rs")
+            && !keep.contains(&path)
+            && is_generated(&path)?
+        {
+            context
+                .progress
+                .lock()
+                .unwrap()
+                .force_update(format!("REMOVED: {}", path.display()).as_str());
+            fs::remove_file(&path)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn is_generated(path: &Path) -> io::Result<bool> {
+    match File::open(path) {
+        Ok(file) => {
+            let mut first_line = String::new();
+            read_line_no_eol(&mut BufReader::new(file), &mut first_line)?;
+            Ok(is_marker_line(&first_line))
+        }
+        Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn is_marker_line(line: &str) -> bool {
+    ["// ", "# "]
+        .iter()
+        .any(|prefix| line.starts_with(prefix) && (&line[prefix.len()..] == MARKER_COMMENT))
+}
+
 fn read_line_no_eol(reader: &mut impl BufRead, buffer: &mut String) -> io::Result<usize> {
     let size = reader.read_line(buffer)?;
     while buffer.ends_with('\r') || buffer.ends_with('\n') {
@@ -16,5 +16,5 @@ mod generated_file;
 mod progress;
 
 pub use difference::Difference;
-pub use generated_file::write_generated;
+pub use generated_file::{remove_stale_generated, write_generated, write_marker_comment};
 pub use progress::Progress;
@@ -1,4 +1,7 @@
 // For easier review, codegen uses this macro, to ensure all output is consistent.
+//
+// Generic classes list their type parameters as `Name<T = Default, ...>`, and their static members (and constructors)
+// in a trailing `static { ... }` block:  those are implemented on the raw (defaulted) type, so `Name::new()` infers.
 
 #[doc(hidden)] // For codegen use only, not (yet?) an otherwise stable part of the glue interface.
 #[macro_export]
@@ -7,6 +10,16 @@ macro_rules! __jni_bindgen {
 
 
 
+    (@deref <$($gen:ident),+> $from:ty => $target:ty; $($rest:tt)*) => {
+        impl<$($gen: $crate::ReferenceType),+> $crate::std::ops::Deref for $from {
+            type Target = $target;
+            fn deref(&self) -> &Self::Target {
+                unsafe { &*(self as *const Self as *const Self::Target) }
+            }
+        }
+        __jni_bindgen! { $($rest)* }
+    };
+
     (@deref $from:ty => (); $($rest:tt)*) => {
         __jni_bindgen! { $($rest)* }
     };
@@ -21,6 +34,36 @@ macro_rules! __jni_bindgen {
         __jni_bindgen! { $($rest)* }
     };
 
+    (@statics $name:ident; static { $($body:tt)* } $($rest:tt)*) => {
+        impl $name { $($body)* }
+        __jni_bindgen! { $($rest)* }
+    };
+
+    (@statics $name:ident; $($rest:tt)*) => {
+        __jni_bindgen! { $($rest)* }
+    };
+
+    (@implements_all $generics:tt $from:tt => ; $($rest:tt)*) => {
+        __jni_bindgen! { $($rest)* }
+    };
+
+    (@implements_all [$($generics:tt)*] [$($from:tt)*] => $interface:ty $(, $more:ty)*; $($rest:tt)*) => {
+        __jni_bindgen! {
+            @implements $($generics)* $($from)* => $interface;
+            @implements_all [$($generics)*] [$($from)*] => $($more),*;
+            $($rest)*
+        }
+    };
+
+    (@implements <$($gen:ident),+> $from:ty => $target:ty; $($rest:tt)*) => {
+        impl<$($gen: $crate::ReferenceType),+> $crate::std::convert::AsRef<$target> for $from {
+            fn as_ref(&self) -> &$target {
+                unsafe { &*(self as *const Self as *const $target) }
+            }
+        }
+        __jni_bindgen! { $($rest)* }
+    };
+
     (@implements $from:ty => $target:ty; $($rest:tt)*) => {
         impl $crate::std::convert::AsRef<$target> for $from {
             fn as_ref(&self) -> &$target {
@@ -35,61 +78,66 @@ macro_rules! __jni_bindgen {
     ($(#[$attr:meta])* private static class $name:ident ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
         $(#[$attr])* #[repr(transparent)] struct $name;
         impl $name { $($body)* }
-        unsafe impl $crate::JniType for $name { fn static_with_jni_type<R>(callback: impl FnOnce(&str) -> R) -> R { callback($jni_type) } }
+        unsafe impl $crate::JniType for $name { fn static_with_jni_type<__jni_R>(callback: impl FnOnce(&str) -> __jni_R) -> __jni_R { callback($jni_type) } }
         __jni_bindgen! {
             // static
             $($rest)*
         }
     };
 
-    ($(#[$attr:meta])* private final class $name:ident ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
-        $(#[$attr])* #[repr(transparent)] struct $name(pub(crate) $crate::ObjectAndEnv);
-        impl $name { $($body)* }
-        unsafe impl $crate::ReferenceType for $name {}
-        unsafe impl $crate::AsJValue for $name { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
-        unsafe impl $crate::JniType for $name { fn static_with_jni_type<R>(callback: impl FnOnce(&str) -> R) -> R { callback($jni_type) } }
+    ($(#[$attr:meta])* private final class $name:ident $(<$($gen:ident = $default:ty),+>)? ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
+        $(#[$attr])* #[repr(transparent)] struct $name $(<$($gen: $crate::ReferenceType = $default),+>)? (pub(crate) $crate::ObjectAndEnv $(, $crate::std::marker::PhantomData<($($gen,)+)>)?);
+        impl $(<$($gen: $crate::ReferenceType),+>)? $name $(<$($gen),+>)? { $($body)* }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::ReferenceType for $name $(<$($gen),+>)? {}
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::AsJValue for $name $(<$($gen),+>)? { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::JniType for $name $(<$($gen),+>)? { fn static_with_jni_type<__jni_R>(callback: impl FnOnce(&str) -> __jni_R) -> __jni_R { callback($jni_type) } }
         __jni_bindgen! {
-            $($(@implements $name => $interface;)*)*
-            @deref $name => $parent;
+            @implements_all [$(<$($gen),+>)?] [$name $(<$($gen),+>)?] => $($($interface),+),*;
+            @deref $(<$($gen),+>)? $name $(<$($gen),+>)? => $parent;
+            @statics $name;
             $($rest)*
         }
     };
 
-    ($(#[$attr:meta])* private class $name:ident ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
-        $(#[$attr])* #[repr(transparent)] struct $name(pub(crate) $crate::ObjectAndEnv);
-        impl $name { $($body)* }
-        unsafe impl $crate::ReferenceType for $name {}
-        unsafe impl $crate::AsJValue for $name { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
-        unsafe impl $crate::JniType for $name { fn static_with_jni_type<R>(callback: impl FnOnce(&str) -> R) -> R { callback($jni_type) } }
+    ($(#[$attr:meta])* private class $name:ident $(<$($gen:ident = $default:ty),+>)? ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
+        $(#[$attr])* #[repr(transparent)] struct $name $(<$($gen: $crate::ReferenceType = $default),+>)? (pub(crate) $crate::ObjectAndEnv $(, $crate::std::marker::PhantomData<($($gen,)+)>)?);
+        impl $(<$($gen: $crate::ReferenceType),+>)? $name $(<$($gen),+>)? { $($body)* }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::ReferenceType for $name $(<$($gen),+>)? {}
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::AsJValue for $name $(<$($gen),+>)? { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::JniType for $name $(<$($gen),+>)? { fn static_with_jni_type<__jni_R>(callback: impl FnOnce(&str) -> __jni_R) -> __jni_R { callback($jni_type) } }
         __jni_bindgen! {
-            $($(@implements $name => $interface;)*)*
-            @deref $name => $parent;
+            @implements_all [$(<$($gen),+>)?] [$name $(<$($gen),+>)?] => $($($interface),+),*;
+            @deref $(<$($gen),+>)? $name $(<$($gen),+>)? => $parent;
+            @statics $name;
             $($rest)*
         }
     };
 
-    ($(#[$attr:meta])* private enum $name:ident ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
-        $(#[$attr])* #[repr(transparent)] struct $name(pub(crate) $crate::ObjectAndEnv);
-        impl $name { $($body)* }
-        unsafe impl $crate::ReferenceType for $name {}
-        unsafe impl $crate::AsJValue for $name { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
-        unsafe impl $crate::JniType for $name { fn static_with_jni_type<R>(callback: impl FnOnce(&str) -> R) -> R { callback($jni_type) } }
+    ($(#[$attr:meta])* private enum $name:ident $(<$($gen:ident = $default:ty),+>)? ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
+        $(#[$attr])* #[repr(transparent)] struct $name $(<$($gen: $crate::ReferenceType = $default),+>)? (pub(crate) $crate::ObjectAndEnv $(, $crate::std::marker::PhantomData<($($gen,)+)>)?);
+        impl $(<$($gen: $crate::ReferenceType),+>)? $name $(<$($gen),+>)? { $($body)* }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::ReferenceType for $name $(<$($gen),+>)? {}
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::AsJValue for $name $(<$($gen),+>)? { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::JniType for $name $(<$($gen),+>)? { fn static_with_jni_type<__jni_R>(callback: impl FnOnce(&str) -> __jni_R) -> __jni_R { callback($jni_type) } }
         __jni_bindgen! {
-            $($(@implements $name => $interface;)*)*
-            @deref $name => $parent;
+            @implements_all [$(<$($gen),+>)?] [$name $(<$($gen),+>)?] => $($($interface),+),*;
+            @deref $(<$($gen),+>)? $name $(<$($gen),+>)? => $parent;
+            @statics $name;
             $($rest)*
         }
     };
 
-    ($(#[$attr:meta])* private interface $name:ident ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
-        $(#[$attr])* #[repr(transparent)] struct $name(pub(crate) $crate::ObjectAndEnv);
-        impl $name { $($body)* }
-        unsafe impl $crate::ReferenceType for $name {}
-        unsafe impl $crate::AsJValue for $name { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
-        unsafe impl $crate::JniType for $name { fn static_with_jni_type<R>(callback: impl FnOnce(&str) -> R) -> R { callback($jni_type) } }
+    ($(#[$attr:meta])* private interface $name:ident $(<$($gen:ident = $default:ty),+>)? ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
+        $(#[$attr])* #[repr(transparent)] struct $name $(<$($gen: $crate::ReferenceType = $default),+>)? (pub(crate) $crate::ObjectAndEnv $(, $crate::std::marker::PhantomData<($($gen,)+)>)?);
+        impl $(<$($gen: $crate::ReferenceType),+>)? $name $(<$($gen),+>)? { $($body)* }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::ReferenceType for $name $(<$($gen),+>)? {}
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::AsJValue for $name $(<$($gen),+>)? { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::JniType for $name $(<$($gen),+>)? { fn static_with_jni_type<__jni_R>(callback: impl FnOnce(&str) -> __jni_R) -> __jni_R { callback($jni_type) } }
         __jni_bindgen! {
-            $($(@implements $name => $interface;)*)*
-            @deref $name => $parent;
+            @implements_all [$(<$($gen),+>)?] [$name $(<$($gen),+>)?] => $($($interface),+),*;
+            @implements $(<$($gen),+>)? $name $(<$($gen),+>)? => $name $(<$($gen),+>)?; // For {Interface}Ext
+            @deref $(<$($gen),+>)? $name $(<$($gen),+>)? => $parent;
+            @statics $name;
             $($rest)*
         }
     };
@@ -99,61 +147,66 @@ macro_rules! __jni_bindgen {
     ($(#[$attr:meta])* public static class $name:ident ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
         $(#[$attr])* #[repr(transparent)] pub struct $name;
         impl $name { $($body)* }
-        unsafe impl $crate::JniType for $name { fn static_with_jni_type<R>(callback: impl FnOnce(&str) -> R) -> R { callback($jni_type) } }
+        unsafe impl $crate::JniType for $name { fn static_with_jni_type<__jni_R>(callback: impl FnOnce(&str) -> __jni_R) -> __jni_R { callback($jni_type) } }
         __jni_bindgen! {
             // static
             $($rest)*
         }
     };
 
-    ($(#[$attr:meta])* public final class $name:ident ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
-        $(#[$attr])* #[repr(transparent)] pub struct $name(pub(crate) $crate::ObjectAndEnv);
-        impl $name { $($body)* }
-        unsafe impl $crate::ReferenceType for $name {}
-        unsafe impl $crate::AsJValue for $name { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
-        unsafe impl $crate::JniType for $name { fn static_with_jni_type<R>(callback: impl FnOnce(&str) -> R) -> R { callback($jni_type) } }
+    ($(#[$attr:meta])* public final class $name:ident $(<$($gen:ident = $default:ty),+>)? ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
+        $(#[$attr])* #[repr(transparent)] pub struct $name $(<$($gen: $crate::ReferenceType = $default),+>)? (pub(crate) $crate::ObjectAndEnv $(, $crate::std::marker::PhantomData<($($gen,)+)>)?);
+        impl $(<$($gen: $crate::ReferenceType),+>)? $name $(<$($gen),+>)? { $($body)* }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::ReferenceType for $name $(<$($gen),+>)? {}
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::AsJValue for $name $(<$($gen),+>)? { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::JniType for $name $(<$($gen),+>)? { fn static_with_jni_type<__jni_R>(callback: impl FnOnce(&str) -> __jni_R) -> __jni_R { callback($jni_type) } }
         __jni_bindgen! {
-            $($(@implements $name => $interface;)*)*
-            @deref $name => $parent;
+            @implements_all [$(<$($gen),+>)?] [$name $(<$($gen),+>)?] => $($($interface),+),*;
+            @deref $(<$($gen),+>)? $name $(<$($gen),+>)? => $parent;
+            @statics $name;
             $($rest)*
         }
     };
 
-    ($(#[$attr:meta])* public class $name:ident ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
-        $(#[$attr])* #[repr(transparent)] pub struct $name(pub(crate) $crate::ObjectAndEnv);
-        impl $name { $($body)* }
-        unsafe impl $crate::ReferenceType for $name {}
-        unsafe impl $crate::AsJValue for $name { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
-        unsafe impl $crate::JniType for $name { fn static_with_jni_type<R>(callback: impl FnOnce(&str) -> R) -> R { callback($jni_type) } }
+    ($(#[$attr:meta])* public class $name:ident $(<$($gen:ident = $default:ty),+>)? ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
+        $(#[$attr])* #[repr(transparent)] pub struct $name $(<$($gen: $crate::ReferenceType = $default),+>)? (pub(crate) $crate::ObjectAndEnv $(, $crate::std::marker::PhantomData<($($gen,)+)>)?);
+        impl $(<$($gen: $crate::ReferenceType),+>)? $name $(<$($gen),+>)? { $($body)* }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::ReferenceType for $name $(<$($gen),+>)? {}
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::AsJValue for $name $(<$($gen),+>)? { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::JniType for $name $(<$($gen),+>)? { fn static_with_jni_type<__jni_R>(callback: impl FnOnce(&str) -> __jni_R) -> __jni_R { callback($jni_type) } }
         __jni_bindgen! {
-            $($(@implements $name => $interface;)*)*
-            @deref $name => $parent;
+            @implements_all [$(<$($gen),+>)?] [$name $(<$($gen),+>)?] => $($($interface),+),*;
+            @deref $(<$($gen),+>)? $name $(<$($gen),+>)? => $parent;
+            @statics $name;
             $($rest)*
         }
     };
 
-    ($(#[$attr:meta])* public enum $name:ident ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
-        $(#[$attr])* #[repr(transparent)] pub struct $name(pub(crate) $crate::ObjectAndEnv);
-        impl $name { $($body)* }
-        unsafe impl $crate::ReferenceType for $name {}
-        unsafe impl $crate::AsJValue for $name { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
-        unsafe impl $crate::JniType for $name { fn static_with_jni_type<R>(callback: impl FnOnce(&str) -> R) -> R { callback($jni_type) } }
+    ($(#[$attr:meta])* public enum $name:ident $(<$($gen:ident = $default:ty),+>)? ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
+        $(#[$attr])* #[repr(transparent)] pub struct $name $(<$($gen: $crate::ReferenceType = $default),+>)? (pub(crate) $crate::ObjectAndEnv $(, $crate::std::marker::PhantomData<($($gen,)+)>)?);
+        impl $(<$($gen: $crate::ReferenceType),+>)? $name $(<$($gen),+>)? { $($body)* }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::ReferenceType for $name $(<$($gen),+>)? {}
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::AsJValue for $name $(<$($gen),+>)? { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::JniType for $name $(<$($gen),+>)? { fn static_with_jni_type<__jni_R>(callback: impl FnOnce(&str) -> __jni_R) -> __jni_R { callback($jni_type) } }
         __jni_bindgen! {
-            $($(@implements $name => $interface;)*)*
-            @deref $name => $parent;
+            @implements_all [$(<$($gen),+>)?] [$name $(<$($gen),+>)?] => $($($interface),+),*;
+            @deref $(<$($gen),+>)? $name $(<$($gen),+>)? => $parent;
+            @statics $name;
             $($rest)*
         }
     };
 
-    ($(#[$attr:meta])* public interface $name:ident ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
-        $(#[$attr])* #[repr(transparent)] pub struct $name(pub(crate) $crate::ObjectAndEnv);
-        impl $name { $($body)* }
-        unsafe impl $crate::ReferenceType for $name {}
-        unsafe impl $crate::AsJValue for $name { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
-        unsafe impl $crate::JniType for $name { fn static_with_jni_type<R>(callback: impl FnOnce(&str) -> R) -> R { callback($jni_type) } }
+    ($(#[$attr:meta])* public interface $name:ident $(<$($gen:ident = $default:ty),+>)? ($jni_type:expr) extends $parent:ty $(, implements $($interface:ty),+)* { $($body:tt)* } $($rest:tt)*) => {
+        $(#[$attr])* #[repr(transparent)] pub struct $name $(<$($gen: $crate::ReferenceType = $default),+>)? (pub(crate) $crate::ObjectAndEnv $(, $crate::std::marker::PhantomData<($($gen,)+)>)?);
+        impl $(<$($gen: $crate::ReferenceType),+>)? $name $(<$($gen),+>)? { $($body)* }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::ReferenceType for $name $(<$($gen),+>)? {}
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::AsJValue for $name $(<$($gen),+>)? { fn as_jvalue(&self) -> $crate::sys::jvalue { $crate::sys::jvalue { l: self.0.object } } }
+        unsafe impl $(<$($gen: $crate::ReferenceType),+>)? $crate::JniType for $name $(<$($gen),+>)? { fn static_with_jni_type<__jni_R>(callback: impl FnOnce(&str) -> __jni_R) -> __jni_R { callback($jni_type) } }
         __jni_bindgen! {
-            $($(@implements $name => $interface;)*)*
-            @deref $name => $parent;
+            @implements_all [$(<$($gen),+>)?] [$name $(<$($gen),+>)?] => $($($interface),+),*;
+            @implements $(<$($gen),+>)? $name $(<$($gen),+>)? => $name $(<$($gen),+>)?; // For {Interface}Ext
+            @deref $(<$($gen),+>)? $name $(<$($gen),+>)? => $parent;
+            @statics $name;
             $($rest)*
         }
     };
@@ -4,7 +4,10 @@ use std::ptr::null_mut;
 
 use jni_sys::*;
 
-use crate::{AsJValue, Env, JniType, Local, ObjectAndEnv, ReferenceType, ThrowableType};
+use crate::{
+    ArrayCritical, ArrayElements, AsJValue, Env, JavaException, JniType, Local, ObjectAndEnv, ReferenceType,
+    ThrowableType,
+};
 
 /// A Java Array of some POD-like type such as bool, jbyte, jchar, jshort, jint, jlong, jfloat, or jdouble.
 ///
@@ -36,6 +39,26 @@ where
     /// Uses env.Set{Type}ArrayRegion to set the contents of the java array from \[start .. start + elements.len())
     fn set_region(&self, start: usize, elements: &[T]);
 
+    /// Uses env.Get{Type}ArrayElements to borrow the contents of the java array without copying them, if the VM can pin
+    /// the array in place.  Changes are written back once the guard is dropped.  Fails with an `OutOfMemoryError` if a
+    /// copy was needed and couldn't be allocated.
+    ///
+    /// **unsafe**:  Nothing else may access the java array while the guard is alive:  neither other guards, nor
+    /// get_region/set_region, nor Java code on other threads.
+    unsafe fn elements(&self) -> Result<ArrayElements<'_, T>, JavaException>;
+
+    /// Uses env.GetPrimitiveArrayCritical to borrow the contents of the java array for the duration of `f` - more
+    /// likely than [elements](Self::elements) to avoid a copy, but the VM may pause garbage collection (and with it,
+    /// other threads) meanwhile, so keep `f` short.  Fails with an `OutOfMemoryError` if a copy couldn't be allocated.
+    ///
+    /// The guard can't escape `f`, but no JNI calls may be made until the elements are released - which the type system
+    /// can't enforce:  besides an [Env], [Local], or [Ref](crate::Ref) captured by `f`, JNI calls can be made through
+    /// [Env::ambient], [VM::with_env](crate::VM::with_env), static methods of generated bindings, or by dropping a
+    /// [Global](crate::Global), none of which `f`'s type can rule out.
+    ///
+    /// **unsafe**:  As for [elements](Self::elements).  `f` must not make JNI calls, by any means.
+    unsafe fn critical<R>(&self, f: impl for<'c> FnOnce(ArrayCritical<'c, T>) -> R) -> Result<R, JavaException>;
+
     /// Uses env.New{Type}Array + Set{Type}ArrayRegion to create a new java array containing a copy of "elements".
     fn from<'env>(env: Env<'env>, elements: &[T]) -> Local<'env, Self> {
         let array = Self::new(env, elements.len());
@@ -76,7 +99,7 @@ where
 }
 
 macro_rules! primitive_array {
-    (#[repr(transparent)] pub struct $name:ident = $type_str:expr, $type:ident { $new_array:ident $set_region:ident $get_region:ident } ) => {
+    (#[repr(transparent)] pub struct $name:ident = $type_str:expr, $type:ident { $new_array:ident $set_region:ident $get_region:ident $get_elements:ident $release_elements:ident } ) => {
         /// A [PrimitiveArray] implementation.
         #[repr(transparent)]
         pub struct $name(ObjectAndEnv);
@@ -121,6 +144,51 @@ macro_rules! primitive_array {
                 unsafe { ((**self.0.env).v1_2.GetArrayLength)(self.0.env as *mut _, self.0.object) as usize }
             }
 
+            unsafe fn elements(&self) -> Result<ArrayElements<'_, $type>, JavaException> {
+                let env = self.0.env;
+                let len = self.len();
+                let mut is_copy = false;
+                let elements = ((**env).v1_1.$get_elements)(env, self.0.object, &mut is_copy);
+                if elements.is_null() && (len != 0 || ((**env).v1_2.ExceptionCheck)(env)) {
+                    return Err(Env::from_raw(env).take_java_exception_or_throw(
+                        "java/lang/OutOfMemoryError\0",
+                        concat!(stringify!($get_elements), " failed"),
+                    ));
+                }
+                let release = (**env).v1_1.$release_elements;
+                Ok(ArrayElements::from_raw(
+                    env,
+                    self.0.object,
+                    elements,
+                    len,
+                    is_copy,
+                    release,
+                ))
+            }
+
+            unsafe fn critical<R>(
+                &self,
+                f: impl for<'c> FnOnce(ArrayCritical<'c, $type>) -> R,
+            ) -> Result<R, JavaException> {
+                let env = self.0.env;
+                let len = self.len();
+                let mut is_copy = false;
+                let elements = ((**env).v1_2.GetPrimitiveArrayCritical)(env, self.0.object, &mut is_copy);
+                if elements.is_null() && (len != 0 || ((**env).v1_2.ExceptionCheck)(env)) {
+                    return Err(Env::from_raw(env).take_java_exception_or_throw(
+                        "java/lang/OutOfMemoryError\0",
+                        "GetPrimitiveArrayCritical failed",
+                    ));
+                }
+                Ok(f(ArrayCritical::from_raw(
+                    env,
+                    self.0.object,
+                    elements as *mut $type,
+                    len,
+                    is_copy,
+                )))
+            }
+
             fn get_region(&self, start: usize, elements: &mut [$type]) {
                 assert!(start <= std::i32::MAX as usize); // jsize == jint == i32
                 assert!(elements.len() <= std::i32::MAX as usize); // jsize == jint == i32
@@ -168,14 +236,14 @@ macro_rules! primitive_array {
     };
 }
 
-primitive_array! { #[repr(transparent)] pub struct BooleanArray = "[Z\0", bool    { NewBooleanArray SetBooleanArrayRegion GetBooleanArrayRegion } }
-primitive_array! { #[repr(transparent)] pub struct ByteArray    = "[B\0", jbyte   { NewByteArray    SetByteArrayRegion    GetByteArrayRegion    } }
-primitive_array! { #[repr(transparent)] pub struct CharArray    = "[C\0", jchar   { NewCharArray    SetCharArrayRegion    GetCharArrayRegion    } }
-primitive_array! { #[repr(transparent)] pub struct ShortArray   = "[S\0", jshort  { NewShortArray   SetShortArrayRegion   GetShortArrayRegion   } }
-primitive_array! { #[repr(transparent)] pub struct IntArray     = "[I\0", jint    { NewIntArray     SetIntArrayRegion     GetIntArrayRegion     } }
-primitive_array! { #[repr(transparent)] pub struct LongArray    = "[J\0", jlong   { NewLongArray    SetLongArrayRegion    GetLongArrayRegion    } }
-primitive_array! { #[repr(transparent)] pub struct FloatArray   = "[F\0", jfloat  { NewFloatArray   SetFloatArrayRegion   GetFloatArrayRegion   } }
-primitive_array! { #[repr(transparent)] pub struct DoubleArray  = "[D\0", jdouble { NewDoubleArray  SetDoubleArrayRegion  GetDoubleArrayRegion  } }
+primitive_array! { #[repr(transparent)] pub struct BooleanArray = "[Z\0", bool    { NewBooleanArray SetBooleanArrayRegion GetBooleanArrayRegion GetBooleanArrayElements ReleaseBooleanArrayElements } }
+primitive_array! { #[repr(transparent)] pub struct ByteArray    = "[B\0", jbyte   { NewByteArray    SetByteArrayRegion    GetByteArrayRegion    GetByteArrayElements    ReleaseByteArrayElements    } }
+primitive_array! { #[repr(transparent)] pub struct CharArray    = "[C\0", jchar   { NewCharArray    SetCharArrayRegion    GetCharArrayRegion    GetCharArrayElements    ReleaseCharArrayElements    } }
+primitive_array! { #[repr(transparent)] pub struct ShortArray   = "[S\0", jshort  { NewShortArray   SetShortArrayRegion   GetShortArrayRegion   GetShortArrayElements   ReleaseShortArrayElements   } }
+primitive_array! { #[repr(transparent)] pub struct IntArray     = "[I\0", jint    { NewIntArray     SetIntArrayRegion     GetIntArrayRegion     GetIntArrayElements     ReleaseIntArrayElements     } }
+primitive_array! { #[repr(transparent)] pub struct LongArray    = "[J\0", jlong   { NewLongArray    SetLongArrayRegion    GetLongArrayRegion    GetLongArrayElements    ReleaseLongArrayElements    } }
+primitive_array! { #[repr(transparent)] pub struct FloatArray   = "[F\0", jfloat  { NewFloatArray   SetFloatArrayRegion   GetFloatArrayRegion   GetFloatArrayElements   ReleaseFloatArrayElements   } }
+primitive_array! { #[repr(transparent)] pub struct DoubleArray  = "[D\0", jdouble { NewDoubleArray  SetDoubleArrayRegion  GetDoubleArrayRegion  GetDoubleArrayElements  ReleaseDoubleArrayElements  } }
 
 /// A Java Array of reference types (classes, interfaces, other arrays, etc.)
 ///
@@ -206,6 +274,7 @@ impl<T: ReferenceType, E: ThrowableType> ObjectArray<T, E> {
         unsafe {
             let fill = null_mut();
             let object = ((**jnienv).v1_2.NewObjectArray)(jnienv, size, class, fill);
+            ((**jnienv).v1_2.DeleteLocalRef)(jnienv, class);
             let exception = ((**jnienv).v1_2.ExceptionOccurred)(jnienv);
             assert!(exception.is_null()); // Only sane exception here is an OOM exception
             Local::from_raw(env, object)
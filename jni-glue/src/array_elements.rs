@@ -0,0 +1,162 @@
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::c_void;
+use std::ptr::NonNull;
+use std::slice;
+
+use jni_sys::*;
+
+/// The elements of a [PrimitiveArray](crate::PrimitiveArray), borrowed with `Get{Type}ArrayElements` by
+/// [PrimitiveArray::elements](crate::PrimitiveArray::elements) - either pinned in place, or copied, at the VM's
+/// discretion.  When dropped, any changes are written back to the array and the elements are released.
+pub struct ArrayElements<'a, T> {
+    env: *mut JNIEnv,
+    array: jarray,
+    elements: *mut T, // Null for empty arrays the VM didn't bother allocating anything for
+    len: usize,
+    is_copy: bool,
+    release: unsafe extern "system" fn(*mut JNIEnv, jarray, *mut T, jint),
+    _array: PhantomData<&'a jarray>,
+}
+
+impl<'a, T> ArrayElements<'a, T> {
+    pub(crate) unsafe fn from_raw(
+        env: *mut JNIEnv,
+        array: jarray,
+        elements: *mut T,
+        len: usize,
+        is_copy: bool,
+        release: unsafe extern "system" fn(*mut JNIEnv, jarray, *mut T, jint),
+    ) -> Self {
+        Self {
+            env,
+            array,
+            elements,
+            len,
+            is_copy,
+            release,
+            _array: PhantomData,
+        }
+    }
+
+    /// Are these a copy of the array's elements, rather than the array's own (pinned) memory?  If so, changes only show
+    /// up in the array once [commit](Self::commit)ted or dropped.
+    pub fn is_copy(&self) -> bool {
+        self.is_copy
+    }
+
+    /// Writes any changes back to the array (`JNI_COMMIT`), keeping the elements borrowed.
+    pub fn commit(&mut self) {
+        if self.is_copy && !self.elements.is_null() {
+            unsafe { (self.release)(self.env, self.array, self.elements, JNI_COMMIT) };
+        }
+    }
+
+    /// Releases the elements without writing any uncommitted changes back to the array (`JNI_ABORT`.)  Changes to
+    /// pinned elements can't be undone, however - see [is_copy](Self::is_copy).
+    pub fn abort(mut self) {
+        self.release_with(JNI_ABORT);
+    }
+
+    fn release_with(&mut self, mode: jint) {
+        if !self.elements.is_null() {
+            unsafe { (self.release)(self.env, self.array, self.elements, mode) };
+            self.elements = std::ptr::null_mut();
+        }
+    }
+}
+
+impl<'a, T> Deref for ArrayElements<'a, T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(non_null(self.elements), self.len) }
+    }
+}
+
+impl<'a, T> DerefMut for ArrayElements<'a, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(non_null(self.elements), self.len) }
+    }
+}
+
+impl<'a, T> Drop for ArrayElements<'a, T> {
+    fn drop(&mut self) {
+        self.release_with(0);
+    }
+}
+
+/// The elements of a [PrimitiveArray](crate::PrimitiveArray), borrowed with `GetPrimitiveArrayCritical` by
+/// [PrimitiveArray::critical](crate::PrimitiveArray::critical) - which hands them to a callback that mustn't make JNI
+/// calls, as they're forbidden until the elements are released.  Released when dropped, writing back any changes.
+pub struct ArrayCritical<'a, T> {
+    env: *mut JNIEnv,
+    array: jarray,
+    elements: *mut T,
+    len: usize,
+    is_copy: bool,
+    _array: PhantomData<&'a jarray>,
+}
+
+impl<'a, T> ArrayCritical<'a, T> {
+    pub(crate) unsafe fn from_raw(
+        env: *mut JNIEnv,
+        array: jarray,
+        elements: *mut T,
+        len: usize,
+        is_copy: bool,
+    ) -> Self {
+        Self {
+            env,
+            array,
+            elements,
+            len,
+            is_copy,
+            _array: PhantomData,
+        }
+    }
+
+    /// Are these a copy of the array's elements, rather than the array's own (pinned) memory?
+    pub fn is_copy(&self) -> bool {
+        self.is_copy
+    }
+
+    /// Releases the elements without writing any changes back to the array (`JNI_ABORT`), if they're a copy.
+    pub fn abort(mut self) {
+        self.release_with(JNI_ABORT);
+    }
+
+    fn release_with(&mut self, mode: jint) {
+        if !self.elements.is_null() {
+            let elements = self.elements as *mut c_void;
+            unsafe { ((**self.env).v1_2.ReleasePrimitiveArrayCritical)(self.env, self.array, elements, mode) };
+            self.elements = std::ptr::null_mut();
+        }
+    }
+}
+
+impl<'a, T> Deref for ArrayCritical<'a, T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(non_null(self.elements), self.len) }
+    }
+}
+
+impl<'a, T> DerefMut for ArrayCritical<'a, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(non_null(self.elements), self.len) }
+    }
+}
+
+impl<'a, T> Drop for ArrayCritical<'a, T> {
+    fn drop(&mut self) {
+        self.release_with(0);
+    }
+}
+
+fn non_null<T>(elements: *mut T) -> *mut T {
+    if elements.is_null() {
+        NonNull::dangling().as_ptr()
+    } else {
+        elements
+    }
+}
@@ -0,0 +1,251 @@
+//! Lookups of classes, methods, and fields that are done once and then reused, for statics in generated code.
+//!
+//! `FindClass` and `Get{Static}{Method,Field}ID` are comparatively expensive, and `FindClass` hands back a local
+//! reference.  The caches below do each lookup the first time it's needed, keep the class alive with a global
+//! reference (which also keeps its method and field IDs valid), and reuse both from then on.
+//!
+//! Caches remember the [VM](crate::VM) that filled them.  Lookups made through a different VM bypass the cache,
+//! returning a local reference to the class as the uncached [Env] methods do.
+
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::os::raw::c_void;
+use std::sync::{Mutex, OnceLock};
+
+use jni_sys::*;
+
+use crate::{Env, MemberKind, MissingMember, ReferenceType};
+
+/// A class looked up with `FindClass` once, and kept as a global reference from then on.
+pub struct ClassCache(OnceLock<(usize, usize)>); // (JavaVM, global jclass)
+
+impl ClassCache {
+    pub const fn new() -> Self {
+        Self(OnceLock::new())
+    }
+
+    /// The cache for `R`'s class, for generic code (which can't have a static per `R` of its own.)
+    pub(crate) fn of<R: ReferenceType>() -> &'static ClassCache {
+        static CACHES: Mutex<BTreeMap<TypeId, &'static ClassCache>> = Mutex::new(BTreeMap::new());
+        let mut caches = CACHES.lock().unwrap();
+        caches
+            .entry(TypeId::of::<R>())
+            .or_insert_with(|| Box::leak(Box::new(ClassCache::new())))
+    }
+
+    /// Like [Env::require_class], but only looked up the first time.
+    ///
+    /// **unsafe**:  `class` must be a null terminated JNI class name, as for [Env::require_class].
+    pub unsafe fn require(&self, env: Env, class: &str) -> jclass {
+        self.try_require(env, class)
+            .unwrap_or_else(|missing| panic!("{}", missing))
+    }
+
+    /// Like [Env::try_require_class], but only looked up the first time.  Failed lookups aren't cached.
+    ///
+    /// **unsafe**:  `class` must be a null terminated JNI class name, as for [Env::require_class].
+    pub unsafe fn try_require(&self, env: Env, class: &str) -> Result<jclass, MissingMember> {
+        self.try_require_cached(env, class).map(|(class, _cached)| class)
+    }
+
+    /// The class, and if it came from the cache (as opposed to a local reference for a different VM.)
+    pub(crate) unsafe fn try_require_cached(&self, env: Env, class: &str) -> Result<(jclass, bool), MissingMember> {
+        let vm = env.vm().as_raw() as usize;
+        let &(cached_vm, cached_class) = match self.0.get() {
+            Some(cached) => cached,
+            None => {
+                let jnienv = env.as_raw();
+                let local = env.try_require_class(class)?;
+                let global = ((**jnienv).v1_2.NewGlobalRef)(jnienv, local);
+                ((**jnienv).v1_2.DeleteLocalRef)(jnienv, local);
+                if let Err((_, global)) = self.0.set((vm, global as usize)) {
+                    // Another thread filled the cache first
+                    ((**jnienv).v1_2.DeleteGlobalRef)(jnienv, global as jclass);
+                }
+                self.0.get().unwrap()
+            }
+        };
+        if cached_vm == vm {
+            Ok((cached_class as jclass, true))
+        } else {
+            Ok((env.try_require_class(class)?, false))
+        }
+    }
+
+    /// A member of the class, cached in `id` - which must only ever be used for the same member.
+    unsafe fn try_require_member(
+        &self,
+        id: &OnceLock<usize>,
+        kind: MemberKind,
+        env: Env,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<(jclass, *mut c_void), MissingMember> {
+        let (jclass, cached) = self.try_require_cached(env, class)?;
+        if !cached {
+            return match env.try_require_member(kind, jclass, Some(class), name, descriptor) {
+                Ok(id) => Ok((jclass, id)),
+                Err(missing) => {
+                    let jnienv = env.as_raw();
+                    ((**jnienv).v1_2.DeleteLocalRef)(jnienv, jclass);
+                    Err(missing)
+                }
+            };
+        }
+        if let Some(&id) = id.get() {
+            return Ok((jclass, id as *mut c_void));
+        }
+        let found = env.try_require_member(kind, jclass, Some(class), name, descriptor)?;
+        let _ = id.set(found as usize); // Any other thread would've found the same ID
+        Ok((jclass, found))
+    }
+}
+
+impl Default for ClassCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A class and one of its methods, looked up once.  Each cache should only ever be used for the same method.
+pub struct MethodCache {
+    class: ClassCache,
+    method: OnceLock<usize>,
+}
+
+impl MethodCache {
+    pub const fn new() -> Self {
+        Self {
+            class: ClassCache::new(),
+            method: OnceLock::new(),
+        }
+    }
+
+    /// Like [Env::require_class_method], but only looked up the first time.
+    pub unsafe fn require_class_method(
+        &self,
+        env: Env,
+        class: &str,
+        method: &str,
+        descriptor: &str,
+    ) -> (jclass, jmethodID) {
+        self.try_require_class_method(env, class, method, descriptor)
+            .unwrap_or_else(|missing| panic!("{}", missing))
+    }
+
+    /// Like [Env::require_class_static_method], but only looked up the first time.
+    pub unsafe fn require_class_static_method(
+        &self,
+        env: Env,
+        class: &str,
+        method: &str,
+        descriptor: &str,
+    ) -> (jclass, jmethodID) {
+        self.try_require_class_static_method(env, class, method, descriptor)
+            .unwrap_or_else(|missing| panic!("{}", missing))
+    }
+
+    /// Like [Env::try_require_class_method], but only looked up the first time.  Failed lookups aren't cached.
+    pub unsafe fn try_require_class_method(
+        &self,
+        env: Env,
+        class: &str,
+        method: &str,
+        descriptor: &str,
+    ) -> Result<(jclass, jmethodID), MissingMember> {
+        self.class
+            .try_require_member(&self.method, MemberKind::Method, env, class, method, descriptor)
+            .map(|(class, id)| (class, id as jmethodID))
+    }
+
+    /// Like [Env::try_require_class_static_method], but only looked up the first time.  Failed lookups aren't cached.
+    pub unsafe fn try_require_class_static_method(
+        &self,
+        env: Env,
+        class: &str,
+        method: &str,
+        descriptor: &str,
+    ) -> Result<(jclass, jmethodID), MissingMember> {
+        self.class
+            .try_require_member(&self.method, MemberKind::StaticMethod, env, class, method, descriptor)
+            .map(|(class, id)| (class, id as jmethodID))
+    }
+}
+
+impl Default for MethodCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A class and one of its fields, looked up once.  Each cache should only ever be used for the same field.
+pub struct FieldCache {
+    class: ClassCache,
+    field: OnceLock<usize>,
+}
+
+impl FieldCache {
+    pub const fn new() -> Self {
+        Self {
+            class: ClassCache::new(),
+            field: OnceLock::new(),
+        }
+    }
+
+    /// Like [Env::require_class_field], but only looked up the first time.
+    pub unsafe fn require_class_field(
+        &self,
+        env: Env,
+        class: &str,
+        field: &str,
+        descriptor: &str,
+    ) -> (jclass, jfieldID) {
+        self.try_require_class_field(env, class, field, descriptor)
+            .unwrap_or_else(|missing| panic!("{}", missing))
+    }
+
+    /// Like [Env::require_class_static_field], but only looked up the first time.
+    pub unsafe fn require_class_static_field(
+        &self,
+        env: Env,
+        class: &str,
+        field: &str,
+        descriptor: &str,
+    ) -> (jclass, jfieldID) {
+        self.try_require_class_static_field(env, class, field, descriptor)
+            .unwrap_or_else(|missing| panic!("{}", missing))
+    }
+
+    /// Like [Env::try_require_class_field], but only looked up the first time.  Failed lookups aren't cached.
+    pub unsafe fn try_require_class_field(
+        &self,
+        env: Env,
+        class: &str,
+        field: &str,
+        descriptor: &str,
+    ) -> Result<(jclass, jfieldID), MissingMember> {
+        self.class
+            .try_require_member(&self.field, MemberKind::Field, env, class, field, descriptor)
+            .map(|(class, id)| (class, id as jfieldID))
+    }
+
+    /// Like [Env::try_require_class_static_field], but only looked up the first time.  Failed lookups aren't cached.
+    pub unsafe fn try_require_class_static_field(
+        &self,
+        env: Env,
+        class: &str,
+        field: &str,
+        descriptor: &str,
+    ) -> Result<(jclass, jfieldID), MissingMember> {
+        self.class
+            .try_require_member(&self.field, MemberKind::StaticField, env, class, field, descriptor)
+            .map(|(class, id)| (class, id as jfieldID))
+    }
+}
+
+impl Default for FieldCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
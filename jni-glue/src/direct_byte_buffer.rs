@@ -0,0 +1,130 @@
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::ptr::NonNull;
+use std::slice;
+
+use jni_sys::*;
+
+use crate::{Env, JavaException, Local, ObjectAndEnv, ReferenceType};
+
+/// A marker type for `java.nio.ByteBuffer` and its subclasses, implemented by generated bindings.  Gives access to the
+/// memory of direct buffers, and lets [DirectByteBuffer]s be created over Rust memory.
+pub trait ByteBufferType: ReferenceType {
+    /// The capacity of this direct buffer, or [None] if it isn't one (or the VM doesn't support direct buffers.)
+    fn direct_capacity(&self) -> Option<usize> {
+        direct_raw(self).map(|(_, capacity)| capacity)
+    }
+
+    /// Borrows the memory of this direct buffer, with `GetDirectBufferAddress` and `GetDirectBufferCapacity`.  Returns
+    /// [None] if it isn't one - e.g. if it's backed by a Java `byte[]` instead.  Ignores the buffer's position and limit.
+    ///
+    /// **unsafe**:  Nothing (e.g. Java code on another thread) may write to the buffer while the slice is alive.
+    unsafe fn direct_bytes(&self) -> Option<&[u8]> {
+        let (address, capacity) = direct_raw(self)?;
+        Some(slice::from_raw_parts(address, capacity))
+    }
+
+    /// Mutably borrows the memory of this direct buffer.  See [direct_bytes](Self::direct_bytes).
+    ///
+    /// **unsafe**:  Nothing else (e.g. Java code on another thread, or another slice of the same buffer) may read or
+    /// write the buffer while the slice is alive.
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn direct_bytes_mut(&self) -> Option<&mut [u8]> {
+        let (address, capacity) = direct_raw(self)?;
+        Some(slice::from_raw_parts_mut(address, capacity))
+    }
+}
+
+fn direct_raw<T: ByteBufferType + ?Sized>(buffer: &T) -> Option<(*mut u8, usize)> {
+    // Sound as per the contract of ReferenceType
+    let object_and_env = unsafe { *(buffer as *const T as *const ObjectAndEnv) };
+    let env = object_and_env.env;
+    let capacity = unsafe { ((**env).v1_4.GetDirectBufferCapacity)(env, object_and_env.object) };
+    if capacity < 0 {
+        return None;
+    }
+    let address = unsafe { ((**env).v1_4.GetDirectBufferAddress)(env, object_and_env.object) } as *mut u8;
+    match (address.is_null(), capacity) {
+        (false, _) => Some((address, capacity as usize)),
+        (true, 0) => Some((NonNull::dangling().as_ptr(), 0)),
+        (true, _) => None,
+    }
+}
+
+/// A direct `java.nio.ByteBuffer` over Rust memory, created with `NewDirectByteBuffer`, which the buffer mutably
+/// borrows for `'data` - so Rust code can't touch it while Java might.  Zero-copy, for e.g. camera and audio frames.
+///
+/// Derefs to the `ByteBuffer`, and converts to `Option<&ByteBuffer>` - so it can be passed to generated bindings
+/// directly:
+///
+/// ```rust,no_run
+/// # use jni_glue as __jni_bindgen;
+/// # use jni_glue::__jni_bindgen;
+/// # __jni_bindgen! {
+/// #     public class ByteBuffer ("java/nio/ByteBuffer\0") extends () {
+/// #         pub fn put<'env>(&'env self, arg0: impl __jni_bindgen::std::convert::Into<__jni_bindgen::std::option::Option<&'env ByteBuffer>>) { unimplemented!() }
+/// #     }
+/// # }
+/// # impl __jni_bindgen::ByteBufferType for ByteBuffer {}
+/// use jni_glue::{DirectByteBuffer, Env, JavaException};
+///
+/// fn send_frame(env: Env, target: &ByteBuffer, frame: &mut [u8]) -> Result<(), JavaException> {
+///     let buffer = unsafe { DirectByteBuffer::<ByteBuffer>::new(env, frame) }?;
+///     target.put(&buffer);
+///     Ok(())
+/// }
+/// ```
+pub struct DirectByteBuffer<'env, 'data, T: ByteBufferType> {
+    buffer: Local<'env, T>,
+    _data: PhantomData<&'data mut [u8]>,
+}
+
+impl<'env, 'data, T: ByteBufferType> DirectByteBuffer<'env, 'data, T> {
+    /// Creates a direct buffer over `data`.  `T` must be `java.nio.ByteBuffer` or `java.nio.MappedByteBuffer`, which
+    /// the VM's direct buffers extend.  Fails with an `IllegalArgumentException` if `data` is too long for a buffer, or
+    /// an `UnsupportedOperationException` if the VM doesn't support direct buffers.
+    ///
+    /// **unsafe**:  `'data` only limits this handle - Java code must not keep the buffer, or any view of it (such as a
+    /// `slice()` or `asIntBuffer()`), past it either.
+    pub unsafe fn new(env: Env<'env>, data: &'data mut [u8]) -> Result<Self, JavaException> {
+        if data.len() > jint::MAX as usize {
+            return Err(env.take_java_exception_or_throw(
+                "java/lang/IllegalArgumentException\0",
+                "Direct buffer capacity exceeds Integer.MAX_VALUE",
+            ));
+        }
+        let jnienv = env.as_raw();
+        let buffer = ((**jnienv).v1_4.NewDirectByteBuffer)(jnienv, data.as_mut_ptr() as *mut _, data.len() as jlong);
+        if buffer.is_null() {
+            return Err(env.take_java_exception_or_throw(
+                "java/lang/UnsupportedOperationException\0",
+                "NewDirectByteBuffer failed",
+            ));
+        }
+        Ok(Self {
+            buffer: Local::from_raw(env, buffer),
+            _data: PhantomData,
+        })
+    }
+
+    pub fn env(&self) -> Env<'env> {
+        self.buffer.env()
+    }
+
+    pub fn as_raw(&self) -> jobject {
+        self.buffer.as_raw()
+    }
+}
+
+impl<'env, 'data, T: ByteBufferType> Deref for DirectByteBuffer<'env, 'data, T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl<'a, 'env, 'data, T: ByteBufferType> From<&'a DirectByteBuffer<'env, 'data, T>> for Option<&'a T> {
+    fn from(buffer: &'a DirectByteBuffer<'env, 'data, T>) -> Self {
+        Some(&**buffer)
+    }
+}
@@ -1,10 +1,15 @@
+use std::ffi::CStr;
 use std::marker::PhantomData;
-use std::os::raw::c_char;
-use std::ptr::null_mut;
+use std::mem;
+use std::os::raw::{c_char, c_void};
+use std::ptr::{null, null_mut};
 
 use jni_sys::*;
 
-use crate::{AsJValue, Local, ReferenceType, ThrowableType, VM};
+use crate::{
+    AsJValue, ClassCache, JavaException, Local, MemberKind, MissingMember, ObjectAndEnv, ReferenceType, ThrowableType,
+    VM,
+};
 
 /// FFI:  Use **Env** instead of \*const JNIEnv.  This represents a per-thread Java exection environment.
 ///
@@ -69,6 +74,16 @@ impl<'env> Env<'env> {
         self.env
     }
 
+    /// Get the current thread's Env from the [VM::ambient] VM, attaching the current thread if necessary.  A thread
+    /// attached here is detached again when it exits.
+    ///
+    /// **unsafe**:  The caller picks `'env`, and must not let it outlive the current thread's attachment or local
+    /// reference frame.  Panics if no ambient [VM] was registered with [VM::set_ambient].
+    pub unsafe fn ambient() -> Self {
+        let vm = VM::ambient().expect("jni_glue::Env::ambient():  no VM registered with VM::set_ambient()");
+        Self::from_raw(vm.attach_current_thread(true))
+    }
+
     pub fn vm(&self) -> VM {
         let jni_env = self.as_raw();
         let mut vm = null_mut();
@@ -96,6 +111,135 @@ impl<'env> Env<'env> {
         ((**self.env).v1_2.ReleaseStringChars)(self.env, string, chars as *const _)
     }
 
+    /// Copies `len` characters of `string` from `start` into `buf`, without pinning or copying the whole string.
+    pub unsafe fn get_string_region(self, string: jstring, start: jsize, len: jsize, buf: *mut jchar) {
+        ((**self.env).v1_2.GetStringRegion)(self.env, string, start, len, buf as *mut _)
+    }
+
+    /// **unsafe**:  `chars` must be null terminated [Modified UTF-8](crate::encode_modified_utf8).
+    pub unsafe fn new_string_utf(self, chars: *const c_char) -> jstring {
+        ((**self.env).v1_2.NewStringUTF)(self.env, chars)
+    }
+
+    /// The length of `string` in bytes of [Modified UTF-8](crate::encode_modified_utf8), excluding the null terminator.
+    pub unsafe fn get_string_utf_length(self, string: jstring) -> jsize {
+        ((**self.env).v1_2.GetStringUTFLength)(self.env, string)
+    }
+
+    pub unsafe fn get_string_utf_chars(self, string: jstring) -> *const c_char {
+        ((**self.env).v1_2.GetStringUTFChars)(self.env, string, null_mut())
+    }
+
+    pub unsafe fn release_string_utf_chars(self, string: jstring, chars: *const c_char) {
+        ((**self.env).v1_2.ReleaseStringUTFChars)(self.env, string, chars)
+    }
+
+    // Exception Methods
+
+    /// Throws `throwable`, e.g. from a native method.  Java sees it once the native method returns.
+    pub fn throw<T: ThrowableType>(self, throwable: &T) {
+        // Sound as per the contract of ReferenceType
+        let object = unsafe { (*(throwable as *const T as *const ObjectAndEnv)).object };
+        unsafe { ((**self.env).v1_2.Throw)(self.env, object) };
+    }
+
+    /// Throws a new `T`, constructed with `message`.  If that fails (say `T` has no `(String)` constructor), the exception
+    /// the failure threw is left pending instead.
+    pub fn throw_new<T: ThrowableType>(self, message: &str) {
+        T::static_with_jni_type(|class| unsafe { self.throw_new_caused(class, message, null_mut()) })
+    }
+
+    /// Is an exception pending?
+    pub fn exception_check(self) -> bool {
+        unsafe { ((**self.env).v1_2.ExceptionCheck)(self.env) }
+    }
+
+    /// Prints the pending exception and its stack trace to stderr (logcat on Android), clearing it.  For debugging.
+    pub fn exception_describe(self) {
+        unsafe { ((**self.env).v1_2.ExceptionDescribe)(self.env) }
+    }
+
+    /// Clears the pending exception, if any.
+    pub fn exception_clear(self) {
+        unsafe { ((**self.env).v1_2.ExceptionClear)(self.env) }
+    }
+
+    /// Throws a new `class` (a null terminated JNI class name), constructed with `message` and `cause` - or just
+    /// `message`, if `cause` is null.  If that fails, the exception the failure threw is left pending instead - or
+    /// failing that, `cause` is thrown.
+    pub(crate) unsafe fn throw_new_caused(self, class: &str, message: &str, cause: jthrowable) {
+        debug_assert!(class.ends_with('\0'));
+        let chars = message.encode_utf16().collect::<Vec<_>>();
+
+        let class = ((**self.env).v1_2.FindClass)(self.env, class.as_ptr() as *const c_char);
+        let init = if class.is_null() {
+            null_mut()
+        } else if cause.is_null() {
+            ((**self.env).v1_2.GetMethodID)(self.env, class, c"<init>".as_ptr(), c"(Ljava/lang/String;)V".as_ptr())
+        } else {
+            ((**self.env).v1_2.GetMethodID)(
+                self.env,
+                class,
+                c"<init>".as_ptr(),
+                c"(Ljava/lang/String;Ljava/lang/Throwable;)V".as_ptr(),
+            )
+        };
+        let message = if init.is_null() {
+            null_mut()
+        } else {
+            self.new_string(chars.as_ptr(), chars.len() as jsize)
+        };
+        let exception = if message.is_null() {
+            null_mut()
+        } else {
+            let args = [jvalue { l: message }, jvalue { l: cause }];
+            ((**self.env).v1_2.NewObjectA)(self.env, class, init, args.as_ptr())
+        };
+
+        if !exception.is_null() {
+            ((**self.env).v1_2.Throw)(self.env, exception);
+        } else if !self.exception_check() && !cause.is_null() {
+            ((**self.env).v1_2.Throw)(self.env, cause);
+        }
+        for local in [exception, message, class] {
+            if !local.is_null() {
+                ((**self.env).v1_2.DeleteLocalRef)(self.env, local);
+            }
+        }
+    }
+
+    /// Takes the pending exception, if any, clearing it.
+    pub(crate) unsafe fn take_java_exception(self) -> Option<JavaException> {
+        let exception = ((**self.env).v1_2.ExceptionOccurred)(self.env);
+        if exception.is_null() {
+            return None;
+        }
+        ((**self.env).v1_2.ExceptionClear)(self.env);
+        let result = JavaException::from_raw(self, exception);
+        ((**self.env).v1_2.DeleteLocalRef)(self.env, exception);
+        Some(result)
+    }
+
+    /// [take_exception_or_throw](Self::take_exception_or_throw), as a [JavaException].
+    pub(crate) unsafe fn take_java_exception_or_throw(self, class: &str, message: &str) -> JavaException {
+        if !((**self.env).v1_2.ExceptionCheck)(self.env) {
+            self.throw_new_caused(class, message, null_mut());
+        }
+        self.take_java_exception().unwrap()
+    }
+
+    /// Takes the exception a failed JNI call should have thrown.  Some VMs fail without throwing anything (e.g. HotSpot's
+    /// `EnsureLocalCapacity`, for capacities over its limit), so throw a new `class` with `message` for them first.
+    pub(crate) unsafe fn take_exception_or_throw<E: ThrowableType>(self, class: &str, message: &str) -> Local<'env, E> {
+        if !((**self.env).v1_2.ExceptionCheck)(self.env) {
+            self.throw_new_caused(class, message, null_mut());
+        }
+        let exception = ((**self.env).v1_2.ExceptionOccurred)(self.env);
+        assert!(!exception.is_null());
+        ((**self.env).v1_2.ExceptionClear)(self.env);
+        Local::from_raw(self, exception)
+    }
+
     // Query Methods
 
     pub unsafe fn require_class(self, class: &str) -> jclass {
@@ -188,6 +332,181 @@ impl<'env> Env<'env> {
         (class, self.require_static_field(class, method, descriptor))
     }
 
+    // Fallible Query Methods
+    //
+    // Like the above, but return an error instead of panicing if the class or member doesn't exist, clearing the
+    // exception the failed lookup threw.
+
+    pub unsafe fn try_require_class(self, class: &str) -> Result<jclass, MissingMember> {
+        debug_assert!(class.ends_with('\0'));
+        let jclass = ((**self.env).v1_2.FindClass)(self.env, class.as_ptr() as *const c_char);
+        if jclass.is_null() {
+            ((**self.env).v1_2.ExceptionClear)(self.env);
+            return Err(MissingMember::new(MemberKind::Class, class, "", ""));
+        }
+        Ok(jclass)
+    }
+
+    pub unsafe fn try_require_method(
+        self,
+        class: jclass,
+        method: &str,
+        descriptor: &str,
+    ) -> Result<jmethodID, MissingMember> {
+        self.try_require_member(MemberKind::Method, class, None, method, descriptor)
+            .map(|id| id as jmethodID)
+    }
+
+    pub unsafe fn try_require_static_method(
+        self,
+        class: jclass,
+        method: &str,
+        descriptor: &str,
+    ) -> Result<jmethodID, MissingMember> {
+        self.try_require_member(MemberKind::StaticMethod, class, None, method, descriptor)
+            .map(|id| id as jmethodID)
+    }
+
+    pub unsafe fn try_require_field(
+        self,
+        class: jclass,
+        field: &str,
+        descriptor: &str,
+    ) -> Result<jfieldID, MissingMember> {
+        self.try_require_member(MemberKind::Field, class, None, field, descriptor)
+            .map(|id| id as jfieldID)
+    }
+
+    pub unsafe fn try_require_static_field(
+        self,
+        class: jclass,
+        field: &str,
+        descriptor: &str,
+    ) -> Result<jfieldID, MissingMember> {
+        self.try_require_member(MemberKind::StaticField, class, None, field, descriptor)
+            .map(|id| id as jfieldID)
+    }
+
+    pub unsafe fn try_require_class_method(
+        self,
+        class: &str,
+        method: &str,
+        descriptor: &str,
+    ) -> Result<(jclass, jmethodID), MissingMember> {
+        self.try_require_class_member(MemberKind::Method, class, method, descriptor)
+            .map(|(class, id)| (class, id as jmethodID))
+    }
+
+    pub unsafe fn try_require_class_static_method(
+        self,
+        class: &str,
+        method: &str,
+        descriptor: &str,
+    ) -> Result<(jclass, jmethodID), MissingMember> {
+        self.try_require_class_member(MemberKind::StaticMethod, class, method, descriptor)
+            .map(|(class, id)| (class, id as jmethodID))
+    }
+
+    pub unsafe fn try_require_class_field(
+        self,
+        class: &str,
+        field: &str,
+        descriptor: &str,
+    ) -> Result<(jclass, jfieldID), MissingMember> {
+        self.try_require_class_member(MemberKind::Field, class, field, descriptor)
+            .map(|(class, id)| (class, id as jfieldID))
+    }
+
+    pub unsafe fn try_require_class_static_field(
+        self,
+        class: &str,
+        field: &str,
+        descriptor: &str,
+    ) -> Result<(jclass, jfieldID), MissingMember> {
+        self.try_require_class_member(MemberKind::StaticField, class, field, descriptor)
+            .map(|(class, id)| (class, id as jfieldID))
+    }
+
+    unsafe fn try_require_class_member(
+        self,
+        kind: MemberKind,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<(jclass, *mut c_void), MissingMember> {
+        let jclass = self.try_require_class(class)?;
+        match self.try_require_member(kind, jclass, Some(class), name, descriptor) {
+            Ok(id) => Ok((jclass, id)),
+            Err(missing) => {
+                ((**self.env).v1_2.DeleteLocalRef)(self.env, jclass);
+                Err(missing)
+            }
+        }
+    }
+
+    /// Looks up the method or field ID of a member of `class`, named `class_name` if known.
+    pub(crate) unsafe fn try_require_member(
+        self,
+        kind: MemberKind,
+        class: jclass,
+        class_name: Option<&str>,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<*mut c_void, MissingMember> {
+        debug_assert!(name.ends_with('\0'));
+        debug_assert!(descriptor.ends_with('\0'));
+
+        let n = name.as_ptr() as *const c_char;
+        let d = descriptor.as_ptr() as *const c_char;
+        let id = match kind {
+            MemberKind::Class => panic!("try_require_member:  a class is not a member"),
+            MemberKind::Method => ((**self.env).v1_2.GetMethodID)(self.env, class, n, d) as *mut c_void,
+            MemberKind::StaticMethod => ((**self.env).v1_2.GetStaticMethodID)(self.env, class, n, d) as *mut c_void,
+            MemberKind::Field => ((**self.env).v1_2.GetFieldID)(self.env, class, n, d) as *mut c_void,
+            MemberKind::StaticField => ((**self.env).v1_2.GetStaticFieldID)(self.env, class, n, d) as *mut c_void,
+        };
+        if id.is_null() {
+            ((**self.env).v1_2.ExceptionClear)(self.env);
+            let class_name = match class_name {
+                Some(class_name) => class_name.to_owned(),
+                None => self.class_name(class),
+            };
+            return Err(MissingMember::new(kind, &class_name, name, descriptor));
+        }
+        Ok(id)
+    }
+
+    /// The JNI name of `class` (e.g. `"java/lang/String"`) as reported by `Class.getName()`, or empty on failure.
+    unsafe fn class_name(self, class: jclass) -> String {
+        self.java_class_name(class).replace('.', "/")
+    }
+
+    /// The name of `class` (e.g. `"java.lang.String"`) as reported by `Class.getName()`, or empty on failure.
+    pub(crate) unsafe fn java_class_name(self, class: jclass) -> String {
+        let class_class = ((**self.env).v1_2.GetObjectClass)(self.env, class);
+        let get_name = ((**self.env).v1_2.GetMethodID)(
+            self.env,
+            class_class,
+            c"getName".as_ptr(),
+            c"()Ljava/lang/String;".as_ptr(),
+        );
+        ((**self.env).v1_2.DeleteLocalRef)(self.env, class_class);
+        if get_name.is_null() {
+            ((**self.env).v1_2.ExceptionClear)(self.env);
+            return String::new();
+        }
+        let name = ((**self.env).v1_2.CallObjectMethodA)(self.env, class, get_name, null());
+        if name.is_null() {
+            ((**self.env).v1_2.ExceptionClear)(self.env);
+            return String::new();
+        }
+        let chars = ((**self.env).v1_2.GetStringUTFChars)(self.env, name, null_mut());
+        let result = CStr::from_ptr(chars).to_string_lossy().into_owned();
+        ((**self.env).v1_2.ReleaseStringUTFChars)(self.env, name, chars);
+        ((**self.env).v1_2.DeleteLocalRef)(self.env, name);
+        result
+    }
+
     // Constructor Methods
 
     pub unsafe fn new_object_a<R: ReferenceType, E: ThrowableType>(
@@ -207,6 +526,40 @@ impl<'env> Env<'env> {
         }
     }
 
+    /// Checks an object returned by a generic method (or field) really is an `R`, which the caller picked rather than
+    /// the member's descriptor.  A mismatch fails like Java's own `checkcast` would:  with a `ClassCastException`.  So
+    /// does an `R` whose class can't be found, as there's then no telling.
+    ///
+    /// **unsafe**:  `E` must be a supertype of `java.lang.ClassCastException` (e.g. `java.lang.Throwable`.)
+    pub unsafe fn check_return<R: ReferenceType, E: ThrowableType>(
+        self,
+        result: Result<Option<Local<'env, R>>, Local<'env, E>>,
+    ) -> Result<Option<Local<'env, R>>, Local<'env, E>> {
+        let object = match result {
+            Ok(Some(object)) => object,
+            other => return other,
+        };
+        let (class, jni_type) = R::static_with_jni_type(|jni_type| {
+            let class = ClassCache::of::<R>().try_require_cached(self, jni_type);
+            (class, jni_type.trim_end_matches('\0').replace('/', "."))
+        });
+        let message = match class {
+            Ok((class, cached)) => {
+                let is_instance = ((**self.env).v1_2.IsInstanceOf)(self.env, object.as_raw(), class);
+                if !cached {
+                    ((**self.env).v1_2.DeleteLocalRef)(self.env, class);
+                }
+                if is_instance {
+                    return Ok(Some(object));
+                }
+                format!("generic return value isn't a {}", jni_type)
+            }
+            Err(_) => format!("generic return value can't be checked:  class {} not found", jni_type),
+        };
+        drop(object);
+        Err(self.take_exception_or_throw("java/lang/ClassCastException\0", &message))
+    }
+
     // Instance Methods
 
     pub unsafe fn call_object_method_a<R: ReferenceType, E: ThrowableType>(
@@ -710,4 +1063,141 @@ impl<'env> Env<'env> {
     pub unsafe fn set_static_double_field(self, class: jclass, field: jfieldID, value: jdouble) {
         ((**self.env).v1_2.SetStaticDoubleField)(self.env, class, field, value);
     }
+
+    // Local Reference Methods
+
+    /// Ensures at least `capacity` more local references can be created in the current frame.  Fails with an
+    /// `OutOfMemoryError` if they can't.
+    pub fn ensure_local_capacity(self, capacity: jint) -> Result<(), JavaException> {
+        unsafe {
+            if ((**self.env).v1_2.EnsureLocalCapacity)(self.env, capacity) == JNI_OK {
+                return Ok(());
+            }
+            Err(self.take_java_exception_or_throw("java/lang/OutOfMemoryError\0", "Local reference capacity exceeded"))
+        }
+    }
+
+    /// Runs `f` in a new local reference frame with room for at least `capacity` local references, deleting every local
+    /// reference created in it once `f` returns (or panics.)  Fails with an `OutOfMemoryError` if the frame can't be
+    /// created.  Use this to keep loops from exhausting the local reference table.
+    ///
+    /// `f` gets an [Env] with a lifetime of its own, which the locals it creates are tied to:  they can't escape the frame
+    /// by accident.  See [with_local_frame_returning](Self::with_local_frame_returning) to keep one of them.
+    ///
+    /// **unsafe**:  Locals created in `f` through objects from outside the frame (e.g. by calling a method of an outer
+    /// [Local]) are tied to the outer lifetime instead, but are deleted with the frame all the same.  They must not be
+    /// used once `f` returns.  This can't be enforced by lifetimes:  bindings tie the locals they return to the borrow
+    /// of the object they're called on rather than to an [Env], and `f` may borrow any outer object for as long as
+    /// it likes - not to mention [Env::ambient] and [VM::with_env], which hand out an [Env] of any lifetime.
+    pub unsafe fn with_local_frame<R>(
+        self,
+        capacity: jint,
+        f: impl for<'frame> FnOnce(Env<'frame>) -> R,
+    ) -> Result<R, JavaException> {
+        let frame = self.push_local_frame(capacity)?;
+        let result = f(Env::from_raw(self.env));
+        drop(frame);
+        Ok(result)
+    }
+
+    /// Like [with_local_frame](Self::with_local_frame), but keeps the [Local] `f` returns (if any) through the pop of
+    /// the frame, as a new local reference in the outer frame.
+    ///
+    /// **unsafe**:  As for [with_local_frame](Self::with_local_frame).
+    pub unsafe fn with_local_frame_returning<T: ReferenceType>(
+        self,
+        capacity: jint,
+        f: impl for<'frame> FnOnce(Env<'frame>) -> Option<Local<'frame, T>>,
+    ) -> Result<Option<Local<'env, T>>, JavaException> {
+        let frame = self.push_local_frame(capacity)?;
+        let result = f(Env::from_raw(self.env)).map_or(null_mut(), Local::into_raw);
+        mem::forget(frame);
+        let result = ((**self.env).v1_2.PopLocalFrame)(self.env, result);
+        Ok(if result.is_null() {
+            None
+        } else {
+            Some(Local::from_raw(self, result))
+        })
+    }
+
+    unsafe fn push_local_frame(self, capacity: jint) -> Result<LocalFrame<'env>, JavaException> {
+        if ((**self.env).v1_2.PushLocalFrame)(self.env, capacity) == JNI_OK {
+            return Ok(LocalFrame(self));
+        }
+        Err(self.take_java_exception_or_throw("java/lang/OutOfMemoryError\0", "Local reference capacity exceeded"))
+    }
+
+    // Native Methods
+
+    /// Binds `natives` to the `native` methods of `class`, with `RegisterNatives`.  Fails with a `NoSuchMethodError` if
+    /// `class` has no `native` method matching one of them.
+    pub unsafe fn register_natives<E: ThrowableType>(
+        self,
+        class: jclass,
+        natives: &[JNINativeMethod],
+    ) -> Result<(), Local<'env, E>> {
+        let result = ((**self.env).v1_2.RegisterNatives)(self.env, class, natives.as_ptr(), natives.len() as jint);
+        let exception = ((**self.env).v1_2.ExceptionOccurred)(self.env);
+        if !exception.is_null() {
+            ((**self.env).v1_2.ExceptionClear)(self.env);
+            Err(Local::from_raw(self, exception))
+        } else {
+            assert_eq!(result, JNI_OK);
+            Ok(())
+        }
+    }
+}
+
+/// Pops a local reference frame pushed by [Env::push_local_frame] when dropped - even if unwinding from a panic.
+struct LocalFrame<'env>(Env<'env>);
+
+impl Drop for LocalFrame<'_> {
+    fn drop(&mut self) {
+        let env = self.0.env;
+        unsafe { ((**env).v1_2.PopLocalFrame)(env, null_mut()) };
+    }
+}
+
+#[test]
+#[allow(non_camel_case_types)] // As generated bindings do
+fn check_return() {
+    use crate::test_vm::*;
+    use crate::{__jni_bindgen, ThrowableType};
+
+    __jni_bindgen! {
+        public class String ("java/lang/String\0") extends () {}
+        public class Integer ("java/lang/Integer\0") extends () {}
+        public class Missing ("jni_glue/test/Missing\0") extends () {}
+        public class Throwable ("java/lang/Throwable\0") extends () {}
+    }
+    impl ThrowableType for Throwable {}
+
+    unsafe fn returned<'env, R: ReferenceType>(
+        env: Env<'env>,
+        object: jobject,
+    ) -> Result<Option<Local<'env, R>>, Local<'env, Throwable>> {
+        let jnienv = env.as_raw();
+        Ok(Some(Local::from_raw(
+            env,
+            ((**jnienv).v1_2.NewLocalRef)(jnienv, object),
+        )))
+    }
+
+    with_env(|env| unsafe {
+        let jnienv = env.as_raw();
+        let string = ((**jnienv).v1_2.NewStringUTF)(jnienv, c"returned".as_ptr());
+        let class_cast_exception = env.require_class("java/lang/ClassCastException\0");
+        let is_class_cast = |thrown: Option<Local<Throwable>>| {
+            thrown.is_some_and(|thrown| ((**jnienv).v1_2.IsInstanceOf)(jnienv, thrown.as_raw(), class_cast_exception))
+        };
+
+        for _cached in 0..2 {
+            assert!(matches!(env.check_return(returned::<String>(env, string)), Ok(Some(_))));
+        }
+        assert!(is_class_cast(env.check_return(returned::<Integer>(env, string)).err()));
+        // No class to check against doesn't panic, nor pass anything off as one.
+        assert!(is_class_cast(env.check_return(returned::<Missing>(env, string)).err()));
+        assert!(matches!(env.check_return::<Missing, Throwable>(Ok(None)), Ok(None)));
+        assert!(env.take_java_exception().is_none());
+    });
 }
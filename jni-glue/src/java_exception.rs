@@ -0,0 +1,390 @@
+use std::error::Error;
+use std::fmt;
+use std::ptr::{null, null_mut};
+
+use jni_sys::*;
+
+use crate::{CastError, Env, Local, ObjectAndEnv, StringChars, ThrowableType, VM};
+
+/// A Java exception, captured into Rust data:  its class, message, stack trace, cause chain, and suppressed exceptions.
+///
+/// Unlike a [Local] Throwable, this can outlive the JNI call that caught it, be sent between threads, and be used as a
+/// [std::error::Error] - e.g. with `?` into `anyhow::Error`.  It keeps a global reference to the original object, which
+/// [throw](Self::throw) can rethrow.
+///
+/// ```rust,no_run
+/// # use jni_glue::*;
+/// # fn example<T: ThrowableType>(
+/// #     call: impl FnOnce() -> Result<(), Local<'static, T>>,
+/// # ) -> Result<(), Box<dyn std::error::Error>> {
+/// call().map_err(JavaException::from)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct JavaException {
+    /// The name of the exception's class, as reported by `Class.getName()` (e.g. `"java.lang.IllegalStateException"`.)
+    pub class: String,
+    /// The result of `getMessage()`, if it returned a string.
+    pub message: Option<String>,
+    pub stack_trace: Vec<StackFrame>,
+    /// The result of `getCause()`, unless `null` or already part of this chain.
+    pub cause: Option<Box<JavaException>>,
+    /// The results of `getSuppressed()` (Java 7 / Android API level 19+.)
+    pub suppressed: Vec<JavaException>,
+    throwable: jthrowable, // Global reference
+    vm: VM,
+}
+
+// Global references may be used from any thread.
+unsafe impl Send for JavaException {}
+unsafe impl Sync for JavaException {}
+
+/// One element of a [JavaException]'s stack trace.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct StackFrame {
+    /// e.g. `"com.example.Widget"`
+    pub class: String,
+    pub method: String,
+    pub file: Option<String>,
+    /// Negative if unknown, or `-2` for native methods.
+    pub line: i32,
+}
+
+impl JavaException {
+    /// Captures `throwable`.  If inspecting it throws, whatever couldn't be retrieved is left out.
+    pub fn new<T: ThrowableType>(throwable: &T) -> Self {
+        // Sound as per the contract of ReferenceType
+        let object_and_env = unsafe { *(throwable as *const T as *const ObjectAndEnv) };
+        unsafe { Self::from_raw(Env::from_raw(object_and_env.env), object_and_env.object) }
+    }
+
+    /// Captures `throwable`.  If inspecting it throws, whatever couldn't be retrieved is left out.
+    ///
+    /// **unsafe**:  `throwable` must be a valid, non-null reference to a `java.lang.Throwable`, and there must not be an
+    /// exception pending in `env`.
+    pub unsafe fn from_raw(env: Env, throwable: jthrowable) -> Self {
+        let inspector = Inspector::new(env);
+        let result = inspector.capture(throwable, &mut Vec::new());
+        inspector.release();
+        result
+    }
+
+    /// Throws the original Java object in `env`, e.g. to propagate it back to Java from a native method.
+    pub fn throw(&self, env: Env) {
+        assert_eq!(self.vm, env.vm()); // Soundness check - env *must* belong to the same VM!
+        let env = env.as_raw();
+        unsafe { ((**env).v1_2.Throw)(env, self.throwable) };
+    }
+
+    /// A new local reference to the original Java object, if it's a `T`.
+    pub fn to_local<'env, T: ThrowableType>(&self, env: Env<'env>) -> Result<Local<'env, T>, CastError> {
+        assert_eq!(self.vm, env.vm()); // Soundness check - env *must* belong to the same VM!
+        let jnienv = env.as_raw();
+        let class = T::static_with_jni_type(|t| unsafe { env.require_class(t) });
+        let is_instance = unsafe { ((**jnienv).v1_2.IsInstanceOf)(jnienv, self.throwable, class) };
+        unsafe { ((**jnienv).v1_2.DeleteLocalRef)(jnienv, class) };
+        if !is_instance {
+            return Err(CastError);
+        }
+        let object = unsafe { ((**jnienv).v1_2.NewLocalRef)(jnienv, self.throwable) };
+        Ok(unsafe { Local::from_raw(env, object) })
+    }
+
+    /// The global reference to the original Java object, valid for as long as this lives.
+    pub fn as_raw(&self) -> jthrowable {
+        self.throwable
+    }
+
+    pub fn vm(&self) -> VM {
+        self.vm
+    }
+
+    /// Writes this the way `Throwable.printStackTrace()` would, indenting each line by `indent`.
+    fn write_stack_trace(&self, f: &mut fmt::Formatter<'_>, indent: &str) -> fmt::Result {
+        for frame in &self.stack_trace {
+            write!(f, "\n{}\tat {}", indent, frame)?;
+        }
+        for suppressed in &self.suppressed {
+            write!(f, "\n{}\tSuppressed: {}", indent, suppressed)?;
+            suppressed.write_stack_trace(f, &format!("{}\t", indent))?;
+        }
+        if let Some(cause) = self.cause.as_ref() {
+            write!(f, "\n{}Caused by: {}", indent, cause)?;
+            cause.write_stack_trace(f, indent)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'env, T: ThrowableType> From<Local<'env, T>> for JavaException {
+    fn from(throwable: Local<'env, T>) -> Self {
+        Self::new(&*throwable)
+    }
+}
+
+impl Drop for JavaException {
+    fn drop(&mut self) {
+        self.vm.with_env(|env| {
+            let env = env.as_raw();
+            unsafe { ((**env).v1_2.DeleteGlobalRef)(env, self.throwable) }
+        });
+    }
+}
+
+impl Error for JavaException {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.cause.as_ref().map(|cause| &**cause as &(dyn Error + 'static))
+    }
+}
+
+/// Formats as `Throwable.toString()` does, e.g. `java.lang.IllegalStateException: message`.
+impl fmt::Display for JavaException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.message.as_ref() {
+            Some(message) => write!(f, "{}: {}", self.class, message),
+            None => f.write_str(&self.class),
+        }
+    }
+}
+
+/// Formats as `Throwable.printStackTrace()` does, including any suppressed exceptions and causes.
+impl fmt::Debug for JavaException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)?;
+        self.write_stack_trace(f, "")
+    }
+}
+
+/// Formats as `StackTraceElement.toString()` does, e.g. `com.example.Widget.run(Widget.java:12)`.
+impl fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}(", self.class, self.method)?;
+        match (self.file.as_ref(), self.line) {
+            (_, -2) => f.write_str("Native Method")?,
+            (Some(file), line) if line >= 0 => write!(f, "{}:{}", file, line)?,
+            (Some(file), _) => f.write_str(file)?,
+            (None, _) => f.write_str("Unknown Source")?,
+        }
+        f.write_str(")")
+    }
+}
+
+/// The methods of `Throwable` and `StackTraceElement` needed to capture a [JavaException].
+struct Inspector<'env> {
+    env: Env<'env>,
+    throwable_class: jclass,
+    frame_class: jclass,
+    get_message: jmethodID,
+    get_stack_trace: jmethodID,
+    get_cause: jmethodID,
+    get_suppressed: jmethodID, // Null before Java 7 / Android API level 19
+    get_class_name: jmethodID,
+    get_method_name: jmethodID,
+    get_file_name: jmethodID,
+    get_line_number: jmethodID,
+}
+
+impl<'env> Inspector<'env> {
+    unsafe fn new(env: Env<'env>) -> Self {
+        let throwable_class = env.require_class("java/lang/Throwable\0");
+        let frame_class = env.require_class("java/lang/StackTraceElement\0");
+        let method = |class, name, descriptor| {
+            env.try_require_method(class, name, descriptor)
+                .map_or(null_mut(), |method| method)
+        };
+        Self {
+            env,
+            throwable_class,
+            frame_class,
+            get_message: method(throwable_class, "getMessage\0", "()Ljava/lang/String;\0"),
+            get_stack_trace: method(throwable_class, "getStackTrace\0", "()[Ljava/lang/StackTraceElement;\0"),
+            get_cause: method(throwable_class, "getCause\0", "()Ljava/lang/Throwable;\0"),
+            get_suppressed: method(throwable_class, "getSuppressed\0", "()[Ljava/lang/Throwable;\0"),
+            get_class_name: method(frame_class, "getClassName\0", "()Ljava/lang/String;\0"),
+            get_method_name: method(frame_class, "getMethodName\0", "()Ljava/lang/String;\0"),
+            get_file_name: method(frame_class, "getFileName\0", "()Ljava/lang/String;\0"),
+            get_line_number: method(frame_class, "getLineNumber\0", "()I\0"),
+        }
+    }
+
+    unsafe fn release(self) {
+        self.delete_local(self.throwable_class);
+        self.delete_local(self.frame_class);
+    }
+
+    /// Captures `throwable`, and any causes and suppressed exceptions not already in `seen` (its ancestors.)
+    unsafe fn capture(&self, throwable: jthrowable, seen: &mut Vec<jthrowable>) -> JavaException {
+        let env = self.env.as_raw();
+        seen.push(throwable);
+
+        let class = ((**env).v1_2.GetObjectClass)(env, throwable);
+        let class_name = self.env.java_class_name(class);
+        self.delete_local(class);
+
+        let message = self.string(self.call_object(throwable, self.get_message));
+
+        let mut stack_trace = Vec::new();
+        self.for_each_element(self.call_object(throwable, self.get_stack_trace), |frame| {
+            stack_trace.push(StackFrame {
+                class: self
+                    .string(self.call_object(frame, self.get_class_name))
+                    .unwrap_or_default(),
+                method: self
+                    .string(self.call_object(frame, self.get_method_name))
+                    .unwrap_or_default(),
+                file: self.string(self.call_object(frame, self.get_file_name)),
+                line: self.call_int(frame, self.get_line_number).unwrap_or(-1),
+            });
+        });
+
+        let mut cause = None;
+        let cause_object = self.call_object(throwable, self.get_cause);
+        if !cause_object.is_null() {
+            if !self.is_seen(cause_object, seen) {
+                cause = Some(Box::new(self.capture(cause_object, seen)));
+            }
+            self.delete_local(cause_object);
+        }
+
+        let mut suppressed = Vec::new();
+        self.for_each_element(self.call_object(throwable, self.get_suppressed), |object| {
+            if !self.is_seen(object, seen) {
+                suppressed.push(self.capture(object, seen));
+            }
+        });
+
+        seen.pop();
+        JavaException {
+            class: class_name,
+            message,
+            stack_trace,
+            cause,
+            suppressed,
+            throwable: ((**env).v1_2.NewGlobalRef)(env, throwable),
+            vm: self.env.vm(),
+        }
+    }
+
+    unsafe fn is_seen(&self, object: jobject, seen: &[jthrowable]) -> bool {
+        let env = self.env.as_raw();
+        seen.iter().any(|&s| ((**env).v1_2.IsSameObject)(env, s, object))
+    }
+
+    /// Calls `f` with each non-null element of the (possibly null) object `array`, then deletes it and the elements.
+    unsafe fn for_each_element(&self, array: jobjectArray, mut f: impl FnMut(jobject)) {
+        if array.is_null() {
+            return;
+        }
+        let env = self.env.as_raw();
+        let len = ((**env).v1_2.GetArrayLength)(env, array);
+        for i in 0..len {
+            let element = ((**env).v1_2.GetObjectArrayElement)(env, array, i);
+            if !element.is_null() {
+                f(element);
+                self.delete_local(element);
+            }
+        }
+        self.delete_local(array);
+    }
+
+    /// Calls a no-argument method returning an object, returning null if it's missing or threw.
+    unsafe fn call_object(&self, object: jobject, method: jmethodID) -> jobject {
+        if method.is_null() {
+            return null_mut();
+        }
+        let env = self.env.as_raw();
+        let result = ((**env).v1_2.CallObjectMethodA)(env, object, method, null());
+        if ((**env).v1_2.ExceptionCheck)(env) {
+            ((**env).v1_2.ExceptionClear)(env);
+            return null_mut();
+        }
+        result
+    }
+
+    unsafe fn call_int(&self, object: jobject, method: jmethodID) -> Option<jint> {
+        if method.is_null() {
+            return None;
+        }
+        let env = self.env.as_raw();
+        let result = ((**env).v1_2.CallIntMethodA)(env, object, method, null());
+        if ((**env).v1_2.ExceptionCheck)(env) {
+            ((**env).v1_2.ExceptionClear)(env);
+            return None;
+        }
+        Some(result)
+    }
+
+    /// Converts and deletes the (possibly null) local reference `string`.
+    unsafe fn string(&self, string: jstring) -> Option<String> {
+        if string.is_null() {
+            return None;
+        }
+        let result = StringChars::from_env_jstring(self.env, string).to_string_lossy();
+        self.delete_local(string);
+        Some(result)
+    }
+
+    unsafe fn delete_local(&self, object: jobject) {
+        let env = self.env.as_raw();
+        ((**env).v1_2.DeleteLocalRef)(env, object);
+    }
+}
+
+#[test]
+fn formatting() {
+    use std::mem::ManuallyDrop;
+
+    let frame = |class: &str, method: &str, file: Option<&str>, line| StackFrame {
+        class: class.to_string(),
+        method: method.to_string(),
+        file: file.map(|f| f.to_string()),
+        line,
+    };
+    // Not backed by an actual throwable, so never dropped.
+    let exception = |class: &str, message: Option<&str>, stack_trace| JavaException {
+        class: class.to_string(),
+        message: message.map(|m| m.to_string()),
+        stack_trace,
+        cause: None,
+        suppressed: Vec::new(),
+        throwable: null_mut(),
+        vm: unsafe { VM::from_raw(null_mut()) },
+    };
+
+    let mut outer = exception(
+        "java.lang.RuntimeException",
+        Some("outer"),
+        vec![
+            frame("com.example.A", "run", Some("A.java"), 12),
+            frame("com.example.A", "main", Some("A.java"), -1),
+        ],
+    );
+    outer.suppressed.push(exception(
+        "java.io.IOException",
+        Some("closing"),
+        vec![frame("com.example.A", "close", None, 20)],
+    ));
+    outer.cause = Some(Box::new(exception(
+        "java.lang.IllegalStateException",
+        None,
+        vec![frame("com.example.B", "go", Some("B.java"), -2)],
+    )));
+    let outer = ManuallyDrop::new(outer);
+
+    assert_eq!(outer.to_string(), "java.lang.RuntimeException: outer");
+    assert_eq!(
+        outer.cause.as_ref().unwrap().to_string(),
+        "java.lang.IllegalStateException"
+    );
+    assert_eq!(
+        format!("{:?}", *outer),
+        "java.lang.RuntimeException: outer
+\tat com.example.A.run(A.java:12)
+\tat com.example.A.main(A.java)
+\tSuppressed: java.io.IOException: closing
+\t\tat com.example.A.close(Unknown Source)
+Caused by: java.lang.IllegalStateException
+\tat com.example.B.go(Native Method)"
+    );
+    assert_eq!(outer.source().unwrap().to_string(), "java.lang.IllegalStateException");
+}
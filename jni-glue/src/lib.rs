@@ -16,28 +16,54 @@ mod refs {
     mod argument;
     mod global;
     mod local;
+    mod monitor_guard;
     mod ref_;
+    mod weak;
 
     pub use argument::*;
     pub use global::*;
     pub use local::*;
+    pub use monitor_guard::*;
     pub use ref_::*;
+    pub use weak::*;
 }
 
 mod __jni_bindgen;
 mod array;
+mod array_elements;
 mod as_jvalue;
+mod cache;
+mod direct_byte_buffer;
 mod env;
+mod java_exception;
 mod jni_type;
+mod missing_member;
+mod modified_utf8;
+mod natives;
+mod proxy;
 mod string_chars;
+mod string_utf_chars;
+#[cfg(test)]
+mod test_vm;
+mod verify;
 mod vm;
 
 pub use array::*;
+pub use array_elements::*;
 pub use as_jvalue::*;
+pub use cache::*;
+pub use direct_byte_buffer::*;
 pub use env::*;
+pub use java_exception::*;
 pub use jni_type::JniType;
+pub use missing_member::*;
+pub use modified_utf8::*;
+pub use natives::*;
+pub use proxy::*;
 pub use refs::*;
 pub use string_chars::*;
+pub use string_utf_chars::*;
+pub use verify::*;
 pub use vm::*;
 
 /// Error returned on failed `.cast()`.`
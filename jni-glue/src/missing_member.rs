@@ -0,0 +1,58 @@
+use std::fmt;
+
+/// What kind of class member a [MissingMember] is.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum MemberKind {
+    Class,
+    Method,
+    StaticMethod,
+    Field,
+    StaticField,
+}
+
+/// Error returned by the `try_require_*` lookups when a class, method, or field doesn't exist in the running VM - such
+/// as a method from a newer Android API level than the device has.  The `NoClassDefFoundError`, `NoSuchMethodError`,
+/// or `NoSuchFieldError` thrown by the failed lookup will have been cleared.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct MissingMember {
+    pub kind: MemberKind,
+    /// The JNI name of the class (e.g. `"java/lang/String"`), or empty if it couldn't be determined.
+    pub class: String,
+    /// The name of the method or field, or empty for classes.
+    pub name: String,
+    /// The JNI descriptor of the method or field (e.g. `"(I)V"`), or empty for classes.
+    pub descriptor: String,
+}
+
+impl MissingMember {
+    pub(crate) fn new(kind: MemberKind, class: &str, name: &str, descriptor: &str) -> Self {
+        let trim = |s: &str| s.trim_end_matches('\0').to_owned();
+        Self {
+            kind,
+            class: trim(class),
+            name: trim(name),
+            descriptor: trim(descriptor),
+        }
+    }
+}
+
+impl std::error::Error for MissingMember {}
+impl fmt::Display for MissingMember {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            MemberKind::Class => write!(f, "Missing class {}", self.class),
+            MemberKind::Method => write!(f, "Missing method {}.{}{}", self.class, self.name, self.descriptor),
+            MemberKind::StaticMethod => write!(
+                f,
+                "Missing static method {}.{}{}",
+                self.class, self.name, self.descriptor
+            ),
+            MemberKind::Field => write!(f, "Missing field {}.{} {}", self.class, self.name, self.descriptor),
+            MemberKind::StaticField => write!(
+                f,
+                "Missing static field {}.{} {}",
+                self.class, self.name, self.descriptor
+            ),
+        }
+    }
+}
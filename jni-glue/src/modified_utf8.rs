@@ -0,0 +1,160 @@
+use std::borrow::Cow;
+use std::char;
+use std::ffi::CString;
+use std::fmt;
+use std::str;
+
+/// Encodes `string` as [Modified UTF-8](https://docs.oracle.com/javase/8/docs/technotes/guides/jni/spec/types.html#modified_utf_8_strings),
+/// as `NewStringUTF` expects:  NULs become `C0 80`, and supplementary characters become surrogate pairs of 3 bytes
+/// each.  Strings with neither (e.g. most identifiers) are just copied.
+///
+/// ```rust
+/// # use jni_glue::*;
+/// assert_eq!(encode_modified_utf8("abc").as_bytes(), b"abc");
+/// assert_eq!(encode_modified_utf8("a\0b").as_bytes(), b"a\xC0\x80b");
+/// assert_eq!(encode_modified_utf8("\u{1F600}").as_bytes(), b"\xED\xA0\xBD\xED\xB8\x80");
+/// ```
+pub fn encode_modified_utf8(string: &str) -> CString {
+    let bytes = string.as_bytes();
+    if !bytes.iter().any(|&b| b == 0 || b >= 0xF0) {
+        // Already valid Modified UTF-8
+        return unsafe { CString::from_vec_unchecked(bytes.to_vec()) };
+    }
+
+    let mut encoded = Vec::with_capacity(bytes.len() + 8);
+    for c in string.chars() {
+        if c == '\0' {
+            encoded.extend_from_slice(&[0xC0, 0x80]);
+        } else if (c as u32) < 0x10000 {
+            encoded.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+        } else {
+            for &unit in c.encode_utf16(&mut [0; 2]).iter() {
+                encoded.extend_from_slice(&[
+                    0xE0 | (unit >> 12) as u8,
+                    0x80 | ((unit >> 6) & 0x3F) as u8,
+                    0x80 | (unit & 0x3F) as u8,
+                ]);
+            }
+        }
+    }
+    unsafe { CString::from_vec_unchecked(encoded) } // Modified UTF-8 never contains NULs
+}
+
+/// Decodes [Modified UTF-8](https://docs.oracle.com/javase/8/docs/technotes/guides/jni/spec/types.html#modified_utf_8_strings),
+/// as returned by `GetStringUTFChars` (without the terminating NUL.)  Borrows `bytes` if they're plain UTF-8 already.
+///
+/// ```rust
+/// # use jni_glue::*;
+/// assert_eq!(decode_modified_utf8(b"abc").unwrap(), "abc");
+/// assert_eq!(decode_modified_utf8(b"a\xC0\x80b").unwrap(), "a\0b");
+/// assert_eq!(decode_modified_utf8(b"\xED\xA0\xBD\xED\xB8\x80").unwrap(), "\u{1F600}");
+/// assert_eq!(decode_modified_utf8(b"ok\xED\xA0\xBD").unwrap_err().valid_up_to(), 2); // Unpaired surrogate
+/// ```
+pub fn decode_modified_utf8(bytes: &[u8]) -> Result<Cow<'_, str>, ModifiedUtf8Error> {
+    if let Some(string) = as_plain_utf8(bytes) {
+        return Ok(Cow::Borrowed(string));
+    }
+    decode_slow(bytes, false).map(Cow::Owned)
+}
+
+/// Decodes [Modified UTF-8](decode_modified_utf8), replacing invalid bytes and unpaired surrogates with
+/// [REPLACEMENT_CHARACTER](char::REPLACEMENT_CHARACTER)s (`'\u{FFFD}'`.)
+pub fn decode_modified_utf8_lossy(bytes: &[u8]) -> Cow<'_, str> {
+    if let Some(string) = as_plain_utf8(bytes) {
+        return Cow::Borrowed(string);
+    }
+    match decode_slow(bytes, true) {
+        Ok(string) => Cow::Owned(string),
+        Err(_) => unreachable!("lossy decoding can't fail"),
+    }
+}
+
+/// Error returned when decoding invalid [Modified UTF-8](decode_modified_utf8).
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ModifiedUtf8Error {
+    valid_up_to: usize,
+}
+
+impl ModifiedUtf8Error {
+    /// The number of bytes that were decoded before the invalid byte sequence or unpaired surrogate.
+    pub fn valid_up_to(&self) -> usize {
+        self.valid_up_to
+    }
+}
+
+impl std::error::Error for ModifiedUtf8Error {}
+impl fmt::Display for ModifiedUtf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid Modified UTF-8 at byte {}", self.valid_up_to)
+    }
+}
+
+/// Modified UTF-8 without encoded NULs or surrogates is plain UTF-8 - which rejects both, as well as the 4 byte
+/// sequences Modified UTF-8 doesn't allow.
+fn as_plain_utf8(bytes: &[u8]) -> Option<&str> {
+    if bytes.iter().any(|&b| b >= 0xF0) {
+        return None;
+    }
+    str::from_utf8(bytes).ok()
+}
+
+fn decode_slow(bytes: &[u8], lossy: bool) -> Result<String, ModifiedUtf8Error> {
+    let mut string = String::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let decoded = next_unit(bytes, i).and_then(|(unit, len)| match unit {
+            0xD800..=0xDBFF => match next_unit(bytes, i + len) {
+                Some((low @ 0xDC00..=0xDFFF, low_len)) => {
+                    let c = 0x10000 + (((unit as u32 - 0xD800) << 10) | (low as u32 - 0xDC00));
+                    char::from_u32(c).map(|c| (c, len + low_len))
+                }
+                _ => None,
+            },
+            0xDC00..=0xDFFF => None,
+            _ => char::from_u32(unit as u32).map(|c| (c, len)),
+        });
+        match decoded {
+            Some((c, len)) => {
+                string.push(c);
+                i += len;
+            }
+            None if lossy => {
+                string.push(char::REPLACEMENT_CHARACTER);
+                i += next_unit(bytes, i).map_or(1, |(_, len)| len);
+            }
+            None => return Err(ModifiedUtf8Error { valid_up_to: i }),
+        }
+    }
+    Ok(string)
+}
+
+/// Decodes the UTF-16 code unit at `bytes[i..]`, and its length in bytes.
+fn next_unit(bytes: &[u8], i: usize) -> Option<(u16, usize)> {
+    let continuation = |j: usize| {
+        bytes
+            .get(i + j)
+            .filter(|&&b| b & 0xC0 == 0x80)
+            .map(|&b| (b & 0x3F) as u16)
+    };
+    let b0 = *bytes.get(i)?;
+    match b0 {
+        0x01..=0x7F => Some((b0 as u16, 1)),
+        0xC0..=0xDF => {
+            let unit = ((b0 & 0x1F) as u16) << 6 | continuation(1)?;
+            if unit >= 0x80 || unit == 0 {
+                Some((unit, 2))
+            } else {
+                None // Overlong
+            }
+        }
+        0xE0..=0xEF => {
+            let unit = ((b0 & 0x0F) as u16) << 12 | continuation(1)? << 6 | continuation(2)?;
+            if unit >= 0x800 {
+                Some((unit, 3))
+            } else {
+                None // Overlong
+            }
+        }
+        _ => None, // Raw NULs, stray continuation bytes, and 4 byte sequences
+    }
+}
@@ -0,0 +1,168 @@
+//! Support for Java `native` methods implemented in Rust, as bound by the trampolines jni-bindgen generates for classes
+//! listed in \[[native\]] sections.
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr::null_mut;
+
+use jni_sys::*;
+
+use crate::{Env, JavaException, Local, ReferenceType, ThrowableType};
+
+/// The types Rust implementations of `native` methods can return, and the JNI types their trampolines return to Java
+/// instead:  `()`, the primitives jni-bindgen binds, and `Option<Local<T>>`.
+///
+/// **unsafe**:  `Raw` must be the JNI type for the Java return type `Self` is bound as.
+pub unsafe trait NativeReturn<'env> {
+    type Raw;
+
+    #[doc(hidden)]
+    fn into_native_return(self) -> Self::Raw;
+
+    /// Returned to Java alongside a thrown exception, which makes it ignore the return value anyways.
+    #[doc(hidden)]
+    fn native_return_default() -> Self::Raw;
+}
+
+unsafe impl<'env> NativeReturn<'env> for () {
+    type Raw = ();
+    fn into_native_return(self) -> Self::Raw {}
+    fn native_return_default() -> Self::Raw {}
+}
+
+unsafe impl<'env> NativeReturn<'env> for bool {
+    type Raw = jboolean;
+    fn into_native_return(self) -> Self::Raw {
+        if self {
+            JNI_TRUE
+        } else {
+            JNI_FALSE
+        }
+    }
+    fn native_return_default() -> Self::Raw {
+        JNI_FALSE
+    }
+}
+
+macro_rules! primitives {
+    ($($rust:ty),*) => {
+        $(
+            unsafe impl<'env> NativeReturn<'env> for $rust {
+                type Raw = $rust;
+                fn into_native_return(self) -> Self::Raw {
+                    self
+                }
+                fn native_return_default() -> Self::Raw {
+                    Default::default()
+                }
+            }
+        )*
+    };
+}
+
+primitives!(i8, u16, i16, i32, i64, f32, f64);
+
+unsafe impl<'env, T: ReferenceType> NativeReturn<'env> for Option<Local<'env, T>> {
+    type Raw = jobject;
+    fn into_native_return(self) -> Self::Raw {
+        self.map_or(null_mut(), Local::into_raw)
+    }
+    fn native_return_default() -> Self::Raw {
+        null_mut()
+    }
+}
+
+/// The errors Rust implementations of `native` methods can return, to be thrown in Java:  `Local<T>`s of throwable types,
+/// and [JavaException]s.
+pub trait NativeError<'env> {
+    /// Throws the error in `env`.
+    fn throw_native(self, env: Env<'env>);
+}
+
+impl<'env, T: ThrowableType> NativeError<'env> for Local<'env, T> {
+    fn throw_native(self, env: Env<'env>) {
+        env.throw(&*self);
+    }
+}
+
+impl<'env> NativeError<'env> for JavaException {
+    fn throw_native(self, env: Env<'env>) {
+        self.throw(env);
+    }
+}
+
+/// Converts the `result` of a Rust implementation of a `native` method into what its trampoline returns to Java,
+/// throwing the error if there is one.
+pub fn native_return<'env, R: NativeReturn<'env>, E: NativeError<'env>>(
+    env: Env<'env>,
+    result: Result<R, E>,
+) -> R::Raw {
+    match result {
+        Ok(value) => value.into_native_return(),
+        Err(error) => {
+            error.throw_native(env);
+            R::native_return_default()
+        }
+    }
+}
+
+/// Runs `body` - the Rust implementation of a `native` method, or anything else called from Java - converting its
+/// result as [native_return] does.  Panics must not unwind into Java, so they're caught and thrown as a
+/// `java.lang.RuntimeException` with the panic message (caused by the exception pending at the time, if any) instead.
+///
+/// ```rust,no_run
+/// # use jni_glue::*;
+/// # use jni_glue::sys::*;
+/// #[no_mangle]
+/// pub extern "system" fn Java_com_example_Widget_answer<'env>(env: Env<'env>, _: jclass) -> jint {
+///     native_call(env, || -> Result<i32, JavaException> { Ok(42) })
+/// }
+/// ```
+pub fn native_call<'env, R: NativeReturn<'env>, E: NativeError<'env>>(
+    env: Env<'env>,
+    body: impl FnOnce() -> Result<R, E>,
+) -> R::Raw {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(result) => native_return(env, result),
+        Err(payload) => {
+            throw_panic(env, &*payload);
+            R::native_return_default()
+        }
+    }
+}
+
+/// Throws a `java.lang.RuntimeException` for the panic that unwound with `payload`.
+pub(crate) fn throw_panic(env: Env, payload: &(dyn Any + Send)) {
+    let message = panic_message(payload);
+    let jnienv = env.as_raw();
+    unsafe {
+        // Few JNI functions may be called with an exception pending, so take it aside to be the cause.
+        let cause = ((**jnienv).v1_2.ExceptionOccurred)(jnienv);
+        if !cause.is_null() {
+            ((**jnienv).v1_2.ExceptionClear)(jnienv);
+        }
+        env.throw_new_caused("java/lang/RuntimeException\0", message, cause);
+        if !cause.is_null() {
+            ((**jnienv).v1_2.DeleteLocalRef)(jnienv, cause);
+        }
+    }
+}
+
+/// The message `panic!` unwound with, if it was given one.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.as_str()
+    } else {
+        "Rust panic"
+    }
+}
+
+#[test]
+fn panic_messages() {
+    let message = |body: fn()| panic_message(&*panic::catch_unwind(body).unwrap_err()).to_string();
+    assert_eq!(message(|| panic!("static")), "static");
+    assert_eq!(message(|| panic!("formatted {}", 42)), "formatted 42");
+    assert_eq!(message(|| panic::panic_any(42)), "Rust panic");
+}
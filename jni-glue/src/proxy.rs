@@ -0,0 +1,903 @@
+//! Java interfaces implemented in Rust, with a
+//! [java.lang.reflect.Proxy](https://docs.oracle.com/javase/8/docs/api/java/lang/reflect/Proxy.html).
+//!
+//! [new_proxy] creates a proxy whose `InvocationHandler` is a `jni_glue.RustInvocationHandler`:  a class with a native
+//! `invoke` forwarding calls to a boxed Rust [InvocationHandler], and a native `finalize` dropping that box once the
+//! handler (and thus the proxy) is garbage collected.
+//!
+//! Desktop JVMs get `RustInvocationHandler` defined at runtime, from class file bytes built here.  Android can't
+//! `DefineClass`, so Android apps must ship the class themselves (and keep it from being renamed or stripped.)  It's
+//! looked up with `FindClass` first, so the first [new_proxy] must happen on a thread that can find app classes, such
+//! as one called from Java:
+//!
+//! ```java
+//! package jni_glue;
+//!
+//! public final class RustInvocationHandler implements java.lang.reflect.InvocationHandler {
+//!     private long ptr;
+//!     private RustInvocationHandler() {}
+//!     public native Object invoke(Object proxy, java.lang.reflect.Method method, Object[] args);
+//!     protected native void finalize();
+//! }
+//! ```
+//!
+//! Only a single VM per process is supported:  class and method IDs are cached by the first [new_proxy].
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::os::raw::c_char;
+use std::panic;
+use std::ptr::null_mut;
+use std::sync::{Mutex, OnceLock};
+
+use jni_sys::*;
+
+use crate::natives::throw_panic;
+use crate::{Env, JavaException, Local, ReferenceType, ThrowableType};
+
+/// Handles the calls made to a proxy created by [new_proxy].  Implemented for closures, although you'll typically use
+/// the `from_rust` constructors jni-bindgen generates for interfaces instead.
+///
+/// `Send + Sync` as Java may call the proxy from any thread, and the handler is dropped on the finalizer thread.
+pub trait InvocationHandler: Send + Sync + 'static {
+    /// Handle a call to any of the proxy's interface methods.  `hashCode`, `equals`, and `toString` only get here if
+    /// the interface declares them (as `CharSequence` does `toString`), and use identity semantics if left
+    /// [unhandled](Invocation::unhandled).
+    fn invoke<'env>(&self, invocation: &Invocation<'env>) -> Response<'env>;
+}
+
+impl<F> InvocationHandler for F
+where
+    F: for<'env> Fn(&Invocation<'env>) -> Response<'env> + Send + Sync + 'static,
+{
+    fn invoke<'env>(&self, invocation: &Invocation<'env>) -> Response<'env> {
+        self(invocation)
+    }
+}
+
+/// Returns `handler` as is.  Helps type inference along for closures, which don't otherwise get a signature with
+/// matching `'env` lifetimes:
+///
+/// ```rust,no_run
+/// # use jni_glue::*;
+/// # fn example<T: ReferenceType>(env: Env) {
+/// let proxy = new_proxy::<T>(env, invocation_handler(|invocation| invocation.unhandled()));
+/// # }
+/// ```
+pub fn invocation_handler<F>(handler: F) -> F
+where
+    F: for<'env> Fn(&Invocation<'env>) -> Response<'env> + Send + Sync + 'static,
+{
+    handler
+}
+
+/// A call made to a proxy created by [new_proxy].
+pub struct Invocation<'env> {
+    env: Env<'env>,
+    handler: &'env Handler,
+    method: jmethodID,
+    reflected: jobject,
+    args: jobjectArray,
+}
+
+impl<'env> Invocation<'env> {
+    pub fn env(&self) -> Env<'env> {
+        self.env
+    }
+
+    /// The method being called.
+    pub fn method(&self) -> jmethodID {
+        self.method
+    }
+
+    /// Is this a call to the proxied interface's `method` (with `descriptor`), declared or inherited?  Both strings must
+    /// be '\0' terminated, as with [Env::require_method].
+    pub fn is(&self, method: &str, descriptor: &str) -> bool {
+        debug_assert!(method.ends_with('\0'));
+        debug_assert!(descriptor.ends_with('\0'));
+
+        let env = self.env.as_raw();
+        unsafe {
+            let id = ((**env).v1_2.GetMethodID)(
+                env,
+                self.handler.interface,
+                method.as_ptr() as *const c_char,
+                descriptor.as_ptr() as *const c_char,
+            );
+            if id.is_null() {
+                ((**env).v1_2.ExceptionClear)(env);
+                return false;
+            }
+            id == self.method
+        }
+    }
+
+    /// The number of arguments passed.
+    pub fn len(&self) -> usize {
+        if self.args.is_null() {
+            return 0; // Proxies pass null instead of empty arrays
+        }
+        let env = self.env.as_raw();
+        unsafe { ((**env).v1_2.GetArrayLength)(env, self.args) as usize }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The argument at `index`, unboxed if it's a primitive.  Panics if `index` is out of bounds.
+    ///
+    /// Java checks arguments against the method's descriptor, but not against generic types - or against `A` at all,
+    /// should the wrong method be dispatched to.  So if the argument isn't an `A`, this returns a [Response] throwing a
+    /// `ClassCastException` instead (or a `NullPointerException`, if `A` is a primitive and the argument null), as
+    /// Java's own casts would.
+    pub fn arg<A: ProxyArg<'env>>(&self, index: usize) -> Result<A, Response<'env>> {
+        assert!(
+            index < self.len(),
+            "jni_glue::Invocation::arg({}):  out of bounds",
+            index
+        );
+        let env = self.env.as_raw();
+        unsafe {
+            let object = ((**env).v1_2.GetObjectArrayElement)(env, self.args, index as jsize);
+            A::from_proxy_arg(self, object)
+        }
+    }
+
+    /// Is `object` an instance of `class` (a null terminated JNI class name), as seen from the proxied interface?
+    unsafe fn is_instance_of(&self, object: jobject, class: &str) -> bool {
+        let class = self.handler.class(self.env, class);
+        let env = self.env.as_raw();
+        !class.is_null() && ((**env).v1_2.IsInstanceOf)(env, object, class) != JNI_FALSE
+    }
+
+    /// Return `result` from the call, boxing primitives, or throw its error.
+    pub fn returns<R: ProxyReturn<'env>, E: ThrowableType>(&self, result: Result<R, Local<'env, E>>) -> Response<'env> {
+        let response = match result {
+            Ok(value) => ResponseKind::Return(value.into_proxy_return(self.env)),
+            Err(exception) => ResponseKind::Throw(exception.into_raw()),
+        };
+        Response {
+            kind: response,
+            pd: PhantomData,
+        }
+    }
+
+    /// Throw a `java.lang.UnsupportedOperationException` naming the method.
+    pub fn unhandled(&self) -> Response<'env> {
+        Response {
+            kind: ResponseKind::Unhandled,
+            pd: PhantomData,
+        }
+    }
+}
+
+impl<'env> Response<'env> {
+    /// Throw a new `class` (a null terminated JNI class name) with `message`.
+    fn throw_new(class: &'static str, message: String) -> Self {
+        Response {
+            kind: ResponseKind::ThrowNew { class, message },
+            pd: PhantomData,
+        }
+    }
+}
+
+/// The result of an [InvocationHandler::invoke], from [Invocation::returns] or [Invocation::unhandled].
+pub struct Response<'env> {
+    kind: ResponseKind,
+    pd: PhantomData<Env<'env>>,
+}
+
+enum ResponseKind {
+    Return(jobject),
+    Throw(jthrowable),
+    ThrowNew { class: &'static str, message: String },
+    Unhandled,
+}
+
+/// Types [Invocation::arg] can convert arguments to:  `Option<Local<T>>` and the primitives jni-bindgen binds.
+///
+/// **unsafe**:  `from_proxy_arg` must take ownership of `object`, a local reference, and check it really is what it's
+/// interpreted as.
+pub unsafe trait ProxyArg<'env>: Sized {
+    #[doc(hidden)]
+    unsafe fn from_proxy_arg(invocation: &Invocation<'env>, object: jobject) -> Result<Self, Response<'env>>;
+}
+
+/// Types [Invocation::returns] can return:  `()`, `Option<Local<T>>`, and the primitives jni-bindgen binds.
+///
+/// **unsafe**:  `into_proxy_return` must return a local reference (or null) the method's return type can be unboxed
+/// or cast from.
+pub unsafe trait ProxyReturn<'env> {
+    #[doc(hidden)]
+    fn into_proxy_return(self, env: Env<'env>) -> jobject;
+}
+
+unsafe impl<'env, T: ReferenceType> ProxyArg<'env> for Option<Local<'env, T>> {
+    unsafe fn from_proxy_arg(invocation: &Invocation<'env>, object: jobject) -> Result<Self, Response<'env>> {
+        if object.is_null() {
+            return Ok(None);
+        }
+        let object = Local::<T>::from_raw(invocation.env, object);
+        T::static_with_jni_type(|jni_type| {
+            if invocation.is_instance_of(object.as_raw(), jni_type) {
+                Ok(Some(object))
+            } else {
+                Err(class_cast(jni_type))
+            }
+        })
+    }
+}
+
+fn class_cast<'env>(class: &str) -> Response<'env> {
+    let class = class.trim_end_matches('\0').replace('/', ".");
+    Response::throw_new(
+        "java/lang/ClassCastException\0",
+        format!("proxy argument isn't a {}", class),
+    )
+}
+
+unsafe impl<'env, T: ReferenceType> ProxyReturn<'env> for Option<Local<'env, T>> {
+    fn into_proxy_return(self, _env: Env<'env>) -> jobject {
+        self.map_or(null_mut(), Local::into_raw)
+    }
+}
+
+unsafe impl<'env> ProxyReturn<'env> for () {
+    fn into_proxy_return(self, _env: Env<'env>) -> jobject {
+        null_mut()
+    }
+}
+
+macro_rules! primitives {
+    ($($rust:ty => $class:literal, $field:ident, $value_of:literal, $unbox:literal, $unbox_descriptor:literal, $call:ident, $from:expr;)*) => {
+        $(
+            unsafe impl<'env> ProxyArg<'env> for $rust {
+                unsafe fn from_proxy_arg(invocation: &Invocation<'env>, object: jobject) -> Result<Self, Response<'env>> {
+                    let env = invocation.env;
+                    if object.is_null() {
+                        return Err(Response::throw_new(
+                            "java/lang/NullPointerException\0",
+                            format!("proxy argument is null instead of a {}", stringify!($rust)),
+                        ));
+                    }
+                    let class = env.require_class($class);
+                    let jnienv = env.as_raw();
+                    let result = if ((**jnienv).v1_2.IsInstanceOf)(jnienv, object, class) {
+                        let method = env.require_method(class, $unbox, $unbox_descriptor);
+                        Ok(($from)(((**jnienv).v1_2.$call)(jnienv, object, method, [].as_ptr())))
+                    } else {
+                        Err(class_cast($class))
+                    };
+                    for local in [object, class] {
+                        ((**jnienv).v1_2.DeleteLocalRef)(jnienv, local);
+                    }
+                    result
+                }
+            }
+
+            unsafe impl<'env> ProxyReturn<'env> for $rust {
+                fn into_proxy_return(self, env: Env<'env>) -> jobject {
+                    unsafe {
+                        let (class, method) = env.require_class_static_method($class, "valueOf\0", $value_of);
+                        let args = [jvalue { $field: self as _ }];
+                        let env = env.as_raw();
+                        let boxed = ((**env).v1_2.CallStaticObjectMethodA)(env, class, method, args.as_ptr());
+                        ((**env).v1_2.DeleteLocalRef)(env, class);
+                        boxed
+                    }
+                }
+            }
+        )*
+    };
+}
+
+primitives! {
+    bool    => "java/lang/Boolean\0",   z, "(Z)Ljava/lang/Boolean;\0",   "booleanValue\0", "()Z\0", CallBooleanMethodA, |v| v != JNI_FALSE;
+    i8      => "java/lang/Byte\0",      b, "(B)Ljava/lang/Byte;\0",      "byteValue\0",    "()B\0", CallByteMethodA, |v| v;
+    u16     => "java/lang/Character\0", c, "(C)Ljava/lang/Character;\0", "charValue\0",    "()C\0", CallCharMethodA, |v| v;
+    i16     => "java/lang/Short\0",     s, "(S)Ljava/lang/Short;\0",     "shortValue\0",   "()S\0", CallShortMethodA, |v| v;
+    i32     => "java/lang/Integer\0",   i, "(I)Ljava/lang/Integer;\0",   "intValue\0",     "()I\0", CallIntMethodA, |v| v;
+    i64     => "java/lang/Long\0",      j, "(J)Ljava/lang/Long;\0",      "longValue\0",    "()J\0", CallLongMethodA, |v| v;
+    f32     => "java/lang/Float\0",     f, "(F)Ljava/lang/Float;\0",     "floatValue\0",   "()F\0", CallFloatMethodA, |v| v;
+    f64     => "java/lang/Double\0",    d, "(D)Ljava/lang/Double;\0",    "doubleValue\0",  "()D\0", CallDoubleMethodA, |v| v;
+}
+
+/// Create a `java.lang.reflect.Proxy` implementing the interface `T`, forwarding calls to `handler`.  `handler` is
+/// dropped once the proxy is garbage collected.
+///
+/// Returns the exception thrown by `Proxy.newProxyInstance` if `T` isn't an interface, or isn't visible from its own
+/// class loader.
+pub fn new_proxy<'env, T: ReferenceType>(
+    env: Env<'env>,
+    handler: impl InvocationHandler,
+) -> Result<Local<'env, T>, JavaException> {
+    let statics = Statics::get(env);
+    let jnienv = env.as_raw();
+    unsafe {
+        let interface = T::static_with_jni_type(|t| env.require_class(t));
+        let loader = ((**jnienv).v1_2.CallObjectMethodA)(jnienv, interface, statics.get_class_loader, [].as_ptr());
+        let handler = Box::new(Handler {
+            interface: ((**jnienv).v1_2.NewGlobalRef)(jnienv, interface),
+            loader: ((**jnienv).v1_2.NewGlobalRef)(jnienv, loader),
+            classes: Mutex::new(HashMap::new()),
+            handler: Box::new(handler),
+        });
+        let interfaces = ((**jnienv).v1_2.NewObjectArray)(jnienv, 1, statics.class_class, interface);
+        let invocation_handler =
+            ((**jnienv).v1_2.NewObjectA)(jnienv, statics.handler_class, statics.handler_init, [].as_ptr());
+        if let Some(exception) = env.take_java_exception() {
+            handler.delete_globals(env);
+            for local in [interface, loader, interfaces, invocation_handler] {
+                if !local.is_null() {
+                    ((**jnienv).v1_2.DeleteLocalRef)(jnienv, local);
+                }
+            }
+            return Err(exception);
+        }
+        ((**jnienv).v1_2.SetLongField)(
+            jnienv,
+            invocation_handler,
+            statics.handler_ptr,
+            Box::into_raw(handler) as jlong,
+        ); // Owned by invocation_handler from here on out
+
+        let args = [
+            jvalue { l: loader },
+            jvalue { l: interfaces },
+            jvalue { l: invocation_handler },
+        ];
+        let proxy = ((**jnienv).v1_2.CallStaticObjectMethodA)(
+            jnienv,
+            statics.proxy_class,
+            statics.new_proxy_instance,
+            args.as_ptr(),
+        );
+        for local in [interface, loader, interfaces, invocation_handler] {
+            ((**jnienv).v1_2.DeleteLocalRef)(jnienv, local);
+        }
+        match env.take_java_exception() {
+            Some(exception) => Err(exception),
+            None => Ok(Local::from_raw(env, proxy)),
+        }
+    }
+}
+
+/// What a `RustInvocationHandler`'s `ptr` points to.
+struct Handler {
+    /// The proxied interface, as a global reference.
+    interface: jclass,
+    /// The proxied interface's class loader, as a global reference (or null, for the bootstrap loader.)
+    loader: jobject,
+    /// Classes found by [Handler::class], by JNI name, as global references.
+    classes: Mutex<HashMap<String, jclass>>,
+    handler: Box<dyn InvocationHandler>,
+}
+
+impl Handler {
+    /// Finds `class` (a null terminated JNI class name) with the proxied interface's class loader, returning null if
+    /// it can't.  `FindClass` won't do:  called from `RustInvocationHandler`'s natives, it uses *that* class's loader,
+    /// which on desktop is the bootstrap loader - and can't see application classes.
+    unsafe fn class(&self, env: Env, class: &str) -> jclass {
+        if let Some(&found) = self.classes.lock().unwrap().get(class) {
+            return found;
+        }
+
+        // Loading classes can run arbitrary Java code - including calls to this proxy - so don't hold the lock for it.
+        let statics = STATICS.get().unwrap();
+        let jnienv = env.as_raw();
+        let name = format!("{}\0", class.trim_end_matches('\0').replace('/', "."));
+        let name = env.new_string_utf(name.as_ptr() as *const c_char);
+        let args = [jvalue { l: name }, jvalue { z: JNI_FALSE }, jvalue { l: self.loader }];
+        let found = ((**jnienv).v1_2.CallStaticObjectMethodA)(
+            jnienv,
+            statics.class_class,
+            statics.class_for_name,
+            args.as_ptr(),
+        );
+        ((**jnienv).v1_2.DeleteLocalRef)(jnienv, name);
+        if found.is_null() {
+            ((**jnienv).v1_2.ExceptionClear)(jnienv); // ClassNotFoundException
+            return null_mut();
+        }
+
+        let global = ((**jnienv).v1_2.NewGlobalRef)(jnienv, found);
+        ((**jnienv).v1_2.DeleteLocalRef)(jnienv, found);
+        let mut classes = self.classes.lock().unwrap();
+        let cached = *classes.entry(class.to_owned()).or_insert(global);
+        if cached != global {
+            ((**jnienv).v1_2.DeleteGlobalRef)(jnienv, global); // Another thread found it first
+        }
+        cached
+    }
+
+    unsafe fn delete_globals(&self, env: Env) {
+        let jnienv = env.as_raw();
+        let classes = self.classes.lock().unwrap();
+        for global in [self.interface, self.loader]
+            .into_iter()
+            .chain(classes.values().copied())
+        {
+            if !global.is_null() {
+                ((**jnienv).v1_2.DeleteGlobalRef)(jnienv, global);
+            }
+        }
+    }
+}
+
+const HANDLER_CLASS: &str = "jni_glue/RustInvocationHandler\0";
+const INVOKE_DESCRIPTOR: &str = "(Ljava/lang/Object;Ljava/lang/reflect/Method;[Ljava/lang/Object;)Ljava/lang/Object;\0";
+
+/// Classes (as global references) and member IDs cached by the first [new_proxy].
+struct Statics {
+    handler_class: jclass,
+    handler_init: jmethodID,
+    handler_ptr: jfieldID,
+    object_class: jclass,
+    object_hash_code: jmethodID,
+    object_equals: jmethodID,
+    object_to_string: jmethodID,
+    class_class: jclass,
+    get_class_loader: jmethodID,
+    class_for_name: jmethodID,
+    proxy_class: jclass,
+    new_proxy_instance: jmethodID,
+    unsupported_operation: jclass,
+    unsupported_operation_init: jmethodID,
+}
+
+// Global references and member IDs are valid on any thread.
+unsafe impl Send for Statics {}
+unsafe impl Sync for Statics {}
+
+static STATICS: OnceLock<Statics> = OnceLock::new();
+
+impl Statics {
+    fn get(env: Env) -> &'static Self {
+        STATICS.get_or_init(|| unsafe { Self::new(env) })
+    }
+
+    unsafe fn new(env: Env) -> Self {
+        let global = |class: jclass| {
+            let jnienv = env.as_raw();
+            let global = ((**jnienv).v1_2.NewGlobalRef)(jnienv, class);
+            ((**jnienv).v1_2.DeleteLocalRef)(jnienv, class);
+            global
+        };
+        let handler_class = global(define_handler_class(env));
+        let object_class = global(env.require_class("java/lang/Object\0"));
+        let class_class = global(env.require_class("java/lang/Class\0"));
+        let proxy_class = global(env.require_class("java/lang/reflect/Proxy\0"));
+        let unsupported_operation = global(env.require_class("java/lang/UnsupportedOperationException\0"));
+        Self {
+            handler_class,
+            handler_init: env.require_method(handler_class, "<init>\0", "()V\0"),
+            handler_ptr: env.require_field(handler_class, "ptr\0", "J\0"),
+            object_class,
+            object_hash_code: env.require_method(object_class, "hashCode\0", "()I\0"),
+            object_equals: env.require_method(object_class, "equals\0", "(Ljava/lang/Object;)Z\0"),
+            object_to_string: env.require_method(object_class, "toString\0", "()Ljava/lang/String;\0"),
+            class_class,
+            get_class_loader: env.require_method(class_class, "getClassLoader\0", "()Ljava/lang/ClassLoader;\0"),
+            class_for_name: env.require_static_method(
+                class_class,
+                "forName\0",
+                "(Ljava/lang/String;ZLjava/lang/ClassLoader;)Ljava/lang/Class;\0",
+            ),
+            proxy_class,
+            new_proxy_instance: env.require_static_method(
+                proxy_class,
+                "newProxyInstance\0",
+                "(Ljava/lang/ClassLoader;[Ljava/lang/Class;Ljava/lang/reflect/InvocationHandler;)Ljava/lang/Object;\0",
+            ),
+            unsupported_operation,
+            unsupported_operation_init: env.require_method(
+                unsupported_operation,
+                "<init>\0",
+                "(Ljava/lang/String;)V\0",
+            ),
+        }
+    }
+}
+
+/// Finds (Android) or defines (desktop) `RustInvocationHandler`, and registers its natives.
+unsafe fn define_handler_class(env: Env) -> jclass {
+    let jnienv = env.as_raw();
+    let name = HANDLER_CLASS.as_ptr() as *const c_char;
+    let mut class = ((**jnienv).v1_2.FindClass)(jnienv, name);
+    if class.is_null() {
+        ((**jnienv).v1_2.ExceptionClear)(jnienv);
+        let bytes = handler_class_file();
+        class = ((**jnienv).v1_2.DefineClass)(
+            jnienv,
+            name,
+            null_mut(),
+            bytes.as_ptr() as *const jbyte,
+            bytes.len() as jsize,
+        );
+        if class.is_null() {
+            ((**jnienv).v1_2.ExceptionClear)(jnienv);
+            panic!("jni_glue::new_proxy():  jni_glue.RustInvocationHandler couldn't be found or defined - Android apps must include it");
+        }
+    }
+
+    let natives = [
+        JNINativeMethod {
+            name: c"invoke".as_ptr() as *mut c_char,
+            signature: INVOKE_DESCRIPTOR.as_ptr() as *mut c_char,
+            fnPtr: invoke as *mut _,
+        },
+        JNINativeMethod {
+            name: c"finalize".as_ptr() as *mut c_char,
+            signature: c"()V".as_ptr() as *mut c_char,
+            fnPtr: finalize as *mut _,
+        },
+    ];
+    let err = ((**jnienv).v1_2.RegisterNatives)(jnienv, class, natives.as_ptr(), natives.len() as jint);
+    assert_eq!(err, JNI_OK);
+    class
+}
+
+/// `RustInvocationHandler` (as in the [module](self) docs) as a class file.  Its constructor is real bytecode, as HotSpot
+/// registers objects for finalization in `Object.<init>`.
+fn handler_class_file() -> Vec<u8> {
+    let utf8s = [
+        "jni_glue/RustInvocationHandler", // #1
+        "java/lang/Object",
+        "java/lang/reflect/InvocationHandler",
+        "ptr",
+        "J", // #5
+        "invoke",
+        INVOKE_DESCRIPTOR.trim_end_matches('\0'),
+        "finalize",
+        "()V",
+        "<init>", // #10
+        "Code",
+    ];
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&0xCAFE_BABE_u32.to_be_bytes());
+    out.extend_from_slice(&[0, 0, 0, 52]); // minor_version, major_version (Java 8)
+    out.extend_from_slice(&17u16.to_be_bytes()); // constant_pool_count
+    for utf8 in utf8s {
+        out.push(1); // CONSTANT_Utf8 (all ASCII, so modified UTF-8 is just UTF-8)
+        out.extend_from_slice(&(utf8.len() as u16).to_be_bytes());
+        out.extend_from_slice(utf8.as_bytes());
+    }
+    for (tag, values) in [
+        (7, &[1][..]),   // #12 CONSTANT_Class:  RustInvocationHandler
+        (7, &[2][..]),   // #13 CONSTANT_Class:  Object
+        (7, &[3][..]),   // #14 CONSTANT_Class:  InvocationHandler
+        (12, &[10, 9]),  // #15 CONSTANT_NameAndType:  <init> ()V
+        (10, &[13, 15]), // #16 CONSTANT_Methodref:  Object.<init>()V
+    ] {
+        out.push(tag);
+        for value in values {
+            out.extend_from_slice(&u16::to_be_bytes(*value));
+        }
+    }
+
+    let tables: [u16; 19] = [
+        0x0031, 12, 13, // ACC_PUBLIC | ACC_FINAL | ACC_SUPER, this_class, super_class
+        1, 14, // interfaces
+        1, 0x0002, 4, 5, 0, // fields:  private long ptr
+        3, 0x0101, 6, 7, 0, // methods:  public native Object invoke(...)
+        0x0104, 8, 9, 0, // protected native void finalize()
+    ];
+    for value in tables {
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+    // private <init>() { super(); }
+    for value in [0x0002, 10, 9, 1, 11] {
+        out.extend_from_slice(&u16::to_be_bytes(value)); // access_flags, name, descriptor, attributes_count, Code
+    }
+    out.extend_from_slice(&17u32.to_be_bytes()); // attribute_length
+    out.extend_from_slice(&[0, 1, 0, 1, 0, 0, 0, 5]); // max_stack, max_locals, code_length
+    out.extend_from_slice(&[0x2a, 0xb7, 0, 16, 0xb1]); // aload_0, invokespecial #16, return
+    out.extend_from_slice(&[0, 0, 0, 0]); // exception_table_length, attributes_count
+
+    out.extend_from_slice(&0u16.to_be_bytes()); // attributes_count
+    out
+}
+
+/// `RustInvocationHandler.invoke`
+extern "system" fn invoke(
+    env: *mut JNIEnv,
+    this: jobject,
+    proxy: jobject,
+    method: jobject,
+    args: jobjectArray,
+) -> jobject {
+    panic::catch_unwind(|| unsafe { invoke_unwinding(env, this, proxy, method, args) }).unwrap_or_else(|payload| {
+        throw_panic(unsafe { Env::from_raw(env) }, &*payload);
+        null_mut()
+    })
+}
+
+/// [invoke], minus catching panics - which mustn't unwind into Java.
+unsafe fn invoke_unwinding(
+    env: *mut JNIEnv,
+    this: jobject,
+    proxy: jobject,
+    method: jobject,
+    args: jobjectArray,
+) -> jobject {
+    let statics = STATICS
+        .get()
+        .expect("RustInvocationHandler.invoke called before jni_glue::new_proxy()");
+    let handler = &*(((**env).v1_2.GetLongField)(env, this, statics.handler_ptr) as *const Handler);
+    let mut method_id = ((**env).v1_2.FromReflectedMethod)(env, method);
+
+    // Proxies are passed Object's own `hashCode`, `equals`, and `toString`, even for interfaces redeclaring them.  Only
+    // redeclared ones are forwarded to the handler (as the interface's, so `Invocation::is` recognizes them), and any
+    // it leaves unhandled get identity semantics, like most Java objects.
+    let object_method = [
+        (statics.object_hash_code, "hashCode\0", "()I\0"),
+        (statics.object_equals, "equals\0", "(Ljava/lang/Object;)Z\0"),
+        (statics.object_to_string, "toString\0", "()Ljava/lang/String;\0"),
+    ]
+    .into_iter()
+    .find(|(id, _, _)| *id == method_id);
+    if let Some((object_method, name, descriptor)) = object_method {
+        let declared = ((**env).v1_2.GetMethodID)(
+            env,
+            handler.interface,
+            name.as_ptr() as *const c_char,
+            descriptor.as_ptr() as *const c_char,
+        );
+        if declared.is_null() {
+            ((**env).v1_2.ExceptionClear)(env);
+        }
+        if declared.is_null() || declared == object_method {
+            return identity(env, object_method, proxy, args);
+        }
+        method_id = declared;
+    }
+
+    let invocation = Invocation {
+        env: Env::from_raw(env),
+        handler,
+        method: method_id,
+        reflected: method,
+        args,
+    };
+    match handler.handler.invoke(&invocation).kind {
+        ResponseKind::Return(value) => value,
+        ResponseKind::Throw(exception) => {
+            ((**env).v1_2.Throw)(env, exception);
+            null_mut()
+        }
+        ResponseKind::ThrowNew { class, message } => {
+            Env::from_raw(env).throw_new_caused(class, &message, null_mut());
+            null_mut()
+        }
+        ResponseKind::Unhandled if object_method.is_some() => identity(env, object_method.unwrap().0, proxy, args),
+        ResponseKind::Unhandled => {
+            let message =
+                ((**env).v1_2.CallObjectMethodA)(env, invocation.reflected, statics.object_to_string, [].as_ptr());
+            let args = [jvalue { l: message }];
+            let exception = ((**env).v1_2.NewObjectA)(
+                env,
+                statics.unsupported_operation,
+                statics.unsupported_operation_init,
+                args.as_ptr(),
+            );
+            if !exception.is_null() {
+                ((**env).v1_2.Throw)(env, exception);
+            }
+            null_mut()
+        }
+    }
+}
+
+/// Object's `hashCode`, `equals`, or `toString` (as `method`) for `proxy`, with identity semantics.
+unsafe fn identity(env: *mut JNIEnv, method: jmethodID, proxy: jobject, args: jobjectArray) -> jobject {
+    let statics = STATICS.get().unwrap();
+    if method == statics.object_hash_code {
+        let hash_code = ((**env).v1_2.CallNonvirtualIntMethodA)(env, proxy, statics.object_class, method, [].as_ptr());
+        hash_code.into_proxy_return(Env::from_raw(env))
+    } else if method == statics.object_equals {
+        let other = ((**env).v1_2.GetObjectArrayElement)(env, args, 0);
+        let equals = ((**env).v1_2.IsSameObject)(env, proxy, other) != JNI_FALSE;
+        ((**env).v1_2.DeleteLocalRef)(env, other);
+        equals.into_proxy_return(Env::from_raw(env))
+    } else {
+        ((**env).v1_2.CallNonvirtualObjectMethodA)(env, proxy, statics.object_class, method, [].as_ptr())
+    }
+}
+
+/// `RustInvocationHandler.finalize`
+extern "system" fn finalize(env: *mut JNIEnv, this: jobject) {
+    // Dropping the handler runs arbitrary Rust code, which mustn't unwind into Java either.
+    if let Err(payload) = panic::catch_unwind(|| unsafe { finalize_unwinding(env, this) }) {
+        throw_panic(unsafe { Env::from_raw(env) }, &*payload);
+    }
+}
+
+unsafe fn finalize_unwinding(env: *mut JNIEnv, this: jobject) {
+    let statics = STATICS
+        .get()
+        .expect("RustInvocationHandler.finalize called before jni_glue::new_proxy()");
+    let handler = ((**env).v1_2.GetLongField)(env, this, statics.handler_ptr) as *mut Handler;
+    if !handler.is_null() {
+        ((**env).v1_2.SetLongField)(env, this, statics.handler_ptr, 0);
+        Box::from_raw(handler).delete_globals(Env::from_raw(env));
+    }
+}
+
+#[test]
+fn handler_class_file_structure() {
+    fn take<'a>(rest: &mut &'a [u8], len: usize) -> &'a [u8] {
+        let (taken, remaining) = rest.split_at(len);
+        *rest = remaining;
+        taken
+    }
+    fn u16(rest: &mut &[u8]) -> u16 {
+        let bytes = take(rest, 2);
+        u16::from_be_bytes([bytes[0], bytes[1]])
+    }
+
+    let bytes = handler_class_file();
+    let rest = &mut &bytes[..];
+    assert_eq!(take(rest, 4), &0xCAFE_BABE_u32.to_be_bytes());
+    assert_eq!((u16(rest), u16(rest)), (0, 52));
+
+    // Constant pool:  every entry well formed, and the count (one more than the number of entries) right
+    let count = u16(rest);
+    let mut utf8s = vec![String::new()]; // constant_pool[0] is unused
+    for _ in 1..count {
+        let tag = take(rest, 1)[0];
+        let utf8 = match tag {
+            1 => {
+                let len = u16(rest) as usize;
+                std::str::from_utf8(take(rest, len)).unwrap().to_owned()
+            }
+            7 => {
+                assert!(u16(rest) < count);
+                String::new()
+            }
+            10 | 12 => {
+                assert!(u16(rest) < count && u16(rest) < count);
+                String::new()
+            }
+            _ => panic!("unexpected constant pool tag {}", tag),
+        };
+        utf8s.push(utf8);
+    }
+    let utf8 = |index: u16| utf8s[index as usize].clone();
+
+    assert_eq!(u16(rest), 0x0031); // ACC_PUBLIC | ACC_FINAL | ACC_SUPER
+    assert_eq!((u16(rest), u16(rest)), (12, 13)); // this_class, super_class
+    assert_eq!((u16(rest), u16(rest)), (1, 14)); // implements InvocationHandler
+
+    let mut members = Vec::new();
+    for _fields_then_methods in 0..2 {
+        for _ in 0..u16(rest) {
+            let (flags, name, descriptor) = (u16(rest), utf8(u16(rest)), utf8(u16(rest)));
+            for _ in 0..u16(rest) {
+                assert_eq!(utf8(u16(rest)), "Code");
+                let len = u32::from_be_bytes(take(rest, 4).try_into().unwrap());
+                take(rest, len as usize);
+            }
+            members.push((flags, name, descriptor));
+        }
+    }
+    assert_eq!(u16(rest), 0); // attributes_count
+    assert!(rest.is_empty());
+
+    let member = |flags: u16, name: &str, descriptor: &str| (flags, name.to_owned(), descriptor.to_owned());
+    assert_eq!(
+        members,
+        vec![
+            member(0x0002, "ptr", "J"),
+            member(0x0101, "invoke", INVOKE_DESCRIPTOR.trim_end_matches('\0')),
+            member(0x0104, "finalize", "()V"),
+            member(0x0002, "<init>", "()V"),
+        ]
+    );
+}
+
+#[test]
+#[allow(non_camel_case_types)] // As generated bindings do
+fn app_class_arguments() {
+    use crate::test_vm::*;
+    use crate::{__jni_bindgen, ThrowableType};
+
+    __jni_bindgen! {
+        public interface Sink ("jni_glue/test/Sink\0") extends () {}
+        public class Ev ("jni_glue/test/Ev\0") extends () {}
+        public class Throwable ("java/lang/Throwable\0") extends () {}
+    }
+    impl ThrowableType for Throwable {}
+
+    with_env(|env| unsafe {
+        // Classes the VM's bootstrap loader (which defines RustInvocationHandler on desktop) can't see.
+        let ev = define_app_class(
+            env,
+            "jni_glue/test/Ev",
+            &class_file(0x0021, "jni_glue/test/Ev", &[], &[]),
+        );
+        let handle = (0x0401, "handle", "(Ljni_glue/test/Ev;)I");
+        let sink = define_app_class(
+            env,
+            "jni_glue/test/Sink",
+            &class_file(0x0601, "jni_glue/test/Sink", &[], &[handle]),
+        );
+
+        let proxy = new_proxy::<Sink>(
+            env,
+            invocation_handler(|invocation| {
+                let ev: Option<Local<Ev>> = match invocation.arg(0) {
+                    Ok(ev) => ev,
+                    Err(response) => return response,
+                };
+                invocation.returns(Ok::<_, Local<Throwable>>(if ev.is_some() { 1 } else { 2 }))
+            }),
+        )
+        .unwrap();
+
+        let jnienv = env.as_raw();
+        let method = env.require_method(sink, "handle\0", "(Ljni_glue/test/Ev;)I\0");
+        let call = |arg: jobject| {
+            let result = ((**jnienv).v1_2.CallIntMethodA)(jnienv, proxy.as_raw(), method, [jvalue { l: arg }].as_ptr());
+            assert!(env.take_java_exception().is_none());
+            result
+        };
+        let ev_object = ((**jnienv).v1_2.AllocObject)(jnienv, ev);
+        assert_eq!(call(ev_object), 1);
+        assert_eq!(call(null_mut()), 2);
+    });
+}
+
+#[test]
+#[allow(non_camel_case_types)] // As generated bindings do
+fn object_methods() {
+    use crate::test_vm::*;
+    use crate::{__jni_bindgen, StringUtfChars};
+
+    __jni_bindgen! {
+        public interface CharSequence ("java/lang/CharSequence\0") extends () {}
+        public class JString ("java/lang/String\0") extends () {}
+        public class Throwable ("java/lang/Throwable\0") extends () {}
+    }
+    impl ThrowableType for Throwable {}
+
+    with_env(|env| unsafe {
+        let jnienv = env.as_raw();
+        let object = env.require_class("java/lang/Object\0");
+        let to_string = env.require_method(object, "toString\0", "()Ljava/lang/String;\0");
+        let equals = env.require_method(object, "equals\0", "(Ljava/lang/Object;)Z\0");
+        let to_string = |proxy: &Local<CharSequence>| {
+            let string = ((**jnienv).v1_2.CallObjectMethodA)(jnienv, proxy.as_raw(), to_string, [].as_ptr());
+            assert!(env.take_java_exception().is_none());
+            StringUtfChars::from_env_jstring(env, string)
+                .to_str_lossy()
+                .into_owned()
+        };
+
+        // CharSequence redeclares toString, so it's forwarded...
+        let named = new_proxy::<CharSequence>(
+            env,
+            invocation_handler(|invocation| {
+                if invocation.is("toString\0", "()Ljava/lang/String;\0") {
+                    let string = invocation.env().new_string_utf(c"from rust".as_ptr());
+                    let string = Local::<JString>::from_raw(invocation.env(), string);
+                    return invocation.returns(Ok::<_, Local<Throwable>>(Some(string)));
+                }
+                invocation.unhandled()
+            }),
+        )
+        .unwrap();
+        assert_eq!(to_string(&named), "from rust");
+
+        // ...but falls back to Object's if left unhandled, as equals and hashCode (which it doesn't redeclare) always do.
+        let unnamed = new_proxy::<CharSequence>(env, invocation_handler(|invocation| invocation.unhandled())).unwrap();
+        assert!(to_string(&unnamed).contains("$Proxy"));
+        let equals = |a: &Local<CharSequence>, b: &Local<CharSequence>| {
+            ((**jnienv).v1_2.CallBooleanMethodA)(jnienv, a.as_raw(), equals, [jvalue { l: b.as_raw() }].as_ptr())
+                != JNI_FALSE
+        };
+        assert!(equals(&named, &named));
+        assert!(!equals(&named, &unnamed));
+    });
+}
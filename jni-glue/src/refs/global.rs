@@ -2,7 +2,7 @@ use std::marker::PhantomData;
 
 use jni_sys::*;
 
-use crate::{Env, Local, Ref, ReferenceType, VM};
+use crate::{Env, Local, Ref, ReferenceType, Weak, VM};
 
 /// A [Global](https://www.ibm.com/support/knowledgecenter/en/SSYKE2_8.0.0/com.ibm.java.vm.80.doc/docs/jni_refs.html),
 /// non-null, reference to a Java object (+ [VM]).
@@ -47,6 +47,18 @@ impl<T: ReferenceType> Global<T> {
         object
     }
 
+    pub fn as_weak(&self) -> Weak<T> {
+        self.vm.with_env(|env| {
+            let env = env.as_raw();
+            let object = unsafe { ((**env).v1_2.NewWeakGlobalRef)(env, self.object) };
+            Weak {
+                object,
+                vm: self.vm,
+                pd: PhantomData,
+            }
+        })
+    }
+
     pub fn with<'env>(&self, env: Env<'env>) -> Ref<'env, T> {
         assert_eq!(self.vm, env.vm()); // Soundness check - env *must* belong to the same VM!
         unsafe { self.with_unchecked(env) }
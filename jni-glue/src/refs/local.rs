@@ -4,7 +4,7 @@ use std::ops::Deref;
 
 use jni_sys::*;
 
-use crate::{Env, Global, Ref, ReferenceType};
+use crate::{Env, Global, JavaException, MonitorGuard, Ref, ReferenceType, Weak};
 
 /// A [Local](https://www.ibm.com/support/knowledgecenter/en/SSYKE2_8.0.0/com.ibm.java.vm.80.doc/docs/jni_refs.html),
 /// non-null, reference to a Java object (+ [Env]) limited to the current thread/stack.
@@ -74,12 +74,29 @@ impl<'env, T: ReferenceType> Local<'env, T> {
         }
     }
 
+    pub fn as_weak(&self) -> Weak<T> {
+        let env = self.env();
+        let jnienv = env.as_raw();
+        let object = unsafe { ((**jnienv).v1_2.NewWeakGlobalRef)(jnienv, self.ref_.as_raw()) };
+        Weak {
+            object,
+            vm: env.vm(),
+            pd: PhantomData,
+        }
+    }
+
+    /// Enters the object's monitor, as `synchronized (object)` would, until the returned guard is dropped.
+    pub fn synchronized(&self) -> Result<MonitorGuard<'env, T>, JavaException> {
+        self.ref_.synchronized()
+    }
+
     pub fn cast<U: ReferenceType>(&self) -> Result<Local<'env, U>, crate::CastError> {
         let env = self.env();
         let jnienv = env.as_raw();
-        let class1 = unsafe { ((**jnienv).v1_2.GetObjectClass)(jnienv, self.as_raw()) };
-        let class2 = U::static_with_jni_type(|t| unsafe { env.require_class(t) });
-        if !unsafe { ((**jnienv).v1_2.IsAssignableFrom)(jnienv, class1, class2) } {
+        let class = U::static_with_jni_type(|t| unsafe { env.require_class(t) });
+        let is_instance = unsafe { ((**jnienv).v1_2.IsInstanceOf)(jnienv, self.as_raw(), class) };
+        unsafe { ((**jnienv).v1_2.DeleteLocalRef)(jnienv, class) };
+        if !is_instance {
             return Err(crate::CastError);
         }
         let object = unsafe { ((**jnienv).v1_2.NewLocalRef)(jnienv, self.as_raw()) };
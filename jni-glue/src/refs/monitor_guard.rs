@@ -0,0 +1,68 @@
+use std::mem;
+use std::ops::Deref;
+use std::ptr;
+
+use jni_sys::*;
+
+use crate::{JavaException, Local, Ref, ReferenceType};
+
+/// Holds a Java object's monitor - as a `synchronized (object) { ... }` block would - until dropped or
+/// [exit](Self::exit)ed.  Created by [Ref::synchronized] or [Local::synchronized].
+///
+/// Holds its own [Local] reference to the object, so it can't outlive the [Env](crate::Env) it was entered with, nor
+/// be sent to another thread - monitors must be exited by the thread that entered them.
+pub struct MonitorGuard<'env, T: ReferenceType> {
+    object: Local<'env, T>,
+}
+
+impl<'env, T: ReferenceType> MonitorGuard<'env, T> {
+    /// Enters the monitor of `object`, with `MonitorEnter`.
+    pub(crate) fn enter(object: Ref<'env, T>) -> Result<Self, JavaException> {
+        let env = object.env();
+        let jnienv = env.as_raw();
+        unsafe {
+            let object: Local<T> = Local::from_raw(env, ((**jnienv).v1_2.NewLocalRef)(jnienv, object.as_raw()));
+            if ((**jnienv).v1_2.MonitorEnter)(jnienv, object.as_raw()) == JNI_OK {
+                Ok(Self { object })
+            } else {
+                Err(env.take_java_exception_or_throw("java/lang/IllegalMonitorStateException\0", "MonitorEnter failed"))
+            }
+        }
+    }
+
+    /// Exits the monitor.  Fails with an `IllegalMonitorStateException` if this thread doesn't own it (any more.)
+    /// Dropping the guard exits the monitor too, but discards that error.
+    pub fn exit(self) -> Result<(), JavaException> {
+        let env = self.object.env();
+        let jnienv = env.as_raw();
+        let object = unsafe { ptr::read(&self.object) };
+        mem::forget(self); // Don't exit the monitor twice.
+        unsafe {
+            if ((**jnienv).v1_2.MonitorExit)(jnienv, object.as_raw()) == JNI_OK {
+                Ok(())
+            } else {
+                Err(env.take_java_exception_or_throw("java/lang/IllegalMonitorStateException\0", "MonitorExit failed"))
+            }
+        }
+    }
+}
+
+impl<'env, T: ReferenceType> Deref for MonitorGuard<'env, T> {
+    type Target = Local<'env, T>;
+    fn deref(&self) -> &Self::Target {
+        &self.object
+    }
+}
+
+impl<'env, T: ReferenceType> Drop for MonitorGuard<'env, T> {
+    fn drop(&mut self) {
+        let jnienv = self.object.env().as_raw();
+        unsafe {
+            // MonitorExit is safe to call with an exception pending - only clear the one it throws itself, if any.
+            let pending = ((**jnienv).v1_2.ExceptionCheck)(jnienv);
+            if ((**jnienv).v1_2.MonitorExit)(jnienv, self.object.as_raw()) != JNI_OK && !pending {
+                ((**jnienv).v1_2.ExceptionClear)(jnienv);
+            }
+        }
+    }
+}
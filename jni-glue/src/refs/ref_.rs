@@ -4,7 +4,7 @@ use std::ops::Deref;
 
 use jni_sys::jobject;
 
-use crate::{Env, ObjectAndEnv, ReferenceType};
+use crate::{Env, JavaException, MonitorGuard, ObjectAndEnv, ReferenceType};
 
 /// A non-null, [reference](https://www.ibm.com/support/knowledgecenter/en/SSYKE2_8.0.0/com.ibm.java.vm.80.doc/docs/jni_refs.html)
 /// to a Java object (+ [Env]).  This may refer to a [Local](crate::Local), [Global](crate::Global), local [Argument](crate::Argument), etc.
@@ -51,12 +51,18 @@ impl<'env, T: ReferenceType> Ref<'env, T> {
         self.oae.object
     }
 
+    /// Enters the object's monitor, as `synchronized (object)` would, until the returned guard is dropped.
+    pub fn synchronized(&self) -> Result<MonitorGuard<'env, T>, JavaException> {
+        MonitorGuard::enter(*self)
+    }
+
     pub fn cast<U: ReferenceType>(&self) -> Result<Ref<'env, U>, crate::CastError> {
         let env = self.env();
         let jnienv = env.as_raw();
-        let class1 = unsafe { ((**jnienv).v1_2.GetObjectClass)(jnienv, self.as_raw()) };
-        let class2 = U::static_with_jni_type(|t| unsafe { env.require_class(t) });
-        if !unsafe { ((**jnienv).v1_2.IsAssignableFrom)(jnienv, class1, class2) } {
+        let class = U::static_with_jni_type(|t| unsafe { env.require_class(t) });
+        let is_instance = unsafe { ((**jnienv).v1_2.IsInstanceOf)(jnienv, self.as_raw(), class) };
+        unsafe { ((**jnienv).v1_2.DeleteLocalRef)(jnienv, class) };
+        if !is_instance {
             return Err(crate::CastError);
         }
         Ok(unsafe { Ref::from_raw(env, self.as_raw()) })
@@ -0,0 +1,106 @@
+use std::marker::PhantomData;
+
+use jni_sys::*;
+
+use crate::{Env, Global, Local, ReferenceType, VM};
+
+/// A [Weak Global](https://docs.oracle.com/javase/8/docs/technotes/guides/jni/spec/functions.html#weak-global-references)
+/// reference to a Java object (+ [VM]) that doesn't keep it from being garbage collected.
+///
+/// Like [Global], this can be stored statically and shared between threads - e.g. to cache Android `View`s or
+/// listeners without leaking them.  The object may be collected at any time, so it must be [upgrade](Self::upgrade)d
+/// to a [Local] before use.
+///
+/// **Not FFI Safe:**  #\[repr(rust)\], and exact layout is likely to change, as for [Global].
+pub struct Weak<T: ReferenceType> {
+    pub(crate) object: jweak,
+    pub(crate) vm: VM,
+    pub(crate) pd: PhantomData<T>,
+}
+
+unsafe impl<T: ReferenceType> Send for Weak<T> {}
+unsafe impl<T: ReferenceType> Sync for Weak<T> {}
+
+impl<T: ReferenceType> Weak<T> {
+    pub unsafe fn from_raw(vm: VM, object: jweak) -> Self {
+        Self {
+            object,
+            vm,
+            pd: PhantomData,
+        }
+    }
+
+    pub fn vm(&self) -> VM {
+        self.vm
+    }
+
+    pub fn as_raw(&self) -> jweak {
+        self.object
+    }
+
+    pub fn into_raw(self) -> jweak {
+        let object = self.object;
+        std::mem::forget(self); // Don't delete the object.
+        object
+    }
+
+    /// Returns a new [Local] reference to the object, or [None] if it's been garbage collected.
+    pub fn upgrade<'env>(&self, env: Env<'env>) -> Option<Local<'env, T>> {
+        assert_eq!(self.vm, env.vm()); // Soundness check - env *must* belong to the same VM!
+        let jnienv = env.as_raw();
+        let object = unsafe { ((**jnienv).v1_2.NewLocalRef)(jnienv, self.object) };
+        if object.is_null() {
+            None
+        } else {
+            Some(unsafe { Local::from_raw(env, object) })
+        }
+    }
+}
+
+impl<'env, T: ReferenceType> From<Local<'env, T>> for Weak<T> {
+    fn from(local: Local<'env, T>) -> Weak<T> {
+        local.as_weak()
+    }
+}
+
+impl<T: ReferenceType> From<Global<T>> for Weak<T> {
+    fn from(global: Global<T>) -> Weak<T> {
+        global.as_weak()
+    }
+}
+
+impl<T: ReferenceType> Clone for Weak<T> {
+    fn clone(&self) -> Self {
+        self.vm.with_env(|env| {
+            let env = env.as_raw();
+            let object = unsafe { ((**env).v1_2.NewWeakGlobalRef)(env, self.object) };
+            Self {
+                object,
+                vm: self.vm,
+                pd: PhantomData,
+            }
+        })
+    }
+}
+
+impl<T: ReferenceType> Drop for Weak<T> {
+    fn drop(&mut self) {
+        self.vm.with_env(|env| {
+            let env = env.as_raw();
+            unsafe { ((**env).v1_2.DeleteWeakGlobalRef)(env, self.object) }
+        });
+    }
+}
+
+#[test]
+fn raw_round_trip() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Weak<crate::IntArray>>();
+
+    // into_raw hands ownership back without running Drop, so a null VM is never touched.
+    let raw = 0x1234 as jweak;
+    let weak = unsafe { Weak::<crate::IntArray>::from_raw(VM::from_raw(std::ptr::null_mut()), raw) };
+    assert_eq!(weak.as_raw(), raw);
+    assert!(weak.vm().as_raw().is_null());
+    assert_eq!(weak.into_raw(), raw);
+}
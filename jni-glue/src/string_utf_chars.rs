@@ -0,0 +1,60 @@
+use std::borrow::Cow;
+use std::os::raw::c_char;
+use std::slice;
+
+use jni_sys::*;
+
+use crate::{decode_modified_utf8, decode_modified_utf8_lossy, Env, ModifiedUtf8Error};
+
+/// Represents an env.GetStringUTFChars + env.GetStringUTFLength query.
+/// Will automatically env.ReleaseStringUTFChars when dropped.
+///
+/// Cheaper than [StringChars](crate::StringChars) for mostly-ASCII strings, which decode without any UTF-16 round
+/// trip.
+pub struct StringUtfChars<'env> {
+    env: Env<'env>,
+    string: jstring,
+    chars: *const c_char,
+    length: jsize, // in bytes
+}
+
+impl<'env> StringUtfChars<'env> {
+    /// Construct a StringUtfChars from an Env + jstring.  Panics if the VM runs out of memory for the characters.
+    pub unsafe fn from_env_jstring(env: Env<'env>, string: jstring) -> Self {
+        debug_assert!(!string.is_null());
+
+        let chars = env.get_string_utf_chars(string);
+        assert!(!chars.is_null(), "GetStringUTFChars failed:  out of memory"); // Only sane failure here is an OOM
+        let length = env.get_string_utf_length(string);
+
+        Self {
+            env,
+            string,
+            chars,
+            length,
+        }
+    }
+
+    /// Get the [Modified UTF-8](crate::decode_modified_utf8) bytes of the string, without the null terminator.
+    pub fn bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.chars as *const u8, self.length as usize) }
+    }
+
+    /// Returns the decoded string - borrowed, unless it contained NULs or supplementary characters - or an
+    /// [Err]\([ModifiedUtf8Error]\) if it contained any unpaired surrogates.
+    pub fn to_str(&self) -> Result<Cow<'_, str>, ModifiedUtf8Error> {
+        decode_modified_utf8(self.bytes())
+    }
+
+    /// Returns the decoded string with any unpaired surrogates replaced with
+    /// [REPLACEMENT_CHARACTER](std::char::REPLACEMENT_CHARACTER)s (`'\u{FFFD}'`.)
+    pub fn to_str_lossy(&self) -> Cow<'_, str> {
+        decode_modified_utf8_lossy(self.bytes())
+    }
+}
+
+impl<'env> Drop for StringUtfChars<'env> {
+    fn drop(&mut self) {
+        unsafe { self.env.release_string_utf_chars(self.string, self.chars) };
+    }
+}
@@ -0,0 +1,166 @@
+//! A JVM for tests that need one:  loaded from `$JAVA_HOME` (or wherever the `java` on the `PATH` lives), and shared by
+//! every test, as only one VM can be created per process.  Tests are skipped if no JVM can be found.
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int, c_void};
+use std::path::PathBuf;
+use std::process::Command;
+use std::ptr::null_mut;
+use std::sync::OnceLock;
+use std::{env, fs};
+
+use jni_sys::*;
+
+use crate::{Env, VM};
+
+/// Calls `f` with the current thread attached to the test VM - or, if there's no JVM to test with, returns without
+/// calling `f` at all.
+pub(crate) fn with_env(f: impl for<'env> FnOnce(Env<'env>)) {
+    static TEST_VM: OnceLock<Option<VM>> = OnceLock::new();
+    match TEST_VM.get_or_init(|| unsafe { create_vm() }) {
+        Some(vm) => f(unsafe { Env::from_raw(vm.attach_current_thread(true)) }),
+        None => eprintln!("skipped:  no JVM found (set JAVA_HOME)"),
+    }
+}
+
+/// Defines `name` (as in `bytes`) in the system class loader, as an application class on the class path would be.
+pub(crate) unsafe fn define_app_class(env: Env, name: &str, bytes: &[u8]) -> jclass {
+    let (class_loader, get_system_class_loader) = env.require_class_static_method(
+        "java/lang/ClassLoader\0",
+        "getSystemClassLoader\0",
+        "()Ljava/lang/ClassLoader;\0",
+    );
+    let jnienv = env.as_raw();
+    let loader = ((**jnienv).v1_2.CallStaticObjectMethodA)(jnienv, class_loader, get_system_class_loader, [].as_ptr());
+    let name = CString::new(name).unwrap();
+    let class = ((**jnienv).v1_2.DefineClass)(
+        jnienv,
+        name.as_ptr(),
+        loader,
+        bytes.as_ptr() as *const jbyte,
+        bytes.len() as jsize,
+    );
+    assert!(!class.is_null(), "DefineClass({:?}) failed", name);
+    for local in [class_loader, loader] {
+        ((**jnienv).v1_2.DeleteLocalRef)(jnienv, local);
+    }
+    class
+}
+
+/// A public, code-less class file for `name`, extending `java/lang/Object` and declaring `methods` (as access flags,
+/// names, and descriptors) - which must be `abstract` or `native`, as they're given no `Code`.
+pub(crate) fn class_file(flags: u16, name: &str, interfaces: &[&str], methods: &[(u16, &str, &str)]) -> Vec<u8> {
+    let mut pool = Vec::new();
+    let mut count = 1u16;
+    let mut utf8 = |pool: &mut Vec<u8>, s: &str| {
+        pool.push(1); // CONSTANT_Utf8 (all ASCII, so modified UTF-8 is just UTF-8)
+        pool.extend_from_slice(&(s.len() as u16).to_be_bytes());
+        pool.extend_from_slice(s.as_bytes());
+        count += 1;
+        count - 1
+    };
+    let mut classes = Vec::new();
+    for class in [name, "java/lang/Object"].iter().chain(interfaces) {
+        classes.push(utf8(&mut pool, class));
+    }
+    let methods: Vec<_> = methods
+        .iter()
+        .map(|&(flags, name, descriptor)| (flags, utf8(&mut pool, name), utf8(&mut pool, descriptor)))
+        .collect();
+    let classes: Vec<u16> = classes
+        .into_iter()
+        .map(|utf8| {
+            pool.push(7); // CONSTANT_Class
+            pool.extend_from_slice(&utf8.to_be_bytes());
+            count += 1;
+            count - 1
+        })
+        .collect();
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&0xCAFE_BABE_u32.to_be_bytes());
+    out.extend_from_slice(&[0, 0, 0, 52]); // minor_version, major_version (Java 8)
+    out.extend_from_slice(&count.to_be_bytes());
+    out.extend_from_slice(&pool);
+    let mut tables = vec![flags, classes[0], classes[1], interfaces.len() as u16];
+    tables.extend_from_slice(&classes[2..]);
+    tables.extend_from_slice(&[0, methods.len() as u16]); // no fields
+    for (flags, name, descriptor) in methods {
+        tables.extend_from_slice(&[flags, name, descriptor, 0]);
+    }
+    tables.push(0); // attributes_count
+    for value in tables {
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+    out
+}
+
+#[cfg(not(unix))]
+unsafe fn create_vm() -> Option<VM> {
+    None
+}
+
+#[cfg(unix)]
+unsafe fn create_vm() -> Option<VM> {
+    extern "C" {
+        fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+        fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    }
+    const RTLD_NOW: c_int = 2;
+    type CreateJavaVM = unsafe extern "system" fn(*mut *mut JavaVM, *mut *mut c_void, *mut c_void) -> jint;
+
+    let libjvm = CString::new(libjvm_path()?.into_os_string().into_string().ok()?).ok()?;
+    let library = dlopen(libjvm.as_ptr(), RTLD_NOW);
+    if library.is_null() {
+        return None;
+    }
+    let create = dlsym(library, c"JNI_CreateJavaVM".as_ptr());
+    if create.is_null() {
+        return None;
+    }
+    let create: CreateJavaVM = std::mem::transmute(create);
+
+    let mut options = [JavaVMOption {
+        optionString: c"-Xrs".as_ptr() as *mut c_char, // Leave signals to the test harness
+        extraInfo: null_mut(),
+    }];
+    let mut args = JavaVMInitArgs {
+        version: JNI_VERSION_1_8,
+        nOptions: options.len() as jint,
+        options: options.as_mut_ptr(),
+        ignoreUnrecognized: JNI_FALSE,
+    };
+    let mut vm = null_mut();
+    let mut jnienv = null_mut();
+    if create(&mut vm, &mut jnienv, &mut args as *mut JavaVMInitArgs as *mut c_void) != JNI_OK {
+        return None;
+    }
+    Some(VM::from_raw(vm))
+}
+
+#[cfg(unix)]
+fn libjvm_path() -> Option<PathBuf> {
+    let java_home = match env::var_os("JAVA_HOME") {
+        Some(java_home) => PathBuf::from(java_home),
+        None => {
+            let output = Command::new("java")
+                .args(["-XshowSettings:properties", "-version"])
+                .output()
+                .ok()?;
+            let properties = String::from_utf8_lossy(&output.stderr).into_owned();
+            let java_home = properties
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("java.home = "))?;
+            PathBuf::from(java_home)
+        }
+    };
+    let name = if cfg!(target_os = "macos") {
+        "libjvm.dylib"
+    } else {
+        "libjvm.so"
+    };
+    ["lib/server", "jre/lib/amd64/server", "jre/lib/server"]
+        .iter()
+        .map(|dir| java_home.join(dir).join(name))
+        .find(|path| fs::metadata(path).is_ok())
+}
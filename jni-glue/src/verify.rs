@@ -0,0 +1,190 @@
+use std::fmt;
+use std::mem;
+
+use jni_sys::*;
+
+use crate::{Env, MemberKind, MissingMember};
+
+/// What [VerifyBindings] found:  the classes, methods, and fields generated bindings use that the running VM doesn't
+/// have - such as those of a newer Android API level than the device's, or of a different version of a jar.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct BindingsReport {
+    /// How many classes, methods, and fields were looked up.  Members of missing classes aren't.
+    pub checked: usize,
+    /// Classes, methods, and fields that couldn't be found.
+    pub missing: Vec<MissingMember>,
+    /// Methods and fields that were found, but as `static` where the bindings expected an instance member or vice versa.
+    pub mismatched: Vec<MissingMember>,
+}
+
+impl BindingsReport {
+    /// Everything checked was found as expected.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+impl fmt::Display for BindingsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} classes and members checked were missing, {} were mismatched",
+            self.missing.len(),
+            self.checked,
+            self.mismatched.len()
+        )?;
+        for missing in &self.missing {
+            write!(f, "\n    {}", missing)?;
+        }
+        for mismatched in &self.mismatched {
+            let (expected, found) = match mismatched.kind {
+                MemberKind::StaticMethod | MemberKind::StaticField => ("static", "non-static"),
+                _ => ("non-static", "static"),
+            };
+            match mismatched.kind {
+                MemberKind::Method | MemberKind::StaticMethod => write!(
+                    f,
+                    "\n    Expected {} method {}.{}{}, but it's {}",
+                    expected, mismatched.class, mismatched.name, mismatched.descriptor, found
+                )?,
+                _ => write!(
+                    f,
+                    "\n    Expected {} field {}.{} {}, but it's {}",
+                    expected, mismatched.class, mismatched.name, mismatched.descriptor, found
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Looks up classes and their members without aborting, collecting any that are missing into a [BindingsReport].  Used
+/// by the `verify_bindings` function jni-bindgen generates with `[codegen] verify_bindings = true`.
+pub struct VerifyBindings<'env> {
+    env: Env<'env>,
+    class: Option<(jclass, String)>, // Local reference, JNI name
+    report: BindingsReport,
+}
+
+impl<'env> VerifyBindings<'env> {
+    pub fn new(env: Env<'env>) -> Self {
+        Self {
+            env,
+            class: None,
+            report: BindingsReport::default(),
+        }
+    }
+
+    /// Looks up `class`, the members of which are checked by the following calls.  Returns `false` if it's missing.
+    ///
+    /// **unsafe**:  `class` must be a null terminated JNI class name, as for [Env::require_class].
+    pub unsafe fn class(&mut self, class: &str) -> bool {
+        self.release_class();
+        self.report.checked += 1;
+        match self.env.try_require_class(class) {
+            Ok(jclass) => {
+                self.class = Some((jclass, class.to_owned()));
+                true
+            }
+            Err(missing) => {
+                self.report.missing.push(missing);
+                false
+            }
+        }
+    }
+
+    /// **unsafe**:  `name` and `descriptor` must be null terminated, as for [Env::require_method].
+    pub unsafe fn method(&mut self, name: &str, descriptor: &str) {
+        self.member(MemberKind::Method, MemberKind::StaticMethod, name, descriptor)
+    }
+
+    /// **unsafe**:  `name` and `descriptor` must be null terminated, as for [Env::require_static_method].
+    pub unsafe fn static_method(&mut self, name: &str, descriptor: &str) {
+        self.member(MemberKind::StaticMethod, MemberKind::Method, name, descriptor)
+    }
+
+    /// **unsafe**:  `name` and `descriptor` must be null terminated, as for [Env::require_field].
+    pub unsafe fn field(&mut self, name: &str, descriptor: &str) {
+        self.member(MemberKind::Field, MemberKind::StaticField, name, descriptor)
+    }
+
+    /// **unsafe**:  `name` and `descriptor` must be null terminated, as for [Env::require_static_field].
+    pub unsafe fn static_field(&mut self, name: &str, descriptor: &str) {
+        self.member(MemberKind::StaticField, MemberKind::Field, name, descriptor)
+    }
+
+    pub fn finish(mut self) -> BindingsReport {
+        self.release_class();
+        mem::take(&mut self.report)
+    }
+
+    unsafe fn member(&mut self, kind: MemberKind, other_kind: MemberKind, name: &str, descriptor: &str) {
+        let (class, class_name) = match self.class.as_ref() {
+            Some((class, class_name)) => (*class, class_name.as_str()),
+            None => return, // Missing classes are reported once, not once per member
+        };
+        self.report.checked += 1;
+        if let Err(missing) = self
+            .env
+            .try_require_member(kind, class, Some(class_name), name, descriptor)
+        {
+            match self
+                .env
+                .try_require_member(other_kind, class, Some(class_name), name, descriptor)
+            {
+                Ok(_) => self.report.mismatched.push(missing),
+                Err(_) => self.report.missing.push(missing),
+            }
+        }
+    }
+
+    fn release_class(&mut self) {
+        if let Some((class, _)) = self.class.take() {
+            let env = self.env.as_raw();
+            unsafe { ((**env).v1_2.DeleteLocalRef)(env, class) };
+        }
+    }
+}
+
+impl Drop for VerifyBindings<'_> {
+    fn drop(&mut self) {
+        self.release_class();
+    }
+}
+
+#[test]
+fn report_display() {
+    let mut report = BindingsReport {
+        checked: 4,
+        ..Default::default()
+    };
+    assert!(report.is_ok());
+    assert_eq!(
+        report.to_string(),
+        "0 of 4 classes and members checked were missing, 0 were mismatched"
+    );
+
+    report.missing.push(MissingMember::new(
+        MemberKind::Method,
+        "com/example/Foo\0",
+        "bar\0",
+        "()V\0",
+    ));
+    report.mismatched.push(MissingMember::new(
+        MemberKind::StaticField,
+        "com/example/Foo",
+        "BAZ",
+        "I",
+    ));
+    report
+        .mismatched
+        .push(MissingMember::new(MemberKind::Method, "com/example/Foo", "qux", "(I)V"));
+    assert!(!report.is_ok());
+    assert_eq!(
+        report.to_string(),
+        "1 of 4 classes and members checked were missing, 2 were mismatched
+    Missing method com/example/Foo.bar()V
+    Expected static field com/example/Foo.BAZ I, but it's non-static
+    Expected non-static method com/example/Foo.qux(I)V, but it's static"
+    );
+}
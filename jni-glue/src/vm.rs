@@ -1,4 +1,6 @@
+use std::cell::Cell;
 use std::ptr::null_mut;
+use std::sync::atomic::{AtomicPtr, Ordering};
 
 use jni_sys::*;
 
@@ -38,11 +40,48 @@ impl VM {
     where
         F: for<'env> FnOnce(Env<'env>) -> R,
     {
+        callback(unsafe { Env::from_raw(self.attach_current_thread(false)) })
+    }
+
+    /// Registers this as the process-wide [VM] used by bindings generated with `static_env = "implicit"`.  Typically
+    /// called from `JNI_OnLoad`:
+    ///
+    /// ```rust,no_run
+    /// # use std::os::raw::c_void;
+    /// # use jni_glue::{sys::*, VM};
+    /// #[no_mangle] pub extern "system" fn JNI_OnLoad(vm: *mut JavaVM, _reserved: *mut c_void) -> jint {
+    ///     unsafe { VM::from_raw(vm) }.set_ambient();
+    ///     JNI_VERSION_1_6
+    /// }
+    /// ```
+    pub fn set_ambient(&self) {
+        AMBIENT_VM.store(self.0, Ordering::Release);
+    }
+
+    /// The [VM] registered by [VM::set_ambient], if any.
+    pub fn ambient() -> Option<VM> {
+        let vm = AMBIENT_VM.load(Ordering::Acquire);
+        if vm.is_null() {
+            None
+        } else {
+            Some(VM(vm))
+        }
+    }
+
+    /// Returns the current thread's JNIEnv, attaching the thread if necessary.  If `detach_on_exit` is set, a thread
+    /// attached here is detached again when it exits, as Android aborts the process if an attached thread exits without
+    /// detaching.  Threads that were already attached are left alone - whoever attached them is responsible for them.
+    pub(crate) fn attach_current_thread(&self, detach_on_exit: bool) -> *mut JNIEnv {
         let mut env = null_mut();
         match unsafe { ((**self.0).v1_2.GetEnv)(self.0, &mut env, JNI_VERSION_1_2) } {
-            JNI_OK => callback(unsafe { Env::from_raw(env as _) }),
+            JNI_OK => env as _,
             JNI_EDETACHED => match unsafe { ((**self.0).v1_2.AttachCurrentThread)(self.0, &mut env, null_mut()) } {
-                JNI_OK => callback(unsafe { Env::from_raw(env as _) }),
+                JNI_OK => {
+                    if detach_on_exit {
+                        DETACH_ON_EXIT.with(|detach| detach.0.set(Some(*self)));
+                    }
+                    env as _
+                }
                 unexpected => panic!("AttachCurrentThread returned unknown error: {}", unexpected),
             },
             JNI_EVERSION => panic!("GetEnv returned JNI_EVERSION"),
@@ -51,5 +90,25 @@ impl VM {
     }
 }
 
+static AMBIENT_VM: AtomicPtr<JavaVM> = AtomicPtr::new(null_mut());
+
+struct DetachOnExit(Cell<Option<VM>>);
+
+impl Drop for DetachOnExit {
+    fn drop(&mut self) {
+        if let Some(vm) = self.0.get() {
+            // Don't detach a thread the application already detached on its own.
+            let mut env = null_mut();
+            if unsafe { ((**vm.0).v1_2.GetEnv)(vm.0, &mut env, JNI_VERSION_1_2) } == JNI_OK {
+                unsafe { ((**vm.0).v1_2.DetachCurrentThread)(vm.0) };
+            }
+        }
+    }
+}
+
+thread_local! {
+    static DETACH_ON_EXIT: DetachOnExit = const { DetachOnExit(Cell::new(None)) };
+}
+
 unsafe impl Send for VM {}
 unsafe impl Sync for VM {}